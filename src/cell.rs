@@ -0,0 +1,309 @@
+//! Typed cell values and column type inference.
+//!
+//! Tables keep their original strings; a [`Cell`] is a typed view on such a string,
+//! interpreted according to the type inferred for its column.
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of values inspected when inferring a column type.
+const INFERENCE_SAMPLE: usize = 10_000;
+
+/// Type of a column, inferred from its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColType {
+    Str,
+    Int,
+    Float,
+    Bool,
+    Date,
+}
+
+impl ColType {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, ColType::Int | ColType::Float)
+    }
+}
+
+impl fmt::Display for ColType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColType::Str => "str",
+            ColType::Int => "int",
+            ColType::Float => "float",
+            ColType::Bool => "bool",
+            ColType::Date => "date",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ColType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "str" | "string" => Ok(ColType::Str),
+            "int" | "integer" => Ok(ColType::Int),
+            "float" => Ok(ColType::Float),
+            "bool" | "boolean" => Ok(ColType::Bool),
+            "date" => Ok(ColType::Date),
+            _ => Err(format!("unknown column type '{}'", name)),
+        }
+    }
+}
+
+/// A typed cell value. Values that don't match the column type stay strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell<'a> {
+    Str(&'a str),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// Seconds since the Unix epoch
+    Date(i64),
+    Null,
+}
+
+impl<'a> Cell<'a> {
+    /// Interprets a raw value as the given column type, falling back to the original string.
+    pub fn parse(value: &'a str, col_type: ColType) -> Cell<'a> {
+        if is_null(value) {
+            return Cell::Null;
+        }
+        let trimmed = value.trim();
+        let typed = match col_type {
+            ColType::Str => None,
+            ColType::Int => trimmed.parse().ok().map(Cell::Int),
+            ColType::Float => trimmed.parse().ok().map(Cell::Float),
+            ColType::Bool => parse_bool(trimmed).map(Cell::Bool),
+            ColType::Date => parse_date(trimmed).map(Cell::Date),
+        };
+        typed.unwrap_or(Cell::Str(value))
+    }
+
+    /// Numeric value of numbers, booleans and dates.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Cell::Int(i) => Some(*i as f64),
+            Cell::Float(f) => Some(*f),
+            Cell::Bool(b) => Some(*b as u8 as f64),
+            Cell::Date(d) => Some(*d as f64),
+            Cell::Str(_) | Cell::Null => None,
+        }
+    }
+
+    // Rank of variants when comparing cells of different kinds
+    fn rank(&self) -> u8 {
+        match self {
+            Cell::Bool(_) => 0,
+            Cell::Int(_) | Cell::Float(_) => 1,
+            Cell::Date(_) => 2,
+            Cell::Str(_) => 3,
+            Cell::Null => 4,
+        }
+    }
+
+    /// Total order: values of the same kind by value, different kinds by kind.
+    pub fn total_cmp(&self, other: &Cell) -> Ordering {
+        match (self, other) {
+            (Cell::Int(a), Cell::Int(b)) => a.cmp(b),
+            (Cell::Int(_) | Cell::Float(_), Cell::Int(_) | Cell::Float(_)) => {
+                self.as_f64().unwrap().total_cmp(&other.as_f64().unwrap())
+            }
+            (Cell::Bool(a), Cell::Bool(b)) => a.cmp(b),
+            (Cell::Date(a), Cell::Date(b)) => a.cmp(b),
+            (Cell::Str(a), Cell::Str(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    /// Order of two values of the same kind; None for nulls and values of different kinds.
+    pub fn compare(&self, other: &Cell) -> Option<Ordering> {
+        if self.rank() != other.rank() || *self == Cell::Null {
+            return None;
+        }
+        Some(self.total_cmp(other))
+    }
+}
+
+/// Marker shown instead of line breaks within values.
+pub const NEWLINE_MARKER: char = '↵';
+
+/// Replaces line breaks and tabs, so that a value occupies a single line on screen.
+pub fn display_text(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\n', '\r', '\t']) {
+        return Cow::Borrowed(value);
+    }
+    let text = value
+        .replace("\r\n", "\n")
+        .replace(['\n', '\r'], &NEWLINE_MARKER.to_string())
+        .replace('\t', " ");
+    Cow::Owned(text)
+}
+
+/// Splits a value into lines of at most `width` characters at its line breaks and spaces,
+/// breaking words longer than a line.
+pub fn wrap(value: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in value.replace("\r\n", "\n").split(['\n', '\r']) {
+        let mut line = String::new();
+        for word in paragraph.replace('\t', " ").split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+            let length = line.chars().count();
+            if length > 0 && length + 1 + word.len() <= width {
+                line.push(' ');
+            } else if length > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > width {
+                lines.push(word.drain(..width).collect());
+            }
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Number of characters a value occupies on screen.
+pub fn display_width(value: &str) -> usize {
+    display_text(value).chars().count()
+}
+
+/// Is the value a missing value marker?
+pub fn is_null(value: &str) -> bool {
+    matches!(value.trim(), "" | "null" | "NULL" | "NA" | "N/A")
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses `YYYY-MM-DD` optionally followed by `T` or space and `HH:MM[:SS[.fff]][Z]`.
+pub fn parse_date(value: &str) -> Option<i64> {
+    let (date, time) = match value.find(['T', ' ']) {
+        Some(i) => (&value[..i], Some(&value[i + 1..])),
+        None => (value, None),
+    };
+    let mut parts = date.split('-');
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(y), Some(m), Some(d), None) if y.len() == 4 && m.len() == 2 && d.len() == 2 => {
+            (y.parse().ok()?, m.parse().ok()?, d.parse().ok()?)
+        }
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    if let Some(time) = time {
+        let time = time.trim_end_matches('Z');
+        let time = time.split('.').next()?;
+        let mut fields = time.split(':');
+        let hours: i64 = fields.next()?.parse().ok()?;
+        let minutes: i64 = fields.next()?.parse().ok()?;
+        let secs: i64 = match fields.next() {
+            Some(s) => s.parse().ok()?,
+            None => 0,
+        };
+        if fields.next().is_some() || hours > 23 || minutes > 59 || secs > 60 {
+            return None;
+        }
+        seconds += hours * 3600 + minutes * 60 + secs;
+    }
+    Some(seconds)
+}
+
+/// Formats seconds since 1970 as `YYYY-MM-DD`, followed by ` HH:MM` unless at midnight and
+/// `:SS` unless at a full minute; the inverse of `parse_date`.
+pub fn format_date(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    let (hours, minutes, secs) = (time / 3600, time / 60 % 60, time % 60);
+    match (time, secs) {
+        (0, _) => format!("{:04}-{:02}-{:02}", year, month, day),
+        (_, 0) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            year, month, day, hours, minutes
+        ),
+        _ => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year, month, day, hours, minutes, secs
+        ),
+    }
+}
+
+/// Parses a duration like `30s`, `5m`, `1h` or `7d` into seconds.
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let unit = value.chars().last()?;
+    let factor = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    let number: i64 = value[..value.len() - 1].parse().ok()?;
+    (number > 0).then(|| number * factor)
+}
+
+// Date of the day since 1970-01-01, the inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Infers the most specific type all non-null values of a column conform to.
+pub fn infer_type<'a, I>(values: I) -> ColType
+where
+    I: Iterator<Item = &'a str>,
+{
+    let candidates = [ColType::Int, ColType::Float, ColType::Bool, ColType::Date];
+    let mut possible = [true; 4];
+    let mut seen = false;
+    for value in values.filter(|v| !is_null(v)).take(INFERENCE_SAMPLE) {
+        seen = true;
+        for (i, col_type) in candidates.iter().enumerate() {
+            if possible[i] && matches!(Cell::parse(value, *col_type), Cell::Str(_)) {
+                possible[i] = false;
+            }
+        }
+        if !possible.contains(&true) {
+            return ColType::Str;
+        }
+    }
+    if !seen {
+        return ColType::Str;
+    }
+    candidates
+        .iter()
+        .zip(possible)
+        .find(|(_, possible)| *possible)
+        .map(|(col_type, _)| *col_type)
+        .unwrap_or(ColType::Str)
+}
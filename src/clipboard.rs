@@ -0,0 +1,151 @@
+//! Copying text to the system clipboard of the terminal. Without the `clipboard` feature the
+//! text is only encoded, never sent to the terminal or a clipboard command.
+use std::env;
+#[cfg(feature = "clipboard")]
+use std::io;
+use std::io::Write;
+#[cfg(feature = "clipboard")]
+use std::process::{Command, Stdio};
+
+/// Largest base64 payload sent via OSC 52; many terminals drop longer sequences.
+pub const OSC52_LIMIT: usize = 100_000;
+
+/// Length of the pieces screen gets the sequence in, it limits pass-through strings.
+const SCREEN_CHUNK: usize = 76;
+
+/// Native clipboard commands tried for text too large for OSC 52, with the environment variable
+/// that has to be set for them to work.
+#[cfg(feature = "clipboard")]
+const NATIVE_COMMANDS: [(&str, &[&str], Option<&str>); 4] = [
+    ("pbcopy", &[], None),
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+];
+
+/// Terminal multiplexer between the viewer and the terminal emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    None,
+    Tmux,
+    Screen,
+}
+
+impl Multiplexer {
+    /// Detects tmux and screen from the environment.
+    pub fn detect() -> Self {
+        if env::var_os("TMUX").is_some() {
+            Multiplexer::Tmux
+        } else if env::var("TERM").is_ok_and(|term| term.starts_with("screen")) {
+            Multiplexer::Screen
+        } else {
+            Multiplexer::None
+        }
+    }
+}
+
+/// Copies the text to the clipboard: via OSC 52 if it is small enough for terminals to accept,
+/// otherwise via a native clipboard command if there is one. Returns a message if the text
+/// could not be copied.
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str, out: &mut dyn Write) -> Result<(), String> {
+    match terminal_sequence(text, Multiplexer::detect()) {
+        Ok(sequence) => out
+            .write_all(sequence.as_bytes())
+            .and_then(|_| out.flush())
+            .map_err(|err| format!("Cannot copy to the clipboard: {}", err)),
+        Err(_) if copy_native(text) => Ok(()),
+        Err(size) => Err(format!(
+            "Selection too large for the terminal clipboard ({} KB encoded, limit {} KB)",
+            size / 1000,
+            OSC52_LIMIT / 1000
+        )),
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str, _out: &mut dyn Write) -> Result<(), String> {
+    Err("Copying to the clipboard is not supported by this build".to_string())
+}
+
+/// Joins values with the separator, quoting those with tabs, line breaks or quotes like in TSV
+/// files, so that spreadsheets paste each value into one cell.
+pub fn join_fields<'a>(values: impl Iterator<Item = &'a str>, separator: &str) -> String {
+    values
+        .map(|value| match value.contains(['\t', '\n', '\r', '"']) {
+            true => format!("\"{}\"", value.replace('"', "\"\"")),
+            false => value.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// OSC 52 sequence for the text, passed through the multiplexer: tmux takes it in one DCS
+/// string, screen in chunks. Fails with the size of the encoded text if it exceeds the limit.
+pub fn terminal_sequence(text: &str, multiplexer: Multiplexer) -> Result<String, usize> {
+    let size = text.len().div_ceil(3) * 4;
+    if size > OSC52_LIMIT {
+        return Err(size);
+    }
+    let sequence = osc52(text);
+    Ok(match multiplexer {
+        Multiplexer::None => sequence,
+        // escape characters inside the pass-through string are doubled
+        Multiplexer::Tmux => format!("\x1BPtmux;{}\x1B\\", sequence.replace('\x1B', "\x1B\x1B")),
+        // the sequence is ASCII, so byte chunks are valid strings
+        Multiplexer::Screen => sequence
+            .as_bytes()
+            .chunks(SCREEN_CHUNK)
+            .map(|chunk| format!("\x1BP{}\x1B\\", String::from_utf8_lossy(chunk)))
+            .collect(),
+    })
+}
+
+// Pipes the text into the first available native clipboard command.
+#[cfg(feature = "clipboard")]
+fn copy_native(text: &str) -> bool {
+    NATIVE_COMMANDS
+        .iter()
+        .filter(|(_, _, var)| var.is_none_or(|var| env::var_os(var).is_some()))
+        .any(|(program, args, _)| pipe_to(program, args, text).is_ok_and(|success| success))
+}
+
+#[cfg(feature = "clipboard")]
+fn pipe_to(program: &str, args: &[&str], text: &str) -> io::Result<bool> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(child.wait()?.success())
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// OSC 52 escape sequence asking the terminal to put the text into the clipboard.
+/// Works over SSH, as long as the terminal emulator supports it.
+pub fn osc52(text: &str) -> String {
+    format!("\x1B]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,27 @@
+//! Ordering of strings: by code points or, with the `collation` feature, by the Unicode
+//! Collation Algorithm, so that accented letters sort next to their base letters and case
+//! differences only break ties.
+use std::cmp::Ordering;
+
+/// Compares two strings.
+pub type TextOrder = Box<dyn FnMut(&str, &str) -> Ordering>;
+
+/// Order by code points, or by human expectations if `collate` is set.
+#[cfg(feature = "collation")]
+pub fn text_order(collate: bool) -> TextOrder {
+    if collate {
+        let mut collator = feruca::Collator::default();
+        Box::new(move |a, b| collator.collate(a, b))
+    } else {
+        Box::new(|a, b| a.cmp(b))
+    }
+}
+
+/// Order by code points; collation requires the `collation` feature.
+#[cfg(not(feature = "collation"))]
+pub fn text_order(_collate: bool) -> TextOrder {
+    Box::new(|a, b| a.cmp(b))
+}
+
+/// Whether the crate was built with collation support.
+pub const AVAILABLE: bool = cfg!(feature = "collation");
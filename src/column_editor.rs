@@ -0,0 +1,117 @@
+//! Showing, hiding and reordering many columns at once in a list of all columns, applied to the
+//! table only when confirmed.
+use crate::renderer::{fixed_width, RenderingAction};
+use crate::state::CharCoord;
+use std::collections::HashSet;
+use termion::style;
+
+/// A full-screen list of the columns in display order.
+pub struct ColumnEditor {
+    /// Index of each column into the header and whether it is shown
+    pub entries: Vec<(usize, bool)>,
+    pub selected: usize,
+    pub offset: usize,
+}
+
+impl ColumnEditor {
+    /// Lists the columns in the given order, starting with the one under the cursor selected;
+    /// `height` is the number of entries that fit on the screen.
+    pub fn new(order: &[usize], hidden: &HashSet<usize>, current: usize, height: usize) -> Self {
+        let mut editor = ColumnEditor {
+            entries: order
+                .iter()
+                .map(|&col| (col, !hidden.contains(&col)))
+                .collect(),
+            selected: order.iter().position(|&col| col == current).unwrap_or(0),
+            offset: 0,
+        };
+        editor.scroll(height);
+        editor
+    }
+
+    /// Moves the selection down; `height` is the number of entries that fit on the screen.
+    pub fn move_down(&mut self, height: usize) -> RenderingAction {
+        if self.selected + 1 >= self.entries.len() {
+            return RenderingAction::None;
+        }
+        self.selected += 1;
+        self.scroll(height);
+        RenderingAction::Pane
+    }
+
+    pub fn move_up(&mut self, height: usize) -> RenderingAction {
+        if self.selected == 0 {
+            return RenderingAction::None;
+        }
+        self.selected -= 1;
+        self.scroll(height);
+        RenderingAction::Pane
+    }
+
+    /// Shows the selected column if it is hidden and hides it otherwise.
+    pub fn toggle(&mut self) -> RenderingAction {
+        match self.entries.get_mut(self.selected) {
+            Some((_, shown)) => {
+                *shown = !*shown;
+                RenderingAction::Pane
+            }
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Swaps the selected column with the next one (`down`) or the previous one, keeping it
+    /// selected.
+    pub fn shift(&mut self, down: bool, height: usize) -> RenderingAction {
+        let other = match down {
+            true if self.selected + 1 < self.entries.len() => self.selected + 1,
+            false if self.selected > 0 => self.selected - 1,
+            _ => return RenderingAction::None,
+        };
+        self.entries.swap(self.selected, other);
+        self.selected = other;
+        self.scroll(height);
+        RenderingAction::Pane
+    }
+
+    // Scrolls as little as possible to keep the selection on the screen.
+    fn scroll(&mut self, height: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+    }
+
+    /// Title and one line per column with its visibility, name and width, fitting into the
+    /// given size.
+    pub fn lines(&self, header: &[String], widths: &[usize], size: CharCoord) -> Vec<String> {
+        let title = "Columns: Space shows or hides, J/K move, Enter applies, Esc cancels";
+        let mut lines = vec![format!(
+            "{}{}{}",
+            style::Bold,
+            fixed_width(title, size.x),
+            style::Reset
+        )];
+        let name_width = header.iter().map(|name| name.chars().count()).max();
+        let name_width = name_width.unwrap_or(0);
+        // as many entries as rows of the table
+        let height = size.y.saturating_sub(2);
+        let stop = (self.offset + height).min(self.entries.len());
+        for (i, &(col, shown)) in self.entries.iter().enumerate().take(stop).skip(self.offset) {
+            // columns shown again get their width when applied
+            let width = match shown && widths[col] > 0 {
+                true => widths[col].to_string(),
+                false => String::new(),
+            };
+            let mark = if shown { 'x' } else { ' ' };
+            let line = format!("[{}] {:name_width$}  {:>5}", mark, header[col], width);
+            let line = fixed_width(&line, size.x);
+            if i == self.selected {
+                lines.push(format!("{}{}{}", style::Invert, line, style::Reset));
+            } else {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,140 @@
+//! Parsing of command batches, alias expansion and suggestions while typing.
+use std::collections::HashMap;
+
+/// Maximum nesting depth when aliases refer to other aliases.
+const MAX_ALIAS_DEPTH: usize = 10;
+
+/// Number of entered commands and searches remembered for suggestions.
+pub const MAX_HISTORY: usize = 100;
+
+/// Splits a batch like `:filter level=ERROR; :sort -time` into single commands,
+/// each starting with its command character (`:` is added if missing). Semicolons inside
+/// quoted values such as `delim=';'` or escaped as `\;` do not separate commands.
+pub fn split_batch(batch: &str) -> Vec<String> {
+    split_unquoted(batch)
+        .into_iter()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .map(|command| {
+            if command.starts_with([':', '/', '?']) {
+                command.to_string()
+            } else {
+                format!(":{}", command)
+            }
+        })
+        .collect()
+}
+
+// Splits at semicolons outside of quotes and not escaped as `\;`. A quote only starts a quoted
+// value at the start of a word, so that apostrophes as in `:note don't` are kept as they are.
+fn split_unquoted(batch: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut prev = ' ';
+    let mut chars = batch.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') if !prev.is_alphanumeric() => quote = Some(c),
+            (None, ';') => {
+                parts.push(&batch[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    parts.push(&batch[start..]);
+    parts
+}
+
+/// Expands aliases in a batch of commands recursively into a flat list of commands.
+pub fn expand(batch: &str, aliases: &HashMap<String, String>) -> Vec<String> {
+    let mut commands = Vec::new();
+    expand_into(batch, aliases, 0, &mut commands);
+    commands
+}
+
+fn expand_into(
+    batch: &str,
+    aliases: &HashMap<String, String>,
+    depth: usize,
+    commands: &mut Vec<String>,
+) {
+    for command in split_batch(batch) {
+        let alias = command
+            .strip_prefix(':')
+            .and_then(|c| aliases.get(c.trim()))
+            .filter(|_| depth < MAX_ALIAS_DEPTH);
+        match alias {
+            Some(expansion) => expand_into(expansion, aliases, depth + 1, commands),
+            None => commands.push(command),
+        }
+    }
+}
+
+/// Completion of a partially typed command line, returning only the missing suffix: the most
+/// recent history entry starting with the input, else a column name starting with the last
+/// word of a command.
+pub fn suggest(input: &str, history: &[String], columns: &[String]) -> Option<String> {
+    if input.chars().count() < 2 {
+        return None;
+    }
+    let entry = history
+        .iter()
+        .rev()
+        .find(|entry| entry.len() > input.len() && entry.starts_with(input));
+    if let Some(entry) = entry {
+        return Some(entry[input.len()..].to_string());
+    }
+    let word = input
+        .strip_prefix(':')?
+        .rsplit(char::is_whitespace)
+        .next()
+        .filter(|word| !word.is_empty() && input.contains(char::is_whitespace))?;
+    columns
+        .iter()
+        .find(|column| column.len() > word.len() && column.starts_with(word))
+        .map(|column| column[word.len()..].to_string())
+}
+
+/// Pattern of a search command, `/pattern` forward or `?pattern` backward, and whether it
+/// searches backward.
+pub fn search_pattern(command: &str) -> Option<(&str, bool)> {
+    match command.strip_prefix('/') {
+        Some(pattern) => Some((pattern, false)),
+        None => command.strip_prefix('?').map(|pattern| (pattern, true)),
+    }
+}
+
+/// Resolves backslash escapes in a search pattern or quoted value: `\t` is a tab and any other
+/// character after a backslash stands for itself, e.g. `\;`, `\'` or `\\`.
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => unescaped.push('\t'),
+                Some(escaped) => unescaped.push(escaped),
+                // a trailing backslash stands for itself
+                None => unescaped.push(c),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Adds an entered command line to the history, moving repeated ones to the end.
+pub fn remember(history: &mut Vec<String>, input: &str) {
+    history.retain(|entry| entry != input);
+    history.push(input.to_string());
+    if history.len() > MAX_HISTORY {
+        history.remove(0);
+    }
+}
@@ -0,0 +1,295 @@
+//! User configuration loaded from a TOML file.
+use crate::state::PADDING;
+#[cfg(feature = "config")]
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::error::Error;
+#[cfg(feature = "config")]
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the environment variables overriding options of the config file.
+pub const ENV_PREFIX: &str = "TABLE_VIEWER_";
+
+/// Rows read at most unless configured otherwise.
+pub const DEFAULT_MAX_ROWS: usize = 2_000_000;
+
+/// Settings read from `~/.config/table-viewer/config.toml`.
+#[derive(Debug)]
+#[cfg_attr(feature = "config", derive(Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct Config {
+    /// User-defined commands: alias name mapped to a `;`-separated command batch.
+    pub aliases: HashMap<String, String>,
+    /// Rhai script defining custom commands and key handlers (requires `scripting` feature).
+    pub script: Option<PathBuf>,
+    /// Grid of tables shown with `--dashboard`.
+    pub dashboard: Option<DashboardConfig>,
+    /// Keep the previous version of files overwritten by `:save` as `<file>.bak`.
+    pub backup: bool,
+    /// Settings for recurring kinds of files by profile name, e.g. `[profile.logs]`.
+    pub profile: BTreeMap<String, Profile>,
+    /// Field delimiter and quote character of files for which neither the command line nor a
+    /// profile sets one, instead of detecting them.
+    pub delimiter: Option<char>,
+    pub quote: Option<char>,
+    /// Use colors, e.g. for the ellipsis of truncated values.
+    pub color: bool,
+    /// Colors for a light or dark terminal background: `light`, `dark` or `auto` to detect it.
+    pub theme: Option<String>,
+    /// Lines under the header and after the frozen columns: `box`, `ascii` or `none`.
+    pub rules: Option<String>,
+    /// Spaces after the content of each column.
+    pub padding: usize,
+    /// Commands run by keys instead of their built-in action, by key name as for scripts.
+    pub keys: HashMap<String, String>,
+    /// Order the rows are sorted in on start, as for `:sort`, e.g. `-time, name`; columns a
+    /// file does not have are ignored.
+    pub sort: Option<String>,
+    /// Rows read at most, so that surprisingly large input does not exhaust the memory; 0 reads
+    /// all rows.
+    pub max_rows: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            aliases: HashMap::new(),
+            script: None,
+            dashboard: None,
+            backup: false,
+            profile: BTreeMap::new(),
+            delimiter: None,
+            quote: None,
+            color: true,
+            theme: None,
+            rules: None,
+            padding: PADDING,
+            keys: HashMap::new(),
+            sort: None,
+            max_rows: DEFAULT_MAX_ROWS,
+        }
+    }
+}
+
+/// Settings applied to files selected with `--profile` or matching one of the profile's file
+/// name patterns.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "config", derive(Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct Profile {
+    /// File name patterns with `*` and `?` wildcards, e.g. `access-*.log`
+    pub files: Vec<String>,
+    pub delimiter: Option<char>,
+    pub quote: Option<char>,
+    /// Column types overriding the inferred ones by column name, e.g. `zip = "str"`
+    pub types: HashMap<String, String>,
+    /// Columns hidden on start
+    pub hidden: Vec<String>,
+    /// Expressions like `status >= 500`; rows for which one holds are shown in bold
+    pub highlight: Vec<String>,
+    /// Commands run by keys instead of their built-in action, by key name as for scripts
+    pub keys: HashMap<String, String>,
+    /// Order the rows are sorted in on start instead of the one of the top level
+    pub sort: Option<String>,
+}
+
+/// Layout and panes of a dashboard.
+#[derive(Debug)]
+#[cfg_attr(feature = "config", derive(Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct DashboardConfig {
+    /// Number of panes side by side
+    pub columns: usize,
+    #[cfg_attr(feature = "config", serde(rename = "pane"))]
+    pub panes: Vec<PaneConfig>,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        DashboardConfig {
+            columns: 2,
+            panes: Vec::new(),
+        }
+    }
+}
+
+/// A dashboard pane showing a file or the output of a shell command.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "config", derive(Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct PaneConfig {
+    /// Title, defaults to the file or command
+    pub title: Option<String>,
+    pub file: Option<PathBuf>,
+    /// Shell command printing CSV
+    pub command: Option<String>,
+    /// Seconds between reloads, 0 to load only once
+    pub refresh: u64,
+}
+
+impl PaneConfig {
+    pub fn title(&self) -> String {
+        match (&self.title, &self.file, &self.command) {
+            (Some(title), _, _) => title.clone(),
+            (None, Some(file), _) => file.display().to_string(),
+            (None, None, Some(command)) => command.clone(),
+            (None, None, None) => String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from the given path or, if none is given, from the default location.
+    /// A missing default config file is not an error.
+    /// Options set by `TABLE_VIEWER_*` environment variables override those of the file.
+    pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+        let mut config = match path {
+            Some(path) => Config::from_file(path)?,
+            None => match default_path() {
+                Some(path) if path.exists() => Config::from_file(&path)?,
+                _ => Config::default(),
+            },
+        };
+        config.apply_env(env::vars())?;
+        Ok(config)
+    }
+
+    /// Overrides options with environment variables named like the option with the prefix
+    /// `TABLE_VIEWER_`, e.g. `TABLE_VIEWER_DELIMITER=';'`. The delimiter and quote character
+    /// also replace those of all profiles.
+    pub fn apply_env<I>(&mut self, vars: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (name, value) in vars {
+            let option = match name.strip_prefix(ENV_PREFIX) {
+                Some(option) => option,
+                None => continue,
+            };
+            match option {
+                "DELIMITER" => {
+                    let delimiter = env_char(&name, &value)?;
+                    self.delimiter = Some(delimiter);
+                    for profile in self.profile.values_mut() {
+                        profile.delimiter = Some(delimiter);
+                    }
+                }
+                "QUOTE" => {
+                    let quote = env_char(&name, &value)?;
+                    self.quote = Some(quote);
+                    for profile in self.profile.values_mut() {
+                        profile.quote = Some(quote);
+                    }
+                }
+                "BACKUP" => self.backup = env_bool(&name, &value)?,
+                "COLOR" => self.color = env_bool(&name, &value)?,
+                "SCRIPT" => self.script = Some(PathBuf::from(value)),
+                "MAX_ROWS" => {
+                    self.max_rows = value
+                        .parse()
+                        .map_err(|_| format!("{} must be a number", name))?
+                }
+                _ => return Err(format!("Unknown environment variable {}", name)),
+            }
+        }
+        Ok(())
+    }
+
+    /// The profile of the given name or else the first one, by name, with a pattern matching the
+    /// file's name.
+    pub fn profile(
+        &self,
+        name: Option<&str>,
+        file: Option<&Path>,
+    ) -> Result<Option<&Profile>, String> {
+        if let Some(name) = name {
+            return match self.profile.get(name) {
+                Some(profile) => Ok(Some(profile)),
+                None => Err(format!("No profile named '{}' in the config", name)),
+            };
+        }
+        let file_name = match file.and_then(Path::file_name) {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => return Ok(None),
+        };
+        Ok(self.profile.values().find(|profile| {
+            profile
+                .files
+                .iter()
+                .any(|pattern| glob_match(pattern, &file_name))
+        }))
+    }
+
+    #[cfg(feature = "config")]
+    pub fn from_file(path: &Path) -> Result<Config, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    #[cfg(not(feature = "config"))]
+    pub fn from_file(_path: &Path) -> Result<Config, Box<dyn Error>> {
+        Err("compiled without config file support".into())
+    }
+}
+
+// A single character; `\t` and `tab` stand for the tab character.
+fn env_char(name: &str, value: &str) -> Result<char, String> {
+    if value == "\\t" || value == "tab" {
+        return Ok('\t');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("{} must be a single character", name)),
+    }
+}
+
+fn env_bool(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("{} must be true or false", name)),
+    }
+}
+
+/// Does the text match the pattern, in which `*` stands for any text and `?` for any character?
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // position after the last `*` in the pattern and the text it was tried against
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            // let the last `*` take one more character
+            _ => match star {
+                Some((after, tried)) => {
+                    star = Some((after, tried + 1));
+                    p = after;
+                    t = tried + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Location of the config file, honoring `XDG_CONFIG_HOME`.
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("table-viewer").join("config.toml"))
+}
@@ -1,3 +1,5 @@
+pub use crate::registry::Table;
+use crate::registry::{DataSource, LoadOptions};
 use std::error::Error;
 use std::fs::File;
 use std::path::Path;
@@ -5,29 +7,181 @@ use std::path::Path;
 use std::io::{self, BufReader, Read};
 use std::iter::once;
 
-pub fn read_csv_from_file(
-    path: &Path,
-    delimiter: u8,
-    quote: u8,
-) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
+/// Default delimiter of a file: tab for `.tsv` and `.tab` files, comma otherwise.
+pub fn default_delimiter(path: &Path) -> u8 {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tsv" | "tab") => b'\t',
+        _ => b',',
+    }
+}
+
+/// Number of bytes at the start of a file used to detect its format.
+const SNIFF_SIZE: usize = 8 * 1024;
+
+/// Delimiters recognized by `sniff`, the default first.
+const DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+/// Detects delimiter and quote character from the start of a file: the delimiter is the one
+/// occurring the same number of times in each line, the most often if several do; the quote
+/// character is `'` if fields start with it more often than with `"`. None if no delimiter occurs
+/// at all, e.g. for a single column.
+pub fn sniff(sample: &[u8]) -> Option<(u8, u8)> {
+    // the last line may be cut off
+    let sample = match sample.iter().rposition(|&b| b == b'\n') {
+        Some(end) if end + 1 < sample.len() => &sample[..end],
+        _ => sample,
+    };
+    let lines = split_lines(sample);
+    let (_, _, delimiter) = DELIMITERS
+        .iter()
+        .map(|&delimiter| {
+            let counts: Vec<usize> = lines
+                .iter()
+                .map(|line| count_unquoted(line, delimiter))
+                .collect();
+            let consistent = counts.windows(2).all(|pair| pair[0] == pair[1]);
+            let fewest = counts.iter().copied().min().unwrap_or_default();
+            (consistent, fewest, delimiter)
+        })
+        .filter(|&(_, fewest, _)| fewest > 0)
+        // first of equally good delimiters
+        .rev()
+        .max_by_key(|&(consistent, fewest, _)| (consistent, fewest))?;
+    let field_starts = |quote: u8| {
+        lines
+            .iter()
+            .flat_map(|line| once(&line[..]).chain(line.split(|&b| b == delimiter).skip(1)))
+            .filter(|field| field.first() == Some(&quote))
+            .count()
+    };
+    let quote = match field_starts(b'\'') > field_starts(b'"') {
+        true => b'\'',
+        false => b'"',
+    };
+    Some((delimiter, quote))
+}
+
+// Splits at line breaks outside of double quotes, ignoring empty lines.
+fn split_lines(sample: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let (mut start, mut quoted) = (0, false);
+    for (i, &b) in sample.iter().enumerate() {
+        match b {
+            b'"' => quoted = !quoted,
+            b'\n' if !quoted => {
+                lines.push(&sample[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    lines.push(&sample[start..]);
+    lines
+        .into_iter()
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn count_unquoted(line: &[u8], delimiter: u8) -> usize {
+    let mut quoted = false;
+    line.iter()
+        .filter(|&&b| {
+            if b == b'"' {
+                quoted = !quoted;
+            }
+            b == delimiter && !quoted
+        })
+        .count()
+}
+
+/// Delimiter and quote character of a file: detected from its content, else the default
+/// delimiter for its extension and `"`.
+pub fn detect_format(path: &Path) -> (u8, u8) {
+    let mut sample = Vec::with_capacity(SNIFF_SIZE);
+    File::open(path)
+        .and_then(|file| file.take(SNIFF_SIZE as u64).read_to_end(&mut sample))
+        .ok()
+        .and_then(|_| sniff(&sample))
+        .unwrap_or((default_delimiter(path), b'"'))
+}
+
+pub fn read_csv_from_file(path: &Path, delimiter: u8, quote: u8) -> Result<Table, Box<dyn Error>> {
     let f = File::open(path)?;
     let reader = BufReader::new(f);
     read_csv(reader, delimiter, quote)
 }
 
-pub fn read_csv_from_stdin(
-    delimiter: u8,
-    quote: u8,
-) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
+pub fn read_csv_from_stdin(delimiter: u8, quote: u8) -> Result<Table, Box<dyn Error>> {
     read_csv(io::stdin(), delimiter, quote)
 }
 
-fn read_csv<R: Read>(
+/// Delimiter-separated values with a default delimiter.
+pub struct CsvSource {
+    name: &'static str,
+    delimiter: u8,
+    extensions: &'static [&'static str],
+}
+
+impl CsvSource {
+    pub fn new(name: &'static str, delimiter: u8, extensions: &'static [&'static str]) -> Self {
+        CsvSource {
+            name,
+            delimiter,
+            extensions,
+        }
+    }
+}
+
+impl DataSource for CsvSource {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+
+    /// Detects delimiter and quote character unless the delimiter is given.
+    fn load(&self, reader: Box<dyn Read>, options: &LoadOptions) -> Result<Table, Box<dyn Error>> {
+        let limit = options.max_rows.unwrap_or(usize::MAX);
+        if let Some(delimiter) = options.delimiter {
+            return read_csv_head(reader, delimiter, options.quote.unwrap_or(b'"'), limit);
+        }
+        let mut reader = reader;
+        let mut sample = Vec::with_capacity(SNIFF_SIZE);
+        reader
+            .by_ref()
+            .take(SNIFF_SIZE as u64)
+            .read_to_end(&mut sample)?;
+        let (delimiter, quote) = sniff(&sample).unwrap_or((self.delimiter, b'"'));
+        read_csv_head(
+            io::Cursor::new(sample).chain(reader),
+            delimiter,
+            options.quote.unwrap_or(quote),
+            limit,
+        )
+    }
+
+    fn delimited(&self, options: &LoadOptions) -> Option<(u8, u8)> {
+        Some((
+            options.delimiter.unwrap_or(self.delimiter),
+            options.quote.unwrap_or(b'"'),
+        ))
+    }
+}
+
+pub fn read_csv<R: Read>(reader: R, delimiter: u8, quote: u8) -> Result<Table, Box<dyn Error>> {
+    read_csv_head(reader, delimiter, quote, usize::MAX)
+}
+
+/// Reads the header and at most `limit` rows, e.g. for a preview.
+pub fn read_csv_head<R: Read>(
     reader: R,
     delimiter: u8,
     quote: u8,
-) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn Error>> {
-    // TODO: add row numbers
+    limit: usize,
+) -> Result<Table, Box<dyn Error>> {
     let mut csv_reader = csv::ReaderBuilder::new()
         .delimiter(delimiter)
         .quote(quote)
@@ -36,7 +190,7 @@ fn read_csv<R: Read>(
         .chain(csv_reader.headers()?.iter().map(|value| value.to_string()))
         .collect();
     let mut rows: Vec<Vec<String>> = Vec::new();
-    for (i, result) in csv_reader.records().enumerate() {
+    for (i, result) in csv_reader.records().take(limit).enumerate() {
         let record = result?;
         let row: Vec<String> = once(format!("{}", i + 1))
             .chain(record.iter().map(|value| value.to_string()))
@@ -0,0 +1,192 @@
+//! Grid of tables loaded from files or shell commands and reloaded periodically.
+use crate::config::{DashboardConfig, PaneConfig};
+use crate::registry::Table;
+use crate::renderer::{fixed_width, TerminalTableRenderer};
+use crate::state::{CharCoord, TableState};
+use crate::termion::input::TermRead;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{stdout, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::event::Key;
+use termion::raw::IntoRawMode;
+use termion::{screen, style};
+
+/// Loads the table of a pane.
+pub type Loader<'a> = dyn Fn(&PaneConfig) -> Result<Table, Box<dyn Error>> + 'a;
+
+/// Position and size of a pane in characters, 1-based like terminal coordinates.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+struct DashboardPane {
+    config: PaneConfig,
+    state: Option<TableState>,
+    error: Option<String>,
+    loaded: Option<Instant>,
+}
+
+pub struct Dashboard {
+    panes: Vec<DashboardPane>,
+    columns: usize,
+    size: CharCoord,
+}
+
+enum Event {
+    Key(Key),
+    Tick,
+}
+
+impl Dashboard {
+    pub fn new(config: &DashboardConfig, size: CharCoord) -> Self {
+        let panes = config
+            .panes
+            .iter()
+            .map(|config| DashboardPane {
+                config: config.clone(),
+                state: None,
+                error: None,
+                loaded: None,
+            })
+            .collect();
+        Dashboard {
+            panes,
+            columns: config.columns.max(1),
+            size,
+        }
+    }
+
+    /// Splits the terminal into a grid with one cell per pane, separated by a space.
+    pub fn layout(&self) -> Vec<Rect> {
+        let columns = self.columns.min(self.panes.len()).max(1);
+        let rows = self.panes.len().div_ceil(columns).max(1);
+        let width = self.size.x.saturating_sub(columns - 1) / columns;
+        let height = self.size.y / rows;
+        (0..self.panes.len())
+            .map(|i| Rect {
+                x: (i % columns) * (width + 1) + 1,
+                y: (i / columns) * height + 1,
+                width,
+                height,
+            })
+            .collect()
+    }
+
+    /// Loads panes that have never been loaded or whose refresh interval has passed.
+    /// Returns whether any pane was reloaded.
+    pub fn refresh(&mut self, now: Instant, load: &Loader) -> bool {
+        let rects = self.layout();
+        let mut changed = false;
+        for (pane, rect) in self.panes.iter_mut().zip(rects) {
+            let due = match pane.loaded {
+                None => true,
+                Some(_) if pane.config.refresh == 0 => false,
+                Some(loaded) => now >= loaded + Duration::from_secs(pane.config.refresh),
+            };
+            if !due {
+                continue;
+            }
+            pane.loaded = Some(now);
+            changed = true;
+            match load(&pane.config) {
+                Ok((header, rows)) => {
+                    // one line for the title
+                    let size = CharCoord {
+                        x: rect.width,
+                        y: rect.height.saturating_sub(1).max(2),
+                    };
+                    pane.state = Some(TableState::new(header, rows, size));
+                    pane.error = None;
+                }
+                Err(err) => pane.error = Some(err.to_string()),
+            }
+        }
+        changed
+    }
+
+    /// Reloads all panes on the next refresh.
+    pub fn invalidate(&mut self) {
+        for pane in &mut self.panes {
+            pane.loaded = None;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let renderer = TerminalTableRenderer {};
+        let mut output = format!("{}{}", termion::clear::All, termion::cursor::Hide);
+        for (pane, rect) in self.panes.iter().zip(self.layout()) {
+            let title = format!(
+                "{}{}{}",
+                style::Invert,
+                fixed_width(&pane.config.title(), rect.width),
+                style::Reset
+            );
+            let lines = match (&pane.error, &pane.state) {
+                (Some(error), _) => vec![fixed_width(&format!("Error: {}", error), rect.width)],
+                (None, Some(state)) => renderer.frame_lines(state),
+                (None, None) => Vec::new(),
+            };
+            let lines = std::iter::once(title).chain(lines).take(rect.height);
+            for (i, line) in lines.enumerate() {
+                output.push_str(&format!(
+                    "{}{}",
+                    termion::cursor::Goto(rect.x as u16, (rect.y + i) as u16),
+                    line
+                ));
+            }
+        }
+        output
+    }
+
+    /// Shows the dashboard until `q` is pressed; `r` reloads all panes.
+    pub fn run(&mut self, load: &Loader) -> Result<(), Box<dyn Error>> {
+        let mut stdout = stdout().into_raw_mode()?;
+        let stdin = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let (sender, receiver) = mpsc::channel();
+        let key_sender = sender.clone();
+        thread::spawn(move || {
+            for key in stdin.keys().map_while(Result::ok) {
+                if key_sender.send(Event::Key(key)).is_err() {
+                    break;
+                }
+            }
+        });
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(1));
+            if sender.send(Event::Tick).is_err() {
+                break;
+            }
+        });
+        self.refresh(Instant::now(), load);
+        // on the alternate screen, so that the shell's contents come back on exit
+        write!(stdout, "{}{}", screen::ToAlternateScreen, self.render())?;
+        stdout.flush()?;
+        for event in receiver.iter() {
+            match event {
+                Event::Key(Key::Char('q') | Key::Ctrl('c') | Key::Ctrl('x')) => break,
+                Event::Key(Key::Char('r')) => self.invalidate(),
+                _ => {}
+            }
+            if self.refresh(Instant::now(), load) {
+                write!(stdout, "{}", self.render())?;
+                stdout.flush()?;
+            }
+        }
+        write!(
+            stdout,
+            "{}{}{}{}",
+            termion::clear::All,
+            termion::cursor::Goto(1, 1),
+            termion::cursor::Show,
+            screen::ToMainScreen
+        )?;
+        Ok(())
+    }
+}
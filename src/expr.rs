@@ -0,0 +1,617 @@
+//! A small expression language evaluated on table rows, e.g. `lower(name)`,
+//! `len(description) > 10` or `price * count >= 100 and flag`.
+//!
+//! Identifiers refer to columns; names that are not valid identifiers can be written in
+//! backticks (`` `unit price` ``). Column values are typed according to their column.
+//!
+//! Table expressions, e.g. `rows > 0 and no_nulls(id)`, are evaluated once on the whole
+//! table: they refer to `rows` and `columns` and aggregate row expressions over all rows.
+use crate::cell::{is_null, parse_date, Cell, ColType};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Value of an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Num(f64),
+    /// Seconds since the Unix epoch
+    Date(i64),
+    Str(String),
+}
+
+impl Value {
+    fn from_cell(cell: Cell) -> Value {
+        match cell {
+            Cell::Null => Value::Null,
+            Cell::Bool(b) => Value::Bool(b),
+            Cell::Int(i) => Value::Num(i as f64),
+            Cell::Float(f) => Value::Num(f),
+            Cell::Date(d) => Value::Date(d),
+            Cell::Str(s) => Value::Str(s.to_string()),
+        }
+    }
+
+    /// Truthiness: null, false, zero and empty strings are false.
+    pub fn is_true(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Num(n) => *n != 0.0,
+            Value::Date(_) => true,
+            Value::Str(s) => !s.is_empty(),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Bool(_) => 0,
+            Value::Num(_) => 1,
+            Value::Date(_) => 2,
+            Value::Str(_) => 3,
+            Value::Null => 4,
+        }
+    }
+
+    /// Total order for sorting: values of the same kind by value, else by kind; nulls last.
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Num(a), Value::Num(b)) => a.total_cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Str(a), Value::Str(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    // Converts strings to the kind of the other value if possible, so that `price > "10"`
+    // and `day < "2022-01-01"` compare by value.
+    fn coerce(self, other: &Value) -> Value {
+        match (&self, other) {
+            (Value::Str(s), Value::Num(_)) => s.trim().parse().map(Value::Num).unwrap_or(self),
+            (Value::Str(s), Value::Date(_)) => {
+                parse_date(s.trim()).map(Value::Date).unwrap_or(self)
+            }
+            _ => self,
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Str(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Date(d) => write!(f, "{}", d),
+            Value::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Binary operators; `Contains` and `NotContains` are `~` and `!~`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    NotContains,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// A parsed expression with column names resolved to indices.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Column(usize),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    /// Only in table expressions; the argument is a row expression
+    Aggregate(Aggregate, Option<Box<Expr>>),
+}
+
+/// Facts about the whole table, usable in table expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Rows,
+    Columns,
+    /// Number of rows where the argument is true
+    Count,
+    All,
+    Any,
+    /// Number of rows where the argument is null or empty
+    Nulls,
+    NoNulls,
+    /// Number of distinct non-null values
+    Distinct,
+    /// No non-null value occurs twice
+    Unique,
+}
+
+const AGGREGATES: [(&str, Aggregate); 7] = [
+    ("count", Aggregate::Count),
+    ("all", Aggregate::All),
+    ("any", Aggregate::Any),
+    ("nulls", Aggregate::Nulls),
+    ("no_nulls", Aggregate::NoNulls),
+    ("distinct", Aggregate::Distinct),
+    ("unique", Aggregate::Unique),
+];
+
+/// Built-in functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Lower,
+    Upper,
+    Trim,
+    Len,
+    /// First number within a string, e.g. 12 in "v12-beta"
+    Num,
+    Abs,
+    Empty,
+    Contains,
+    StartsWith,
+}
+
+const FUNCTIONS: [(&str, Function, usize); 9] = [
+    ("lower", Function::Lower, 1),
+    ("upper", Function::Upper, 1),
+    ("trim", Function::Trim, 1),
+    ("len", Function::Len, 1),
+    ("num", Function::Num, 1),
+    ("abs", Function::Abs, 1),
+    ("empty", Function::Empty, 1),
+    ("contains", Function::Contains, 2),
+    ("starts_with", Function::StartsWith, 2),
+];
+
+impl Expr {
+    /// Parses an expression, resolving column names against the header.
+    pub fn parse(text: &str, header: &[String]) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            header,
+            table: false,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+        }
+    }
+
+    /// Parses a table expression: bare identifiers are `rows` and `columns`, column names
+    /// only occur within aggregates such as `nulls(id)` or `all(price >= 0)`.
+    pub fn parse_table(text: &str, header: &[String]) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            header,
+            table: true,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+        }
+    }
+
+    /// Evaluates the expression on a row; `types` are the column types.
+    pub fn eval(&self, row: &[String], types: &[ColType]) -> Value {
+        self.eval_with(&|expr| match expr {
+            Expr::Column(col) => Value::from_cell(Cell::parse(&row[*col], types[*col])),
+            _ => Value::Null,
+        })
+    }
+
+    /// Evaluates a table expression on all rows; `columns` is the number of columns.
+    pub fn eval_table(&self, rows: &[Vec<String>], types: &[ColType], columns: usize) -> Value {
+        self.eval_with(&|expr| match expr {
+            Expr::Aggregate(Aggregate::Rows, _) => Value::Num(rows.len() as f64),
+            Expr::Aggregate(Aggregate::Columns, _) => Value::Num(columns as f64),
+            Expr::Aggregate(aggregate, Some(arg)) => {
+                let values = rows.iter().map(|row| arg.eval(row, types));
+                self::aggregate(*aggregate, values)
+            }
+            _ => Value::Null,
+        })
+    }
+
+    // Evaluates operators and functions, leaving columns and aggregates to `leaf`.
+    fn eval_with(&self, leaf: &dyn Fn(&Expr) -> Value) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Column(_) | Expr::Aggregate(..) => leaf(self),
+            Expr::Not(expr) => Value::Bool(!expr.eval_with(leaf).is_true()),
+            Expr::Neg(expr) => match expr.eval_with(leaf) {
+                Value::Num(n) => Value::Num(-n),
+                _ => Value::Null,
+            },
+            Expr::Binary(op, left, right) => {
+                let left = left.eval_with(leaf);
+                // short-circuit logical operators
+                match (op, left.is_true()) {
+                    (BinOp::Or, true) => return Value::Bool(true),
+                    (BinOp::And, false) => return Value::Bool(false),
+                    _ => {}
+                }
+                binary(*op, left, right.eval_with(leaf))
+            }
+            Expr::Call(function, args) => {
+                let args: Vec<Value> = args.iter().map(|arg| arg.eval_with(leaf)).collect();
+                call(*function, args)
+            }
+        }
+    }
+
+    /// Does the row satisfy the expression?
+    pub fn matches(&self, row: &[String], types: &[ColType]) -> bool {
+        self.eval(row, types).is_true()
+    }
+}
+
+fn binary(op: BinOp, left: Value, right: Value) -> Value {
+    match op {
+        BinOp::Or | BinOp::And => Value::Bool(right.is_true()),
+        BinOp::Contains => Value::Bool(left.text().contains(&right.text())),
+        BinOp::NotContains => Value::Bool(!left.text().contains(&right.text())),
+        BinOp::Add => match (left, right) {
+            (Value::Num(a), Value::Num(b)) => Value::Num(a + b),
+            (Value::Null, _) | (_, Value::Null) => Value::Null,
+            (a, b) => Value::Str(format!("{}{}", a.text(), b.text())),
+        },
+        BinOp::Sub | BinOp::Mul | BinOp::Div => match (left, right) {
+            (Value::Num(a), Value::Num(b)) => match op {
+                BinOp::Sub => Value::Num(a - b),
+                BinOp::Mul => Value::Num(a * b),
+                _ if b == 0.0 => Value::Null,
+                _ => Value::Num(a / b),
+            },
+            _ => Value::Null,
+        },
+        _ => {
+            let left = left.coerce(&right);
+            let right = right.coerce(&left);
+            let ordering = left.total_cmp(&right);
+            let comparable = left.rank() == right.rank() && left != Value::Null;
+            Value::Bool(match op {
+                BinOp::Eq => ordering == Ordering::Equal,
+                BinOp::Ne => ordering != Ordering::Equal,
+                BinOp::Lt => comparable && ordering == Ordering::Less,
+                BinOp::Le => comparable && ordering != Ordering::Greater,
+                BinOp::Gt => comparable && ordering == Ordering::Greater,
+                _ => comparable && ordering != Ordering::Less,
+            })
+        }
+    }
+}
+
+fn call(function: Function, args: Vec<Value>) -> Value {
+    let arg = &args[0];
+    match function {
+        Function::Lower => Value::Str(arg.text().to_lowercase()),
+        Function::Upper => Value::Str(arg.text().to_uppercase()),
+        Function::Trim => Value::Str(arg.text().trim().to_string()),
+        Function::Len => Value::Num(arg.text().chars().count() as f64),
+        Function::Num => match arg {
+            Value::Num(n) => Value::Num(*n),
+            other => extract_number(&other.text()).map_or(Value::Null, Value::Num),
+        },
+        Function::Abs => match arg {
+            Value::Num(n) => Value::Num(n.abs()),
+            _ => Value::Null,
+        },
+        Function::Empty => Value::Bool(matches!(arg, Value::Null) || arg.text().trim().is_empty()),
+        Function::Contains => Value::Bool(arg.text().contains(&args[1].text())),
+        Function::StartsWith => Value::Bool(arg.text().starts_with(&args[1].text())),
+    }
+}
+
+fn aggregate(aggregate: Aggregate, values: impl Iterator<Item = Value>) -> Value {
+    let null = |value: &Value| matches!(value, Value::Null) || is_null(&value.text());
+    let mut values = values;
+    match aggregate {
+        Aggregate::Count => Value::Num(values.filter(Value::is_true).count() as f64),
+        Aggregate::All => Value::Bool(values.all(|value| value.is_true())),
+        Aggregate::Any => Value::Bool(values.any(|value| value.is_true())),
+        Aggregate::Nulls => Value::Num(values.filter(null).count() as f64),
+        Aggregate::NoNulls => Value::Bool(!values.any(|value| null(&value))),
+        Aggregate::Distinct | Aggregate::Unique => {
+            let mut seen = HashSet::new();
+            let mut duplicate = false;
+            for value in values.filter(|value| !null(value)) {
+                duplicate |= !seen.insert(value.text());
+            }
+            match aggregate {
+                Aggregate::Distinct => Value::Num(seen.len() as f64),
+                _ => Value::Bool(!duplicate),
+            }
+        }
+        Aggregate::Rows | Aggregate::Columns => Value::Null,
+    }
+}
+
+// First (optionally negative, decimal) number within a text.
+fn extract_number(text: &str) -> Option<f64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let start = match text[..start].ends_with('-') {
+        true => start - 1,
+        false => start,
+    };
+    let end = text[start + 1..]
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map_or(text.len(), |i| i + start + 1);
+    text[start..end].trim_end_matches('.').parse().ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    /// Column name in backticks
+    Quoted(String),
+    Op(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{}", n),
+            Token::Str(s) => write!(f, "'{}'", s),
+            Token::Ident(s) => write!(f, "{}", s),
+            Token::Quoted(s) => write!(f, "`{}`", s),
+            Token::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+/// Operators and punctuation, longest first.
+const OPERATORS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "!~", "&&", "||", "=", "<", ">", "~", "!", "+", "-", "*", "/", "(",
+    ")", ",",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| format!("Invalid number '{}'", &rest[..len]))?;
+            (Token::Num(number), len)
+        } else if c == '\'' || c == '"' || c == '`' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| format!("Missing closing {}", c))?;
+            let content = rest[1..end + 1].to_string();
+            let token = match c {
+                '`' => Token::Quoted(content),
+                _ => Token::Str(content),
+            };
+            (token, end + 2)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (Token::Ident(rest[..len].to_string()), len)
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| format!("Unexpected '{}'", c))?;
+            (Token::Op(op), op.len())
+        };
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    header: &'a [String],
+    /// Parsing a table expression outside of aggregates
+    table: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    // Consumes the next token if it is one of the given operators or keywords.
+    fn accept(&mut self, ops: &[&str]) -> Option<&'static str> {
+        let op = match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => *op,
+            Some(Token::Ident(word)) => match (word.as_str(), ops) {
+                ("or", _) if ops.contains(&"||") => "||",
+                ("and", _) if ops.contains(&"&&") => "&&",
+                ("not", _) if ops.contains(&"!") => "!",
+                _ => return None,
+            },
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(op)
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Op(found)) if found == op => Ok(()),
+            Some(token) => Err(format!("Expected '{}' instead of '{}'", op, token)),
+            None => Err(format!("Expected '{}'", op)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.accept(&["||"]).is_some() {
+            left = Expr::Binary(BinOp::Or, Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.comparison()?;
+        while self.accept(&["&&"]).is_some() {
+            left = Expr::Binary(BinOp::And, Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.sum()?;
+        let op = match self.accept(&["==", "!=", "<=", ">=", "!~", "=", "<", ">", "~"]) {
+            Some("=" | "==") => BinOp::Eq,
+            Some("!=") => BinOp::Ne,
+            Some("<") => BinOp::Lt,
+            Some("<=") => BinOp::Le,
+            Some(">") => BinOp::Gt,
+            Some(">=") => BinOp::Ge,
+            Some("~") => BinOp::Contains,
+            Some(_) => BinOp::NotContains,
+            None => return Ok(left),
+        };
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        while let Some(op) = self.accept(&["+", "-"]) {
+            let op = if op == "+" { BinOp::Add } else { BinOp::Sub };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.accept(&["*", "/"]) {
+            let op = if op == "*" { BinOp::Mul } else { BinOp::Div };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.accept(&["-", "!"]) {
+            Some("-") => Ok(Expr::Neg(Box::new(self.unary()?))),
+            Some(_) => Ok(Expr::Not(Box::new(self.unary()?))),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Literal(Value::Num(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::Str(s))),
+            Some(Token::Op("(")) => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Quoted(name)) => self.column(&name),
+            Some(Token::Ident(name)) => {
+                if let Some(Token::Op("(")) = self.peek() {
+                    self.pos += 1;
+                    return self.call(&name);
+                }
+                match name.as_str() {
+                    "true" => Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => Ok(Expr::Literal(Value::Null)),
+                    "rows" if self.table => Ok(Expr::Aggregate(Aggregate::Rows, None)),
+                    "columns" if self.table => Ok(Expr::Aggregate(Aggregate::Columns, None)),
+                    _ => self.column(&name),
+                }
+            }
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+
+    fn column(&self, name: &str) -> Result<Expr, String> {
+        match self.header.iter().position(|column| column == name) {
+            Some(_) if self.table => Err(format!(
+                "Column {} outside of an aggregate, e.g. nulls({})",
+                name, name
+            )),
+            Some(col) => Ok(Expr::Column(col)),
+            None => Err(format!("Unknown column: {}", name)),
+        }
+    }
+
+    // Parses the argument of an aggregate as a row expression.
+    fn aggregate(&mut self, name: &str, aggregate: Aggregate) -> Result<Expr, String> {
+        self.table = false;
+        let arg = self.or();
+        self.table = true;
+        let arg = arg?;
+        self.expect(")")
+            .map_err(|_| format!("{} expects 1 arguments", name))?;
+        Ok(Expr::Aggregate(aggregate, Some(Box::new(arg))))
+    }
+
+    // Parses the arguments of a function call after the opening parenthesis.
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        if self.table {
+            if let Some((_, aggregate)) = AGGREGATES.iter().find(|(n, _)| *n == name) {
+                return self.aggregate(name, *aggregate);
+            }
+        }
+        let (_, function, arity) = FUNCTIONS
+            .iter()
+            .find(|(function, _, _)| *function == name)
+            .ok_or_else(|| format!("Unknown function: {}", name))?;
+        let mut args = Vec::new();
+        if self.accept(&[")"]).is_none() {
+            loop {
+                args.push(self.or()?);
+                if self.accept(&[","]).is_none() {
+                    break;
+                }
+            }
+            self.expect(")")?;
+        }
+        if args.len() != *arity {
+            return Err(format!("{} expects {} arguments", name, arity));
+        }
+        Ok(Expr::Call(*function, args))
+    }
+}
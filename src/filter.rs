@@ -0,0 +1,191 @@
+//! Row filters of the form `<column> <operator> <value>`, e.g. `price > 10`, or
+//! `<column> <operator> <column>`, e.g. `expected != actual`.
+use crate::cell::{Cell, ColType};
+use crate::command::unescape;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Comparison operators, longest first so that `<=` is not read as `<`.
+const OPERATORS: [(&str, Op); 8] = [
+    ("!=", Op::Ne),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("!~", Op::NotContains),
+    ("=", Op::Eq),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+    ("~", Op::Contains),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    NotContains,
+    /// The value is a comma-separated list of tags containing the filter value; tags have no
+    /// commas
+    HasTag,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Op::HasTag => "has",
+            _ => OPERATORS
+                .iter()
+                .find(|(_, op)| op == self)
+                .map(|(symbol, _)| *symbol)
+                .expect("operator without symbol"),
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A predicate on the value of a single column.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub col: usize,
+    pub op: Op,
+    pub value: String,
+    /// Column whose value is compared against instead of `value`, which is its name
+    pub other: Option<usize>,
+}
+
+impl Filter {
+    /// Parses `<column> <operator> <value>`; column and value may be quoted, with `\'` or `\"`
+    /// for quotes and `\\` for backslashes inside. An unquoted value that is the name of a column
+    /// refers to that column.
+    pub fn parse(expression: &str, header: &[String]) -> Result<Filter, String> {
+        let name_len = quoted_name_len(expression);
+        let (start, symbol, op) = expression
+            .char_indices()
+            .skip_while(|&(i, _)| i < name_len)
+            .find_map(|(i, _)| {
+                OPERATORS
+                    .iter()
+                    .find(|(symbol, _)| expression[i..].starts_with(symbol))
+                    .map(|(symbol, op)| (i, *symbol, *op))
+            })
+            .ok_or_else(|| format!("No operator in filter '{}'", expression))?;
+        let name = unquote(expression[..start].trim());
+        let raw_value = expression[start + symbol.len()..].trim();
+        let value = unquote(raw_value);
+        let col = header
+            .iter()
+            .position(|column| *column == name)
+            .ok_or_else(|| format!("Unknown column: {}", name))?;
+        let other = match value {
+            Cow::Borrowed(value) => header.iter().position(|column| column == value),
+            Cow::Owned(_) => None,
+        };
+        Ok(Filter {
+            col,
+            op,
+            value: value.to_string(),
+            other,
+        })
+    }
+
+    /// Keeps rows whose tag column contains the given tag.
+    pub fn tag(col: usize, tag: &str) -> Filter {
+        Filter {
+            col,
+            op: Op::HasTag,
+            value: tag.to_string(),
+            other: None,
+        }
+    }
+
+    /// Does the row satisfy the filter? `types` are the column types.
+    pub fn matches(&self, row: &[String], types: &[ColType]) -> bool {
+        let raw = row[self.col].as_str();
+        let (other, other_type) = match self.other {
+            Some(other) => (row[other].as_str(), types[other]),
+            None => (self.value.as_str(), types[self.col]),
+        };
+        match self.op {
+            Op::Contains => return raw.contains(other),
+            Op::NotContains => return !raw.contains(other),
+            Op::HasTag => return raw.split(", ").any(|tag| tag == other),
+            _ => {}
+        }
+        let cell = Cell::parse(raw, types[self.col]);
+        let value = Cell::parse(other, other_type);
+        match self.op {
+            Op::Eq => cell.total_cmp(&value) == Ordering::Equal,
+            Op::Ne => cell.total_cmp(&value) != Ordering::Equal,
+            _ => match cell.compare(&value) {
+                Some(ordering) => match self.op {
+                    Op::Lt => ordering == Ordering::Less,
+                    Op::Le => ordering != Ordering::Greater,
+                    Op::Gt => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                },
+                None => false,
+            },
+        }
+    }
+
+    /// Human-readable form of the filter, given the table header.
+    pub fn describe(&self, header: &[String]) -> String {
+        match self.op {
+            Op::HasTag => format!("tag:{}", self.value),
+            _ => format!("{} {} {}", header[self.col], self.op, self.value),
+        }
+    }
+}
+
+/// Column name or value as written in a filter so that `Filter::parse` reads it back as it is:
+/// quoted if it is empty, has surrounding spaces, contains an operator, quote, backslash or
+/// semicolon or is one of the column names in `header`, which would refer to that column.
+pub fn quote(text: &str, header: &[String]) -> String {
+    let plain = !text.is_empty()
+        && text.trim() == text
+        && !text.contains(['=', '!', '<', '>', '~', '\'', '"', '\\', ';', '\t'])
+        && !header.iter().any(|name| name == text);
+    if plain {
+        return text.to_string();
+    }
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace(';', "\\;")
+        .replace('\t', "\\t");
+    format!("'{}'", escaped)
+}
+
+// Text of a quoted value with its escapes resolved, or of an unquoted one as it is.
+fn unquote(value: &str) -> Cow<'_, str> {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return Cow::Owned(unescape(&value[1..value.len() - 1]));
+        }
+    }
+    Cow::Borrowed(value)
+}
+
+// Length of a leading quoted column name including the quotes, so that operators in it are not
+// taken for the filter's operator; 0 if the expression does not start with a quote.
+fn quoted_name_len(expression: &str) -> usize {
+    let quote = match expression.chars().next() {
+        Some(quote @ ('"' | '\'')) => quote,
+        _ => return 0,
+    };
+    let mut chars = expression.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => return i + 1,
+            _ => {}
+        }
+    }
+    0
+}
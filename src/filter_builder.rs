@@ -0,0 +1,214 @@
+//! Building a filter by choosing its column, operator and value in a form, without knowing the
+//! filter syntax. The filter is applied as if typed with `:filter`.
+use crate::filter;
+use crate::renderer::{fixed_width, RenderingAction};
+use termion::style;
+
+/// Operators offered, each with the filter operator it stands for; `empty` compares with an
+/// empty value, which matches missing values as well.
+pub const OPERATORS: [(&str, &str); 6] = [
+    ("=", "="),
+    ("!=", "!="),
+    ("contains", "~"),
+    (">", ">"),
+    ("<", "<"),
+    ("empty", "="),
+];
+
+/// Width of the field names in front of the fields.
+const LABEL_WIDTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Column,
+    Operator,
+    Value,
+}
+
+/// A form at the bottom of the screen, over the table.
+pub struct FilterBuilder {
+    /// Columns to choose from, by index into the header
+    pub columns: Vec<usize>,
+    /// Index of the chosen column into `columns`
+    pub column: usize,
+    /// Index of the chosen operator into `OPERATORS`
+    pub operator: usize,
+    pub value: String,
+    pub field: Field,
+    /// Values of the chosen column in the shown rows, most frequent first, for completing the
+    /// value
+    pub values: Vec<String>,
+}
+
+impl FilterBuilder {
+    /// Offers the given columns, starting with `current` chosen and the value field empty.
+    pub fn new(columns: Vec<usize>, current: usize) -> Self {
+        FilterBuilder {
+            column: columns.iter().position(|&col| col == current).unwrap_or(0),
+            columns,
+            operator: 0,
+            value: String::new(),
+            field: Field::Column,
+            values: Vec::new(),
+        }
+    }
+
+    /// Index of the chosen column into the header.
+    pub fn col(&self) -> usize {
+        self.columns[self.column]
+    }
+
+    fn takes_value(&self) -> bool {
+        OPERATORS[self.operator].0 != "empty"
+    }
+
+    /// Moves to the next field (`down`) or the previous one, skipping the value if the operator
+    /// takes none.
+    pub fn move_field(&mut self, down: bool) -> RenderingAction {
+        let field = match (self.field, down) {
+            (Field::Column, true) => Field::Operator,
+            (Field::Operator, true) if self.takes_value() => Field::Value,
+            (Field::Operator, false) => Field::Column,
+            (Field::Value, false) => Field::Operator,
+            _ => return RenderingAction::None,
+        };
+        self.field = field;
+        RenderingAction::Pane
+    }
+
+    /// Chooses the next (`forward`) or previous column or operator, wrapping around.
+    pub fn choose(&mut self, forward: bool) -> RenderingAction {
+        let (choice, count) = match self.field {
+            Field::Column => (&mut self.column, self.columns.len()),
+            Field::Operator => (&mut self.operator, OPERATORS.len()),
+            Field::Value => return RenderingAction::None,
+        };
+        if count == 0 {
+            return RenderingAction::None;
+        }
+        *choice = match forward {
+            true => (*choice + 1) % count,
+            false => (*choice + count - 1) % count,
+        };
+        RenderingAction::Pane
+    }
+
+    /// Types a character into the value.
+    pub fn insert(&mut self, c: char) -> RenderingAction {
+        match self.field {
+            Field::Value => {
+                self.value.push(c);
+                RenderingAction::Pane
+            }
+            _ => RenderingAction::None,
+        }
+    }
+
+    pub fn delete(&mut self) -> RenderingAction {
+        match self.field {
+            Field::Value if self.value.pop().is_some() => RenderingAction::Pane,
+            _ => RenderingAction::None,
+        }
+    }
+
+    /// Missing suffix of the most frequent value starting with the typed one.
+    pub fn suggestion(&self) -> Option<&str> {
+        if self.field != Field::Value {
+            return None;
+        }
+        self.values
+            .iter()
+            .find(|value| value.len() > self.value.len() && value.starts_with(&self.value))
+            .map(|value| &value[self.value.len()..])
+    }
+
+    /// Completes the value with the suggestion, if any.
+    pub fn complete(&mut self) -> RenderingAction {
+        match self.suggestion().map(str::to_string) {
+            Some(suffix) => {
+                self.value.push_str(&suffix);
+                RenderingAction::Pane
+            }
+            None => RenderingAction::None,
+        }
+    }
+
+    /// The filter as typed with `:filter`, with the column name and value quoted if needed.
+    pub fn expression(&self, header: &[String]) -> String {
+        let (_, symbol) = OPERATORS[self.operator];
+        let value = match self.takes_value() {
+            true => filter::quote(&self.value, header),
+            false => filter::quote("", header),
+        };
+        format!(
+            "{} {} {}",
+            filter::quote(&header[self.col()], &[]),
+            symbol,
+            value
+        )
+    }
+
+    /// Title and one line per field, each of the given width, with the suggested completion of
+    /// the value dimmed.
+    pub fn lines(&self, header: &[String], width: usize) -> Vec<String> {
+        let title = "Filter: ↑/↓ field, ←/→ choose, Tab completes, Enter applies, Esc cancels";
+        let mut lines = vec![format!(
+            "{}{}{}",
+            style::Invert,
+            fixed_width(title, width),
+            style::Reset
+        )];
+        let value = match self.takes_value() {
+            true => self.value.as_str(),
+            false => "",
+        };
+        let fields = [
+            (Field::Column, "Column", header[self.col()].as_str()),
+            (Field::Operator, "Operator", OPERATORS[self.operator].0),
+            (Field::Value, "Value", value),
+        ];
+        for (field, label, text) in fields {
+            let label = format!("{:LABEL_WIDTH$}", label);
+            let suggestion = self.suggestion().filter(|_| field == Field::Value);
+            let line = fixed_width(
+                &format!("{}{}{}", label, text, suggestion.unwrap_or("")),
+                width,
+            );
+            // the label of the current field is bold, the suggestion after the value dimmed
+            let (label, rest) = split_chars(&line, LABEL_WIDTH);
+            let (text, suggestion) = split_chars(rest, text.chars().count());
+            let label = match field == self.field {
+                true => format!("{}{}{}", style::Bold, label, style::Reset),
+                false => label.to_string(),
+            };
+            lines.push(format!(
+                "{}{}{}{}{}",
+                label,
+                text,
+                style::Faint,
+                suggestion,
+                style::Reset
+            ));
+        }
+        lines
+    }
+
+    /// Line of the current field among the lines, counting from 0, and the column of the
+    /// cursor in it: at the end of the value or the start of a choice.
+    pub fn cursor(&self) -> (usize, usize) {
+        match self.field {
+            Field::Column => (1, LABEL_WIDTH),
+            Field::Operator => (2, LABEL_WIDTH),
+            Field::Value => (3, LABEL_WIDTH + self.value.chars().count()),
+        }
+    }
+}
+
+// Splits the text after the given number of characters, or at its end if it is shorter.
+fn split_chars(text: &str, count: usize) -> (&str, &str) {
+    let i = text
+        .char_indices()
+        .nth(count)
+        .map_or(text.len(), |(i, _)| i);
+    text.split_at(i)
+}
@@ -0,0 +1,88 @@
+//! Recently opened files, most recent first, kept in a small history file.
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of files remembered.
+const MAX_ENTRIES: usize = 50;
+
+#[derive(Debug, Default)]
+pub struct History {
+    /// History file, None to keep the history in memory only
+    path: Option<PathBuf>,
+    /// Opened files and when they were last opened (seconds since the epoch), most recent first
+    pub entries: Vec<(PathBuf, u64)>,
+}
+
+impl History {
+    /// Loads the history from a file; a missing file yields an empty history.
+    pub fn load(path: &Path) -> Result<History, Box<dyn Error>> {
+        let mut history = History {
+            path: Some(path.to_path_buf()),
+            entries: Vec::new(),
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(history),
+            Err(err) => return Err(err.into()),
+        };
+        for (i, line) in content.lines().enumerate() {
+            let invalid = || format!("{}:{}: invalid entry", path.display(), i + 1);
+            let (time, file) = line.split_once('\t').ok_or_else(invalid)?;
+            let time = time.parse().map_err(|_| invalid())?;
+            history.entries.push((PathBuf::from(file), time));
+        }
+        Ok(history)
+    }
+
+    /// Moves the file to the top of the history.
+    pub fn record(&mut self, file: &Path) {
+        let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        self.entries.retain(|(path, _)| *path != file);
+        self.entries.insert(0, (file, now));
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    /// Files of the history that still exist, most recent first.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .map(|(path, _)| path.clone())
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Writes the history to its file, creating the directory if needed.
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content: String = self
+            .entries
+            .iter()
+            .map(|(file, time)| format!("{}\t{}\n", time, file.display()))
+            .collect();
+        fs::write(path, content)
+    }
+}
+
+/// Location of the history file, honoring `XDG_STATE_HOME`.
+pub fn default_path() -> Option<PathBuf> {
+    let state_dir = match env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state_dir.join("table-viewer").join("history"))
+}
@@ -0,0 +1,188 @@
+//! Keyboard and mouse input of the viewer: key presses, including modifiers reported with the
+//! xterm and kitty keyboard protocols, mouse buttons and text pasted in bracketed paste mode.
+use crate::termion::input::TermRead;
+use std::io::Read;
+use termion::event::{Event, Key, MouseEvent};
+
+/// Makes the terminal report mouse buttons and movements with a button held, as `MouseTerminal`
+/// does, e.g. to turn them off while the viewer is suspended.
+pub const ENABLE_MOUSE: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+pub const DISABLE_MOUSE: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+/// Makes the terminal wrap pasted text in `ESC [200~` and `ESC [201~`.
+pub const ENABLE_PASTE: &str = "\x1b[?2004h";
+pub const DISABLE_PASTE: &str = "\x1b[?2004l";
+
+/// Asks terminals supporting the kitty keyboard protocol to report keys with modifiers
+/// unambiguously as `CSI <code>;<modifiers> u`. Other terminals ignore it.
+pub const ENABLE_KEYBOARD_PROTOCOL: &str = "\x1b[>1u";
+pub const DISABLE_KEYBOARD_PROTOCOL: &str = "\x1b[<u";
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Modifier keys held down with a key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl Modifiers {
+    // Decodes the modifier parameter of CSI sequences: 1 plus a bit mask of shift (1), alt (2)
+    // and ctrl (4), higher bits like super are ignored.
+    fn parse(param: &str) -> Option<Modifiers> {
+        let mask = param.parse::<u8>().ok()?.checked_sub(1)?;
+        Some(Modifiers {
+            shift: mask & 1 != 0,
+            alt: mask & 2 != 0,
+            ctrl: mask & 4 != 0,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Modifiers::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Key(Key),
+    /// Key with modifiers that cannot be expressed by a `Key`, e.g. Shift+Up
+    Modified(Modifiers, Key),
+    /// Text pasted at once, including any line breaks
+    Paste(String),
+    Mouse(MouseEvent),
+}
+
+/// Reads keys from the source, collecting the keys between the paste markers into one paste.
+/// Unknown sequences are skipped. Ends with the source or at the first read error.
+pub fn inputs<R: Read>(source: R) -> impl Iterator<Item = Input> {
+    let mut events = source.events().map_while(Result::ok);
+    std::iter::from_fn(move || loop {
+        match events.next()? {
+            Event::Key(key) => return Some(Input::Key(key)),
+            Event::Mouse(mouse) => return Some(Input::Mouse(mouse)),
+            Event::Unsupported(bytes) if bytes == PASTE_START => {
+                let mut text = String::new();
+                for event in events.by_ref() {
+                    match event {
+                        Event::Unsupported(bytes) if bytes == PASTE_END => break,
+                        Event::Key(Key::Char(c)) => text.push(c),
+                        _ => {}
+                    }
+                }
+                return Some(Input::Paste(text));
+            }
+            Event::Unsupported(bytes) => {
+                if let Some(input) = parse_csi(&bytes) {
+                    return Some(input);
+                }
+            }
+        }
+    })
+}
+
+// Parses the key sequences termion does not know: keys with modifiers, `CSI 1;<modifiers> <A-H>`
+// for arrows, Home and End or `CSI <number>;<modifiers> ~` for the keys above the arrows, and
+// `CSI <code>[;<modifiers>] u` from the kitty keyboard protocol.
+fn parse_csi(bytes: &[u8]) -> Option<Input> {
+    let sequence = std::str::from_utf8(bytes.strip_prefix(b"\x1b[")?).ok()?;
+    let last = sequence.chars().last()?;
+    let params = &sequence[..sequence.len() - last.len_utf8()];
+    // sub-parameters, e.g. alternate keys or event types, are not needed
+    let mut params = params
+        .split(';')
+        .map(|param| param.split(':').next().unwrap_or(""));
+    let number = params.next()?;
+    let modifiers = match params.next() {
+        Some(param) => Modifiers::parse(param)?,
+        None => Modifiers::default(),
+    };
+    let key = match (last, number) {
+        ('A', "1") => Key::Up,
+        ('B', "1") => Key::Down,
+        ('C', "1") => Key::Right,
+        ('D', "1") => Key::Left,
+        ('H', "1") => Key::Home,
+        ('F', "1") => Key::End,
+        ('~', "1" | "7") => Key::Home,
+        ('~', "2") => Key::Insert,
+        ('~', "3") => Key::Delete,
+        ('~', "4" | "8") => Key::End,
+        ('~', "5") => Key::PageUp,
+        ('~', "6") => Key::PageDown,
+        ('u', "27") => Key::Esc,
+        ('u', "13") => Key::Char('\n'),
+        ('u', "9") => Key::Char('\t'),
+        ('u', "127") => Key::Backspace,
+        ('u', code) => Key::Char(char::from_u32(code.parse().ok()?)?),
+        _ => return None,
+    };
+    Some(with_modifiers(modifiers, key))
+}
+
+// Uses termion's keys where possible, so that Ctrl-c is the same key in both protocols.
+fn with_modifiers(modifiers: Modifiers, key: Key) -> Input {
+    let only = |shift, alt, ctrl| modifiers == Modifiers { shift, alt, ctrl };
+    match key {
+        _ if modifiers.is_empty() => Input::Key(key),
+        Key::Char('\t') if only(true, false, false) => Input::Key(Key::BackTab),
+        Key::Char(c) if only(false, false, true) => Input::Key(Key::Ctrl(c)),
+        Key::Char(c) if only(false, true, false) => Input::Key(Key::Alt(c)),
+        key => Input::Modified(modifiers, key),
+    }
+}
+
+/// Name of a key with modifiers for key handlers of scripts, e.g. `ctrl-shift-left`, `alt-x`
+/// or `pagedown`; None for keys without a name.
+pub fn key_name(modifiers: Modifiers, key: Key) -> Option<String> {
+    let (modifiers, key) = match key {
+        Key::Ctrl(c) => (
+            Modifiers {
+                ctrl: true,
+                ..modifiers
+            },
+            Key::Char(c),
+        ),
+        Key::Alt(c) => (
+            Modifiers {
+                alt: true,
+                ..modifiers
+            },
+            Key::Char(c),
+        ),
+        key => (modifiers, key),
+    };
+    let name = match key {
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char('\t') => "tab".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::F(n) => format!("f{}", n),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Esc => "esc".to_string(),
+        _ => return None,
+    };
+    let prefixes = [
+        (modifiers.ctrl, "ctrl-"),
+        (modifiers.alt, "alt-"),
+        (modifiers.shift, "shift-"),
+    ];
+    let prefix: String = prefixes
+        .iter()
+        .filter(|(held, _)| *held)
+        .map(|(_, prefix)| *prefix)
+        .collect();
+    Some(format!("{}{}", prefix, name))
+}
@@ -0,0 +1,84 @@
+//! JSON Lines input: one JSON object per line, e.g. log exports. The columns are the keys of all
+//! objects in order of their first appearance; objects without a key have an empty value there.
+pub use crate::registry::Table;
+use crate::registry::{DataSource, LoadOptions};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read};
+use std::iter::once;
+
+/// Newline-delimited JSON objects.
+pub struct JsonLinesSource;
+
+impl DataSource for JsonLinesSource {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["jsonl", "ndjson"]
+    }
+
+    fn load(&self, reader: Box<dyn Read>, options: &LoadOptions) -> Result<Table, Box<dyn Error>> {
+        read_jsonl_head(reader, options.max_rows.unwrap_or(usize::MAX))
+    }
+}
+
+/// Reads one object per line, skipping blank lines.
+pub fn read_jsonl<R: Read>(reader: R) -> Result<Table, Box<dyn Error>> {
+    read_jsonl_head(reader, usize::MAX)
+}
+
+/// Reads at most `limit` objects.
+pub fn read_jsonl_head<R: Read>(reader: R, limit: usize) -> Result<Table, Box<dyn Error>> {
+    let mut header = vec!["#".to_string()];
+    let mut columns: HashMap<String, usize> = HashMap::new();
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if records.len() == limit {
+            break;
+        }
+        let object = match serde_json::from_str(&line) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err(format!("Line {}: not a JSON object", i + 1).into()),
+            Err(err) => return Err(format!("Line {}: {}", i + 1, err).into()),
+        };
+        let mut record = Vec::with_capacity(object.len());
+        for (key, value) in object {
+            let col = *columns.entry(key).or_insert_with_key(|key| {
+                header.push(key.clone());
+                header.len() - 1
+            });
+            record.push((col, cell_value(value)));
+        }
+        records.push(record);
+    }
+    let rows = records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let mut row: Vec<String> = once(format!("{}", i + 1))
+                .chain(vec![String::new(); header.len() - 1])
+                .collect();
+            for (col, value) in record {
+                row[col] = value;
+            }
+            row
+        })
+        .collect();
+    Ok((header, rows))
+}
+
+// Strings without quotes, null as empty value, arrays and objects as compact JSON.
+fn cell_value(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,278 @@
+//! Paging through files too large to load: rows are read on demand, found via an index of the
+//! positions of every `INDEX_STEP`th record, so memory use does not grow with the file.
+use crate::registry::Table;
+use crate::renderer::RenderingAction;
+use crate::state::{group_digits, TableState};
+#[cfg(feature = "csv")]
+use csv::{Position, Reader, ReaderBuilder};
+use std::cmp::min;
+use std::error::Error;
+#[cfg(feature = "csv")]
+use std::fs::File;
+#[cfg(feature = "csv")]
+use std::iter::once;
+#[cfg(feature = "csv")]
+use std::path::Path;
+use termion::event::Key;
+
+/// Number of records between two positions in the index.
+#[cfg(feature = "csv")]
+const INDEX_STEP: usize = 1024;
+
+/// A table whose rows are read when they are needed instead of all at once.
+pub trait TableDataSource {
+    /// Column names, starting with `#` for the row numbers.
+    fn header(&self) -> &[String];
+    /// Number of data rows, None until the source has been read to the end.
+    fn row_count(&self) -> Option<usize>;
+    /// Reads up to `count` rows starting at row `start`, fewer at the end of the table.
+    fn rows(&mut self, start: usize, count: usize) -> Result<Vec<Vec<String>>, Box<dyn Error>>;
+    /// Reads the source to the end to count its rows.
+    fn count_rows(&mut self) -> Result<usize, Box<dyn Error>>;
+}
+
+/// A CSV file read on demand.
+#[cfg(feature = "csv")]
+pub struct IndexedCsv {
+    reader: Reader<File>,
+    header: Vec<String>,
+    /// Positions of records 0, `INDEX_STEP`, 2 * `INDEX_STEP` and so on
+    checkpoints: Vec<Position>,
+    /// Number of records indexed so far and the position of the next one
+    indexed: usize,
+    frontier: Position,
+    complete: bool,
+}
+
+#[cfg(feature = "csv")]
+impl IndexedCsv {
+    /// Opens the file, reading only its header.
+    pub fn open(path: &Path, delimiter: u8, quote: u8) -> Result<Self, Box<dyn Error>> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(delimiter)
+            .quote(quote)
+            .from_reader(File::open(path)?);
+        let header = once("#".to_string())
+            .chain(reader.headers()?.iter().map(|value| value.to_string()))
+            .collect();
+        let frontier = reader.position().clone();
+        Ok(IndexedCsv {
+            reader,
+            header,
+            checkpoints: Vec::new(),
+            indexed: 0,
+            frontier,
+            complete: false,
+        })
+    }
+
+    // Scans the file until the record `row` is indexed or the file ends.
+    fn index_until(&mut self, row: usize) -> Result<(), Box<dyn Error>> {
+        if self.complete || row < self.indexed {
+            return Ok(());
+        }
+        self.reader.seek(self.frontier.clone())?;
+        let mut record = csv::ByteRecord::new();
+        while self.indexed <= row {
+            let position = self.reader.position().clone();
+            if !self.reader.read_byte_record(&mut record)? {
+                self.complete = true;
+                break;
+            }
+            if self.indexed.is_multiple_of(INDEX_STEP) {
+                self.checkpoints.push(position);
+            }
+            self.indexed += 1;
+        }
+        self.frontier = self.reader.position().clone();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "csv")]
+impl TableDataSource for IndexedCsv {
+    fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    fn row_count(&self) -> Option<usize> {
+        self.complete.then_some(self.indexed)
+    }
+
+    fn rows(&mut self, start: usize, count: usize) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+        // one more to find out if the table ends with these rows
+        self.index_until(start.saturating_add(count))?;
+        if start >= self.indexed || count == 0 {
+            return Ok(Vec::new());
+        }
+        let checkpoint = start / INDEX_STEP;
+        self.reader.seek(self.checkpoints[checkpoint].clone())?;
+        let mut rows = Vec::with_capacity(count);
+        let mut skip = csv::ByteRecord::new();
+        for _ in checkpoint * INDEX_STEP..start {
+            self.reader.read_byte_record(&mut skip)?;
+        }
+        for (i, result) in self.reader.records().take(count).enumerate() {
+            let record = result?;
+            rows.push(
+                once(format!("{}", start + i + 1))
+                    .chain(record.iter().map(|value| value.to_string()))
+                    .collect(),
+            );
+        }
+        Ok(rows)
+    }
+
+    fn count_rows(&mut self) -> Result<usize, Box<dyn Error>> {
+        self.index_until(usize::MAX)?;
+        Ok(self.indexed)
+    }
+}
+
+/// Window of a table data source shown by the viewer: its table holds only the rows on screen,
+/// with the column types of the first page. Sorting, filtering and searching need all rows and
+/// are not available.
+pub struct Pager {
+    source: Box<dyn TableDataSource>,
+    /// Row of the table shown first
+    top: usize,
+}
+
+impl Pager {
+    pub fn new(source: Box<dyn TableDataSource>) -> Self {
+        Pager { source, top: 0 }
+    }
+
+    /// Column names of the source and its first `count` rows, to set up the table with.
+    pub fn first_page(&mut self, count: usize) -> Result<Table, Box<dyn Error>> {
+        Ok((self.source.header().to_vec(), self.source.rows(0, count)?))
+    }
+
+    /// Position of the cursor in the table: 0 in the header, else the number of the data row.
+    pub fn position(&self, state: &TableState) -> usize {
+        match state.cur_pos.row {
+            0 => 0,
+            row => self.top + row,
+        }
+    }
+
+    // Reads the window of rows starting at `top` into the table. Does not scroll beyond the last
+    // row.
+    fn load(&mut self, state: &mut TableState, top: usize) -> Result<(), Box<dyn Error>> {
+        let page = state.displayable_data_rows();
+        let mut top = top;
+        let mut rows = self.source.rows(top, page)?;
+        if rows.len() < page && top > 0 {
+            let count = self.source.row_count().unwrap_or(top + rows.len());
+            top = count.saturating_sub(page);
+            rows = self.source.rows(top, page)?;
+        }
+        state.show_page(rows);
+        self.top = top;
+        Ok(())
+    }
+
+    /// Moves the cursor to a position in the table (0 for the header), scrolling as needed.
+    pub fn move_to(
+        &mut self,
+        state: &mut TableState,
+        position: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let page = state.displayable_data_rows();
+        let top = match position {
+            0 => 0,
+            position if position <= self.top => position - 1,
+            position if position > self.top + page => position - page,
+            _ => self.top,
+        };
+        // a page shorter than the window may follow a resize
+        if top != self.top || state.rows.len() < page {
+            self.load(state, top)?;
+        }
+        state.cur_pos.row = min(position.saturating_sub(self.top), state.row_count());
+        Ok(())
+    }
+
+    /// Moves to the last row, reading the whole file to find it.
+    pub fn move_end(&mut self, state: &mut TableState) -> Result<(), Box<dyn Error>> {
+        let count = self.source.count_rows()?;
+        self.move_to(state, count)
+    }
+
+    /// Fills the window again after the terminal was resized, keeping the cursor on its row.
+    pub fn refill(&mut self, state: &mut TableState, position: usize) {
+        let result = match self.load(state, self.top) {
+            Ok(()) => self.move_to(state, position),
+            err => err,
+        };
+        self.show_result(state, result);
+    }
+
+    /// Text of the status line: the position and, once known, the number of rows.
+    pub fn status(&self, state: &TableState) -> String {
+        let count = match self.source.row_count() {
+            Some(count) => group_digits(count),
+            None => "?".to_string(),
+        };
+        format!(
+            "row {}/{} (read on demand)",
+            group_digits(self.position(state)),
+            count
+        )
+    }
+
+    // Shows the position, or the error if reading failed.
+    fn show_result(&self, state: &mut TableState, result: Result<(), Box<dyn Error>>) {
+        state.status_message = Some(match result {
+            Ok(()) => self.status(state),
+            Err(err) => format!("Error: {}", err),
+        });
+    }
+
+    /// Handles a key of the viewer's normal mode: moving the cursor, reading rows as needed, or
+    /// quitting. Other keys are not available.
+    pub fn handle_key(
+        &mut self,
+        state: &mut TableState,
+        key: Key,
+        prev_key: Key,
+    ) -> RenderingAction {
+        let page = state.displayable_data_rows();
+        let position = self.position(state);
+        let result = match key {
+            Key::Char('q') | Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => {
+                return RenderingAction::Reset
+            }
+            Key::Down | Key::Char('j') => self.move_to(state, position + 1),
+            Key::Up | Key::Char('k') => self.move_to(state, position.saturating_sub(1)),
+            Key::PageDown => self.move_to(state, position + page),
+            Key::PageUp => self.move_to(state, position.saturating_sub(page)),
+            Key::Home => self.move_to(state, 0),
+            Key::Char('g') if prev_key == Key::Char('g') => self.move_to(state, 0),
+            Key::Char('g') => return RenderingAction::None,
+            Key::End | Key::Char('G') => self.move_end(state),
+            Key::Right | Key::Char('l') => {
+                state.move_right();
+                Ok(())
+            }
+            Key::Left | Key::Char('h') => {
+                state.move_left();
+                Ok(())
+            }
+            Key::Char('0') => {
+                state.move_start_of_line();
+                Ok(())
+            }
+            Key::Char('$') => {
+                state.move_end_of_line();
+                Ok(())
+            }
+            _ => {
+                state.status_message = Some("Not available while reading on demand".to_string());
+                return RenderingAction::Status;
+            }
+        };
+        self.show_result(state, result);
+        RenderingAction::Rerender
+    }
+}
@@ -1,5 +1,41 @@
 extern crate termion;
+pub mod cell;
+pub mod clipboard;
+pub mod collation;
+pub mod column_editor;
+pub mod command;
+pub mod config;
+#[cfg(feature = "csv")]
 pub mod csv;
+pub mod dashboard;
+pub mod expr;
+pub mod filter;
+pub mod filter_builder;
+pub mod history;
+pub mod input;
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+pub mod lazy;
+pub mod lint;
+#[cfg(feature = "log")]
+pub mod log;
+pub mod pane;
+#[cfg(feature = "csv")]
+pub mod picker;
+pub mod plot;
+pub mod registry;
 pub mod renderer;
+pub mod sample;
+#[cfg(feature = "config")]
+pub mod schema;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod session;
+#[cfg(feature = "spreadsheet")]
+pub mod spreadsheet;
 pub mod state;
+pub mod stats;
+pub mod theme;
 pub mod viewer;
+#[cfg(feature = "csv")]
+pub mod writer;
@@ -0,0 +1,116 @@
+//! Detection of cells deviating from the dominant pattern of their column.
+use crate::cell::is_null;
+use crate::pane::PaneEntry;
+use std::collections::HashMap;
+
+/// Share of cells that must follow a pattern for it to count as dominant.
+const DOMINANCE: f64 = 0.9;
+
+/// Maximum number of reported cells.
+const MAX_FINDINGS: usize = 10_000;
+
+// Character classes of a value as bit mask
+const DIGIT: u8 = 1;
+const LOWER: u8 = 2;
+const UPPER: u8 = 4;
+const SPACE: u8 = 8;
+const PUNCT: u8 = 16;
+const OTHER: u8 = 32;
+
+fn charset(value: &str) -> u8 {
+    value.chars().fold(0, |mask, c| {
+        mask | match c {
+            '0'..='9' => DIGIT,
+            'a'..='z' => LOWER,
+            'A'..='Z' => UPPER,
+            c if c.is_whitespace() => SPACE,
+            c if c.is_ascii_punctuation() => PUNCT,
+            _ => OTHER,
+        }
+    })
+}
+
+fn is_numeric(value: &str) -> bool {
+    value.trim().parse::<f64>().is_ok()
+}
+
+// Most frequent key and whether it is dominant among `total` values
+fn dominant<K: Copy + Eq + std::hash::Hash>(counts: &HashMap<K, usize>, total: usize) -> Option<K> {
+    let (key, count) = counts.iter().max_by_key(|(_, count)| **count)?;
+    (*count as f64 >= DOMINANCE * total as f64).then_some(*key)
+}
+
+/// Patterns most cells of a column follow.
+struct ColumnPattern {
+    numeric: Option<bool>,
+    charset: Option<u8>,
+    length: Option<usize>,
+}
+
+impl ColumnPattern {
+    fn learn<'a, I: Iterator<Item = &'a str>>(values: I) -> Self {
+        let mut numeric = HashMap::new();
+        let mut charsets = HashMap::new();
+        let mut lengths = HashMap::new();
+        let mut total = 0;
+        for value in values.filter(|v| !is_null(v)) {
+            total += 1;
+            *numeric.entry(is_numeric(value)).or_insert(0) += 1;
+            *charsets.entry(charset(value)).or_insert(0) += 1;
+            *lengths.entry(value.chars().count()).or_insert(0) += 1;
+        }
+        ColumnPattern {
+            numeric: dominant(&numeric, total),
+            charset: dominant(&charsets, total),
+            length: dominant(&lengths, total),
+        }
+    }
+
+    // Describes how a value deviates from the pattern, if it does
+    fn deviation(&self, value: &str) -> Option<String> {
+        if is_null(value) {
+            return None;
+        }
+        if self.numeric == Some(true) && !is_numeric(value) {
+            return Some("not numeric".to_string());
+        }
+        if self.numeric == Some(false) && is_numeric(value) {
+            return Some("numeric in text column".to_string());
+        }
+        if let Some(mask) = self.charset {
+            if charset(value) & !mask != 0 {
+                return Some("unusual characters".to_string());
+            }
+        }
+        match self.length {
+            Some(length) if value.chars().count() != length => Some(format!(
+                "length {}, usually {}",
+                value.chars().count(),
+                length
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Lists cells of data columns (all but the row number) that deviate from their column's pattern.
+pub fn lint(header: &[String], rows: &[Vec<String>]) -> Vec<PaneEntry> {
+    let mut findings = Vec::new();
+    for (col, name) in header.iter().enumerate().skip(1) {
+        let pattern = ColumnPattern::learn(rows.iter().map(|row| row[col].as_str()));
+        for (i, row) in rows.iter().enumerate() {
+            if let Some(deviation) = pattern.deviation(&row[col]) {
+                findings.push(PaneEntry {
+                    row: Some(i),
+                    col,
+                    text: format!("{}: {}: '{}' {}", row[0], name, row[col], deviation),
+                    highlight: false,
+                });
+                if findings.len() >= MAX_FINDINGS {
+                    return findings;
+                }
+            }
+        }
+    }
+    findings
+}
@@ -0,0 +1,42 @@
+//! Debug log for bug reports: keys, commands, mode changes and render times, written to the file
+//! given with `--log`.
+use std::fs::OpenOptions;
+use std::sync::Mutex;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Level and file of a `--log` option like `debug:/tmp/tv.log`. The level is optional and
+/// defaults to debug; a prefix that is no level belongs to the file name, e.g. in `/tmp/a:b.log`.
+pub fn parse_option(option: &str) -> (Level, &str) {
+    match option.split_once(':') {
+        Some((level, path)) => match level.parse::<Level>() {
+            Ok(level) => (level, path),
+            Err(_) => (Level::DEBUG, option),
+        },
+        None => (Level::DEBUG, option),
+    }
+}
+
+/// Subscriber writing events of the given level and above to the writer, one line each.
+pub fn subscriber<W>(level: Level, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_ansi(false)
+        .with_writer(writer)
+        .finish()
+}
+
+/// Appends events to the file of a `--log` option for the rest of the process.
+pub fn start(option: &str) -> Result<(), String> {
+    let (level, path) = parse_option(option);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("cannot open log file {}: {}", path, err))?;
+    tracing::subscriber::set_global_default(subscriber(level, Mutex::new(file)))
+        .map_err(|err| err.to_string())
+}
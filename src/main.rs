@@ -1,9 +1,31 @@
-use table_viewer::renderer::TerminalTableRenderer;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, IsTerminal};
 use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
 
 use clap::Parser;
+use table_viewer::cell::{infer_type, ColType};
+use table_viewer::config::{Config, PaneConfig};
+use table_viewer::dashboard::Dashboard;
+use table_viewer::expr::Expr;
+use table_viewer::history::{self, History};
+#[cfg(feature = "csv")]
+use table_viewer::lazy::IndexedCsv;
+use table_viewer::registry::{LoadOptions, Registry, Table};
+use table_viewer::renderer::{TableRenderer, TerminalTableRenderer};
+use table_viewer::schema::Schema;
+#[cfg(feature = "scripting")]
+use table_viewer::script::ScriptHost;
+use table_viewer::session::Session;
+#[cfg(feature = "spreadsheet")]
+use table_viewer::spreadsheet;
+use table_viewer::state::SearchCase;
+use table_viewer::stats;
+use table_viewer::theme::Theme;
 use table_viewer::viewer::TableViewer;
-use table_viewer::csv::{read_csv_from_file, read_csv_from_stdin};
+use tracing::info;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
@@ -12,14 +34,21 @@ use table_viewer::csv::{read_csv_from_file, read_csv_from_stdin};
 /// Move between cells using the arrow keys or Vim's hjkl. Page up and down.
 /// Jump to start via Home or gg. Jump to end via End or G. Sort by column
 /// under cursor with a (ascending) or d (descending); return to original
-/// order with o. Search for substring in column under cursor by typing /
-/// followed by search term and Enter. Repeat last search starting from
-/// current cursor position by typing Space. Exit with q or Ctrl-x.
+/// order with o. Add further columns to sort by with A or D. Search for
+/// substring in column under cursor by typing / followed by search term and
+/// Enter. Repeat last search starting from current cursor position by typing
+/// Space. Mark a row with m and compare it with the row under the cursor
+/// with c. List all rows matching the last search with :matches; select with
+/// j/k and jump to the match with Enter. Exit with q or Ctrl-x.
 struct Args {
     /// Path to CSV/TSV file
     #[clap()]
     file: Option<String>,
 
+    /// Input format (default based on file extension, see --formats)
+    #[clap(short, long)]
+    format: Option<String>,
+
     /// Field delimiter (default based on file extension)
     #[clap(short, long)]
     delimiter: Option<char>,
@@ -27,38 +56,504 @@ struct Args {
     /// Quote character
     #[clap(short, long)]
     quote: Option<char>,
+
+    /// Sheet of a spreadsheet to show (default: choose one if there are several)
+    #[clap(long)]
+    sheet: Option<String>,
+
+    /// Path to config file (default ~/.config/table-viewer/config.toml)
+    #[clap(long)]
+    config: Option<String>,
+
+    /// Profile of the config to apply (default: the first whose file patterns match the file)
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Validate the table against a TOML schema of expected columns and types
+    #[clap(long)]
+    schema: Option<String>,
+
+    /// Colors for a light or dark terminal background: light, dark or auto to detect it
+    /// (default: the config's theme or auto)
+    #[clap(long)]
+    theme: Option<String>,
+
+    /// Print the table as plain text and exit, the default when the output is not a terminal
+    #[clap(long)]
+    print: bool,
+
+    /// Renderer used to display the table
+    #[clap(long, default_value = "terminal")]
+    renderer: String,
+
+    /// List available input formats and renderers, then exit
+    #[clap(long)]
+    formats: bool,
+
+    /// Show the grid of files and commands defined in the [dashboard] section of the config
+    #[clap(long)]
+    dashboard: bool,
+
+    /// Search case-insensitively
+    #[clap(short = 'i', long)]
+    ignore_case: bool,
+
+    /// Search case-insensitively unless the pattern contains uppercase letters
+    #[clap(short = 'S', long, conflicts_with = "ignore-case")]
+    smart_case: bool,
+
+    /// Lines of the header: 2 for files whose first line names groups of columns, each spanning
+    /// the columns up to the next name
+    #[clap(long, value_name = "N", default_value_t = 1)]
+    header_rows: usize,
+
+    /// Read at most N rows, 0 for all (default 2,000,000 or max_rows of the config)
+    #[clap(long, value_name = "N")]
+    max_rows: Option<usize>,
+
+    /// Keep the row numbers and the first N columns in view when scrolling horizontally
+    #[clap(long, value_name = "N")]
+    freeze_cols: Option<usize>,
+
+    /// Choose one of the recently opened files
+    #[clap(long)]
+    recent: bool,
+
+    /// Print type, nulls, distinct values, minimum and maximum of each column, then exit
+    #[clap(long)]
+    describe: bool,
+
+    /// Print the --describe profile as JSON
+    #[clap(long, requires = "describe")]
+    json: bool,
+
+    /// Check a table expression such as 'rows > 0' or 'no_nulls(id)' without starting the
+    /// viewer; exits with 1 if a check fails and 2 if it is invalid (repeatable)
+    #[clap(long = "assert", value_name = "EXPRESSION")]
+    assertions: Vec<String>,
+
+    /// Read rows from the file as they are shown instead of loading it, read-only and without
+    /// sorting, filtering or search, e.g. for files too large for memory
+    #[clap(long)]
+    lazy: bool,
+
+    /// Append keys, commands, mode changes and render times to a file for bug reports, e.g.
+    /// debug:/tmp/tv.log (levels error, warn, info, debug and trace; debug if left out)
+    #[clap(long, value_name = "LEVEL:FILE")]
+    log: Option<String>,
+}
+
+/// Asks which sheet to show if the workbook has several; None for the first one.
+#[cfg(feature = "spreadsheet")]
+fn choose_sheet(path: &Path) -> Option<String> {
+    let names = spreadsheet::sheet_names(File::open(path).ok()?).ok()?;
+    if names.len() < 2 {
+        return None;
+    }
+    for (i, name) in names.iter().enumerate() {
+        eprintln!("{:>3}) {}", i + 1, name);
+    }
+    eprint!("Sheet [1]: ");
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok()?;
+    let answer = answer.trim();
+    match answer.parse::<usize>() {
+        Ok(number) if (1..=names.len()).contains(&number) => Some(names[number - 1].clone()),
+        _ => names.iter().find(|name| name.as_str() == answer).cloned(),
+    }
+}
+
+#[cfg(not(feature = "spreadsheet"))]
+fn choose_sheet(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Evaluates the assertions on the table and reports failures; returns the exit code.
+fn check_assertions(assertions: &[String], header: &[String], rows: &[Vec<String>]) -> i32 {
+    let types: Vec<ColType> = (0..header.len())
+        .map(|col| infer_type(rows.iter().map(|row| row[col].as_str())))
+        .collect();
+    let mut code = 0;
+    for assertion in assertions {
+        match Expr::parse_table(assertion, header) {
+            Ok(expr) if expr.eval_table(rows, &types, header.len() - 1).is_true() => {}
+            Ok(_) => {
+                eprintln!("Assertion failed: {}", assertion);
+                code = code.max(1);
+            }
+            Err(err) => {
+                eprintln!("Invalid assertion '{}': {}", assertion, err);
+                code = 2;
+            }
+        }
+    }
+    code
+}
+
+fn print_formats(registry: &Registry) {
+    println!("Formats:");
+    for source in registry.sources() {
+        println!(
+            "  {:<10} .{}",
+            source.name(),
+            source.extensions().join(", .")
+        );
+    }
+    println!("Renderers:");
+    for name in registry.renderer_names() {
+        println!("  {}", name);
+    }
+}
+
+/// Loads the table of a dashboard pane from its file or the CSV output of its command.
+fn load_pane(registry: &Registry, pane: &PaneConfig) -> Result<Table, Box<dyn Error>> {
+    let options = LoadOptions::default();
+    match (&pane.file, &pane.command) {
+        (Some(file), _) => {
+            let source = registry
+                .source_for_path(file)
+                .or_else(|| registry.source("csv"))
+                .ok_or("no data source for file")?;
+            source.load(Box::new(BufReader::new(File::open(file)?)), &options)
+        }
+        (None, Some(command)) => {
+            let output = Command::new("sh").arg("-c").arg(command).output()?;
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(&output.stderr).trim().into());
+            }
+            let source = registry.source("csv").ok_or("no CSV data source")?;
+            source.load(Box::new(Cursor::new(output.stdout)), &options)
+        }
+        (None, None) => Err("pane has neither file nor command".into()),
+    }
+}
+
+fn run_dashboard(config: &Config, registry: &Registry) {
+    let dashboard = match &config.dashboard {
+        Some(dashboard) if !dashboard.panes.is_empty() => dashboard,
+        _ => {
+            eprintln!("No dashboard panes defined in the config");
+            std::process::exit(1);
+        }
+    };
+    let size = TerminalTableRenderer {}.window_size();
+    let load = |pane: &PaneConfig| load_pane(registry, pane);
+    if let Err(err) = Dashboard::new(dashboard, size).run(&load) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Pages through a delimited file, reading the rows on screen only.
+#[cfg(feature = "csv")]
+fn page_file(path: &Path, delimited: Option<(u8, u8)>, registry: &Registry, renderer: &str) {
+    let (delimiter, quote) = match delimited {
+        Some(format) => format,
+        None => {
+            eprintln!("Only delimited formats can be read lazily");
+            std::process::exit(1);
+        }
+    };
+    let renderer = match registry.renderer(renderer) {
+        Some(renderer) => renderer,
+        None => {
+            eprintln!("Unknown renderer '{}', see --formats", renderer);
+            std::process::exit(1);
+        }
+    };
+    let result = IndexedCsv::open(path, delimiter, quote)
+        .and_then(|source| TableViewer::paged(renderer, Box::new(source)))
+        .and_then(|mut viewer| viewer.run());
+    if let Err(err) = result {
+        eprintln!("Error reading file '{:?}': {}", path, err);
+        std::process::exit(1);
+    }
 }
 
 fn main() {
     let args = Args::parse();
-    let delimiter = match args.delimiter {
-        Some(c) => c as u8,
-        None => match args.file {
-            Some(ref file) if file.ends_with(".tsv") => b'\t',
-            _ => b',',
-        },
+    if let Some(ref option) = args.log {
+        if let Err(err) = table_viewer::log::start(option) {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    }
+    let config = match Config::load(args.config.as_deref().map(Path::new)) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error reading config: {}", err);
+            std::process::exit(1);
+        }
     };
-    let quote = match args.quote {
-        Some(c) => c as u8,
-        None => b'"',
+    let registry = Registry::default();
+    if args.formats {
+        print_formats(&registry);
+        return;
+    }
+    if args.dashboard {
+        run_dashboard(&config, &registry);
+        return;
+    }
+    let source = match args.format {
+        Some(ref name) => registry.source(name),
+        None => args
+            .file
+            .as_ref()
+            .and_then(|file| registry.source_for_path(Path::new(file)))
+            .or_else(|| registry.source("csv")),
     };
-    let (header, rows) = match args.file {
-        Some(ref file) => match read_csv_from_file(Path::new(file), delimiter, quote) {
-            Ok(viewer) => viewer,
+    let source = match source {
+        Some(source) => source,
+        None => {
+            eprintln!("Unknown format, see --formats for available formats");
+            std::process::exit(1);
+        }
+    };
+    let profile = match config.profile(args.profile.as_deref(), args.file.as_deref().map(Path::new))
+    {
+        Ok(profile) => profile.cloned().unwrap_or_default(),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let delimiter = args.delimiter.or(profile.delimiter).or(config.delimiter);
+    let quote = args.quote.or(profile.quote).or(config.quote);
+    let sheet = match args.file {
+        Some(ref file)
+            if args.sheet.is_none()
+                && source.name() == "spreadsheet"
+                && io::stdin().is_terminal() =>
+        {
+            choose_sheet(Path::new(file))
+        }
+        _ => args.sheet.clone(),
+    };
+    // detect the format of delimited files up front, so that it is also used for saving
+    let detected = match args.file {
+        #[cfg(feature = "csv")]
+        Some(ref file)
+            if delimiter.is_none() && source.delimited(&Default::default()).is_some() =>
+        {
+            Some(table_viewer::csv::detect_format(Path::new(file)))
+        }
+        _ => None,
+    };
+    let max_rows = args.max_rows.unwrap_or(config.max_rows);
+    let options = LoadOptions {
+        delimiter: delimiter
+            .map(|c| c as u8)
+            .or(detected.map(|(delimiter, _)| delimiter)),
+        quote: quote.map(|c| c as u8).or(detected.map(|(_, quote)| quote)),
+        sheet,
+        // one more row tells whether the table was cut off
+        max_rows: (max_rows > 0).then(|| max_rows + 1),
+    };
+    // without file and input, choose a file interactively
+    let pick_file = cfg!(feature = "csv")
+        && args.file.is_none()
+        && !args.describe
+        && args.assertions.is_empty()
+        && (args.recent || io::stdin().is_terminal());
+    #[cfg(feature = "csv")]
+    if let Some(ref file) = args.file {
+        if args.lazy && !args.describe && args.assertions.is_empty() {
+            page_file(
+                Path::new(file),
+                source.delimited(&options),
+                &registry,
+                &args.renderer,
+            );
+            return;
+        }
+    }
+    let start = Instant::now();
+    let (header, mut rows) = match args.file {
+        Some(ref file) => match File::open(file)
+            .map_err(|err| err.into())
+            .and_then(|f| source.load(Box::new(BufReader::new(f)), &options))
+        {
+            Ok(table) => table,
             Err(err) => {
                 eprintln!("Error reading file '{:?}': {}", file, err);
                 std::process::exit(1);
             }
         },
-        None => match read_csv_from_stdin(delimiter, quote) {
-            Ok(viewer) => viewer,
+        None if pick_file => (vec!["#".to_string()], Vec::new()),
+        None => match source.load(Box::new(io::stdin()), &options) {
+            Ok(table) => table,
             Err(err) => {
                 eprintln!("Error reading from stdin: {}", err);
                 std::process::exit(1);
             }
         },
     };
-    let mut table_viewer = TableViewer::new(TerminalTableRenderer {}, header, rows);
+    info!(
+        file = args.file.as_deref().unwrap_or("stdin"),
+        format = source.name(),
+        rows = rows.len(),
+        columns = header.len().saturating_sub(1),
+        millis = start.elapsed().as_millis() as u64,
+        "loaded"
+    );
+    let cut_off = max_rows > 0 && rows.len() > max_rows;
+    if cut_off {
+        rows.truncate(max_rows);
+        if args.describe || !args.assertions.is_empty() {
+            eprintln!("Only the first {} rows were read, see --max-rows", max_rows);
+        }
+    }
+    if args.describe {
+        let profiles = stats::describe(&header, &rows);
+        if args.json {
+            println!("{}", stats::describe_json(rows.len(), &profiles));
+        } else {
+            print!("{}", stats::describe_text(&profiles));
+        }
+    }
+    if !args.assertions.is_empty() {
+        std::process::exit(check_assertions(&args.assertions, &header, &rows));
+    }
+    if args.describe {
+        return;
+    }
+    let renderer = match registry.renderer(&args.renderer) {
+        Some(renderer) => renderer,
+        None => {
+            eprintln!("Unknown renderer '{}', see --formats", args.renderer);
+            std::process::exit(1);
+        }
+    };
+    let mut history = match history::default_path().map(|path| History::load(&path)) {
+        Some(Ok(history)) => history,
+        Some(Err(err)) => {
+            eprintln!("Ignoring history: {}", err);
+            History::default()
+        }
+        None => History::default(),
+    };
+    if let Some(ref file) = args.file {
+        history.record(Path::new(file));
+        if let Err(err) = history.save() {
+            eprintln!("Error saving history: {}", err);
+        }
+    }
+    // printing instead of viewing if the output is redirected
+    let interactive = !args.print && io::stdout().is_terminal();
+    let theme = args.theme.as_deref().or(config.theme.as_deref());
+    let theme = match theme.unwrap_or("auto") {
+        // without colors there is no need to ask the terminal
+        "auto" if config.color && interactive => Theme::detect().unwrap_or_default(),
+        "auto" => Theme::default(),
+        name => match name.parse() {
+            Ok(theme) => theme,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+        },
+    };
+    let rules = match config.rules.as_deref().unwrap_or("none").parse() {
+        Ok(rules) => rules,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let table_viewer = match args.header_rows {
+        1 => TableViewer::new(renderer, header, rows),
+        2 => TableViewer::new(renderer, header, rows).with_group_header(),
+        _ => {
+            eprintln!("Error: --header-rows must be 1 or 2");
+            std::process::exit(1);
+        }
+    };
+    let table_viewer = table_viewer
+        .with_aliases(config.aliases)
+        .with_keys(config.keys)
+        .with_padding(config.padding)
+        .with_history(history)
+        .with_load_time(start.elapsed())
+        .with_row_cap(cut_off.then_some(max_rows))
+        .with_search_case(match (args.ignore_case, args.smart_case) {
+            (true, _) => SearchCase::Insensitive,
+            (_, true) => SearchCase::Smart,
+            _ => SearchCase::Sensitive,
+        })
+        .with_save_format(source.delimited(&options), config.backup)
+        .with_frozen_columns(args.freeze_cols)
+        .with_color(config.color)
+        .with_theme(theme)
+        .with_rules(rules);
+    let table_viewer = match table_viewer.with_profile(&profile) {
+        Ok(table_viewer) => table_viewer,
+        Err(err) => {
+            eprintln!("Error applying profile: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let table_viewer = match args.schema {
+        Some(ref path) => match Schema::from_file(Path::new(path)) {
+            Ok(schema) => table_viewer.with_schema(&schema),
+            Err(err) => {
+                eprintln!("Error reading schema '{:?}': {}", path, err);
+                std::process::exit(1);
+            }
+        },
+        None => table_viewer,
+    };
+    // after the profile and schema, whose column types decide the order
+    let table_viewer = table_viewer.with_sort(profile.sort.as_deref().or(config.sort.as_deref()));
+    let table_viewer = match args.file {
+        Some(ref file) => match Session::load(Path::new(file)) {
+            Ok(session) => table_viewer.with_session(session),
+            Err(err) => {
+                eprintln!("Error reading notes: {}", err);
+                std::process::exit(1);
+            }
+        },
+        None => table_viewer,
+    };
+    #[cfg(feature = "csv")]
+    let table_viewer = if pick_file && args.recent {
+        table_viewer.with_recent_files()
+    } else if pick_file {
+        match table_viewer.with_file_picker(Path::new(".")) {
+            Ok(table_viewer) => table_viewer,
+            Err(err) => {
+                eprintln!("Error listing files: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        table_viewer
+    };
+    #[cfg(feature = "scripting")]
+    let table_viewer = match config.script {
+        Some(ref path) => match ScriptHost::from_file(path) {
+            Ok(script) => table_viewer.with_script(script),
+            Err(err) => {
+                eprintln!("Error loading script '{:?}': {}", path, err);
+                std::process::exit(1);
+            }
+        },
+        None => table_viewer,
+    };
+    #[cfg(not(feature = "scripting"))]
+    if config.script.is_some() {
+        eprintln!("Ignoring script: compiled without scripting support");
+    }
+    let mut table_viewer = table_viewer;
+    if !interactive {
+        match table_viewer.print(&mut io::stdout().lock()) {
+            // e.g. piped into head
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            _ => return,
+        }
+    }
     match table_viewer.run() {
         Ok(_) => (),
         Err(err) => {
@@ -0,0 +1,59 @@
+//! Navigable list panes displayed instead of the table.
+use crate::renderer::RenderingAction;
+
+/// A full-screen list of entries pointing to table cells.
+pub struct ListPane {
+    pub title: String,
+    pub entries: Vec<PaneEntry>,
+    pub selected: usize,
+    pub offset: usize,
+}
+
+/// A single pane entry: the cell it refers to and the text to display.
+/// Entries without row refer to the header.
+#[derive(Debug, Clone)]
+pub struct PaneEntry {
+    pub row: Option<usize>,
+    pub col: usize,
+    pub text: String,
+    /// Shown emphasized, e.g. differing values when comparing rows
+    pub highlight: bool,
+}
+
+impl ListPane {
+    pub fn new(title: String, entries: Vec<PaneEntry>) -> Self {
+        ListPane {
+            title,
+            entries,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&PaneEntry> {
+        self.entries.get(self.selected)
+    }
+
+    /// Moves the selection down; `height` is the number of entries that fit on the screen.
+    pub fn move_down(&mut self, height: usize) -> RenderingAction {
+        if self.selected + 1 >= self.entries.len() {
+            return RenderingAction::None;
+        }
+        self.selected += 1;
+        if self.selected >= self.offset + height {
+            self.offset += 1;
+        }
+        RenderingAction::Pane
+    }
+
+    pub fn move_up(&mut self) -> RenderingAction {
+        if self.selected == 0 {
+            return RenderingAction::None;
+        }
+        self.selected -= 1;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        }
+        RenderingAction::Pane
+    }
+}
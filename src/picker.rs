@@ -0,0 +1,160 @@
+//! Choosing a table file from a directory or the recently opened ones, with a preview of the
+//! highlighted file.
+use crate::csv::{detect_format, read_csv_head};
+use crate::registry::Table;
+use crate::renderer::{fixed_width, RenderingAction, TerminalTableRenderer};
+use crate::state::{CharCoord, TableState};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use termion::style;
+
+/// Extensions of the files offered.
+const EXTENSIONS: [&str; 4] = ["csv", "tsv", "tab", "txt"];
+
+/// Number of data rows shown in the preview.
+const PREVIEW_ROWS: usize = 5;
+
+pub struct FilePicker {
+    pub title: String,
+    pub files: Vec<PathBuf>,
+    pub selected: usize,
+    pub offset: usize,
+    // header and first rows of the selected file or an error message
+    preview: Result<Table, String>,
+    // directory whose files are listed by name, None to list full paths
+    dir: Option<PathBuf>,
+}
+
+impl FilePicker {
+    /// Lists the CSV-like files in the directory.
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            })
+            .collect();
+        files.sort();
+        let title = format!("Open file in {}", dir.display());
+        Ok(FilePicker::with_files(
+            title,
+            files,
+            Some(dir.to_path_buf()),
+        ))
+    }
+
+    /// Lists the given files, e.g. the recently opened ones, with their full paths.
+    pub fn recent(files: Vec<PathBuf>) -> Self {
+        FilePicker::with_files("Recent files".to_string(), files, None)
+    }
+
+    fn with_files(title: String, files: Vec<PathBuf>, dir: Option<PathBuf>) -> Self {
+        let mut picker = FilePicker {
+            title,
+            files,
+            selected: 0,
+            offset: 0,
+            preview: Ok(Default::default()),
+            dir,
+        };
+        picker.load_preview();
+        picker
+    }
+
+    pub fn selected_file(&self) -> Option<&Path> {
+        self.files.get(self.selected).map(PathBuf::as_path)
+    }
+
+    /// Number of file names shown at once in a terminal of the given height.
+    pub fn list_height(height: usize) -> usize {
+        // title, separator, preview header and rows
+        height.saturating_sub(PREVIEW_ROWS + 3).max(1)
+    }
+
+    /// Moves the selection down; `height` is the number of file names that fit on the screen.
+    pub fn move_down(&mut self, height: usize) -> RenderingAction {
+        if self.selected + 1 >= self.files.len() {
+            return RenderingAction::None;
+        }
+        self.selected += 1;
+        if self.selected >= self.offset + height {
+            self.offset += 1;
+        }
+        self.load_preview();
+        RenderingAction::Pane
+    }
+
+    pub fn move_up(&mut self) -> RenderingAction {
+        if self.selected == 0 {
+            return RenderingAction::None;
+        }
+        self.selected -= 1;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        }
+        self.load_preview();
+        RenderingAction::Pane
+    }
+
+    fn load_preview(&mut self) {
+        self.preview = match self.selected_file() {
+            Some(path) => File::open(path)
+                .map_err(|err| err.into())
+                .and_then(|file| {
+                    let (delimiter, quote) = detect_format(path);
+                    read_csv_head(BufReader::new(file), delimiter, quote, PREVIEW_ROWS)
+                })
+                .map_err(|err| err.to_string()),
+            None => Err(match &self.dir {
+                Some(dir) => format!("No CSV or TSV files in {}", dir.display()),
+                None => "No files".to_string(),
+            }),
+        };
+    }
+
+    /// Title, file names and preview of the selected file, fitting into the given size.
+    pub fn lines(&self, size: CharCoord) -> Vec<String> {
+        let height = FilePicker::list_height(size.y);
+        let mut lines = vec![format!(
+            "{}{}{}",
+            style::Bold,
+            fixed_width(&self.title, size.x),
+            style::Reset
+        )];
+        let stop = (self.offset + height).min(self.files.len());
+        for (i, file) in self.files.iter().enumerate().take(stop).skip(self.offset) {
+            let name = match &self.dir {
+                Some(_) => file.file_name().unwrap_or_default().to_string_lossy(),
+                None => file.to_string_lossy(),
+            };
+            let name = fixed_width(&name, size.x);
+            if i == self.selected {
+                lines.push(format!("{}{}{}", style::Invert, name, style::Reset));
+            } else {
+                lines.push(name);
+            }
+        }
+        lines.resize(height + 1, String::new());
+        lines.push("─".repeat(size.x));
+        match &self.preview {
+            Ok((header, rows)) if header.len() > 1 => {
+                let preview_size = CharCoord {
+                    x: size.x,
+                    y: PREVIEW_ROWS + 2,
+                };
+                let state = TableState::new(header.clone(), rows.clone(), preview_size);
+                lines.extend(TerminalTableRenderer {}.frame_lines(&state));
+            }
+            Ok(_) => lines.push(String::new()),
+            Err(err) => lines.push(fixed_width(err, size.x)),
+        }
+        lines.truncate(size.y);
+        lines
+    }
+}
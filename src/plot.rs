@@ -0,0 +1,73 @@
+//! Scatter plots drawn with Braille characters, each of which shows 2 x 4 dots.
+
+/// Bits of the dots of a Braille character by column and row.
+const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// Smallest and largest value.
+fn range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    })
+}
+
+// Position of the value among `dots` dots between min and max, in the middle if all are equal.
+fn scale(value: f64, (min, max): (f64, f64), dots: usize) -> usize {
+    let share = match max > min {
+        true => (value - min) / (max - min),
+        false => 0.5,
+    };
+    (share * (dots - 1) as f64).round() as usize
+}
+
+/// Draws the points in `width` x `height` characters, with the largest y at the top.
+pub fn braille(points: &[(f64, f64)], width: usize, height: usize) -> Vec<String> {
+    let mut grid = vec![vec![0u32; width]; height];
+    if width > 0 && height > 0 {
+        let x_range = range(points.iter().map(|&(x, _)| x));
+        let y_range = range(points.iter().map(|&(_, y)| y));
+        for &(x, y) in points {
+            let x = scale(x, x_range, 2 * width);
+            let y = 4 * height - 1 - scale(y, y_range, 4 * height);
+            grid[y / 4][x / 2] |= DOTS[x % 2][y % 4];
+        }
+    }
+    grid.into_iter()
+        .map(|line| {
+            line.into_iter()
+                .map(|bits| match bits {
+                    0 => ' ',
+                    bits => char::from_u32(0x2800 + bits).unwrap_or(' '),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Scatter plot of the points filling `width` x `height` characters, with the y axis labeled
+/// with the largest and smallest y on the left and the x axis with the smallest and largest x
+/// below.
+pub fn scatter(points: &[(f64, f64)], width: usize, height: usize) -> Vec<String> {
+    let (x_min, x_max) = range(points.iter().map(|&(x, _)| x));
+    let (y_min, y_max) = range(points.iter().map(|&(_, y)| y));
+    let (y_max, y_min) = (y_max.to_string(), y_min.to_string());
+    let margin = y_max.chars().count().max(y_min.chars().count());
+    let plot_width = width.saturating_sub(margin + 1);
+    let plot_height = height.saturating_sub(2);
+    let mut lines: Vec<String> = braille(points, plot_width, plot_height)
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let label = match i {
+                0 => y_max.as_str(),
+                i if i + 1 == plot_height => y_min.as_str(),
+                _ => "",
+            };
+            format!("{:>margin$}│{}", label, line)
+        })
+        .collect();
+    lines.push(format!("{:margin$}└{}", "", "─".repeat(plot_width)));
+    let (x_min, x_max) = (x_min.to_string(), x_max.to_string());
+    let gap = plot_width.saturating_sub(x_min.chars().count() + x_max.chars().count());
+    lines.push(format!("{:margin$} {}{:gap$}{}", "", x_min, "", x_max));
+    lines
+}
@@ -0,0 +1,119 @@
+//! Registration points for input formats and renderers.
+//!
+//! Downstream crates can add their own [`DataSource`]s and [`TableRenderer`]s to a
+//! [`Registry`] without modifying this crate.
+#[cfg(feature = "csv")]
+use crate::csv::CsvSource;
+#[cfg(feature = "jsonl")]
+use crate::jsonl::JsonLinesSource;
+use crate::renderer::{TableRenderer, TerminalTableRenderer};
+#[cfg(feature = "spreadsheet")]
+use crate::spreadsheet::SpreadsheetSource;
+use std::error::Error;
+use std::io::Read;
+use std::path::Path;
+
+/// Header and data rows of a table.
+pub type Table = (Vec<String>, Vec<Vec<String>>);
+
+/// Options passed to data sources when loading a table.
+#[derive(Debug, Default)]
+pub struct LoadOptions {
+    /// Field delimiter overriding the source's default
+    pub delimiter: Option<u8>,
+    /// Quote character overriding the source's default
+    pub quote: Option<u8>,
+    /// Sheet of a workbook, by default the first one
+    pub sheet: Option<String>,
+    /// Rows to read at most, all if None
+    pub max_rows: Option<usize>,
+}
+
+/// An input format that can be turned into a table.
+pub trait DataSource {
+    /// Unique name used to select the format, e.g. via `--format`.
+    fn name(&self) -> &str;
+    /// File extensions (without dot) used to detect the format.
+    fn extensions(&self) -> &[&str];
+    fn load(&self, reader: Box<dyn Read>, options: &LoadOptions) -> Result<Table, Box<dyn Error>>;
+    /// Delimiter and quote character for writing a loaded table back in this format,
+    /// None if the format cannot be written.
+    fn delimited(&self, _options: &LoadOptions) -> Option<(u8, u8)> {
+        None
+    }
+}
+
+type RendererFactory = Box<dyn Fn() -> Box<dyn TableRenderer>>;
+
+/// Known data sources and renderers.
+pub struct Registry {
+    sources: Vec<Box<dyn DataSource>>,
+    renderers: Vec<(String, RendererFactory)>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Registry {
+            sources: Vec::new(),
+            renderers: Vec::new(),
+        }
+    }
+
+    /// Registers a data source; sources registered later take precedence.
+    pub fn register_source(&mut self, source: Box<dyn DataSource>) {
+        self.sources.insert(0, source);
+    }
+
+    /// Registers a renderer factory; renderers registered later take precedence.
+    pub fn register_renderer<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn TableRenderer> + 'static,
+    {
+        self.renderers
+            .insert(0, (name.to_string(), Box::new(factory)));
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &dyn DataSource> {
+        self.sources.iter().map(|source| source.as_ref())
+    }
+
+    pub fn renderer_names(&self) -> impl Iterator<Item = &str> {
+        self.renderers.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn source(&self, name: &str) -> Option<&dyn DataSource> {
+        self.sources().find(|source| source.name() == name)
+    }
+
+    /// Finds the data source matching the extension of the given path.
+    pub fn source_for_path(&self, path: &Path) -> Option<&dyn DataSource> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        self.sources()
+            .find(|source| source.extensions().contains(&extension.as_str()))
+    }
+
+    pub fn renderer(&self, name: &str) -> Option<Box<dyn TableRenderer>> {
+        self.renderers
+            .iter()
+            .find(|(renderer_name, _)| renderer_name == name)
+            .map(|(_, factory)| factory())
+    }
+}
+
+impl Default for Registry {
+    /// Creates a registry with the built-in sources enabled by features and the terminal renderer.
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        #[cfg(feature = "csv")]
+        registry.register_source(Box::new(CsvSource::new("tsv", b'\t', &["tsv", "tab"])));
+        #[cfg(feature = "csv")]
+        registry.register_source(Box::new(CsvSource::new("csv", b',', &["csv"])));
+        #[cfg(feature = "jsonl")]
+        registry.register_source(Box::new(JsonLinesSource));
+        #[cfg(feature = "spreadsheet")]
+        registry.register_source(Box::new(SpreadsheetSource));
+        registry.register_renderer("terminal", || Box::new(TerminalTableRenderer {}));
+        registry
+    }
+}
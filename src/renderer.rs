@@ -1,25 +1,56 @@
 //! Table rendering.
+use crate::cell::{display_text, display_width};
+use crate::input;
 use crate::state::CharCoord;
 use crate::state::TableState;
-use std::cmp::min;
-use termion::style;
+use std::cmp::{min, Ordering};
+use std::iter::once;
+use termion::color::{self, AnsiValue};
+use termion::{screen, style};
 
+#[derive(Debug)]
 pub enum RenderingAction {
     MoveCursor,
+    Status,
     Rerender,
     Command,
+    Pane,
     Reset,
     None,
 }
 
+impl RenderingAction {
+    /// Combines two actions into the one that covers the effects of both.
+    pub fn merge(self, other: RenderingAction) -> RenderingAction {
+        if other.priority() > self.priority() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn priority(&self) -> u8 {
+        match self {
+            RenderingAction::None => 0,
+            RenderingAction::MoveCursor => 1,
+            RenderingAction::Status => 2,
+            RenderingAction::Command => 3,
+            RenderingAction::Rerender => 4,
+            RenderingAction::Pane => 5,
+            RenderingAction::Reset => 6,
+        }
+    }
+}
+
 /// Rendering interface: receives table state and generates rendering string.
 pub trait TableRenderer {
     fn render(&self, ts: &TableState, action: &RenderingAction) -> Option<String> {
         match action {
             RenderingAction::Rerender => Some(self.full_render(ts)),
             RenderingAction::MoveCursor => Some(self.go_to_cur_pos(ts)),
+            RenderingAction::Status => Some(self.render_status(ts)),
             RenderingAction::Command => Some(self.render_command(ts)),
-            RenderingAction::Reset => Some(self.reset_window()),
+            RenderingAction::Pane => Some(self.render_pane(ts)),
             _ => None,
         }
     }
@@ -27,48 +58,237 @@ pub trait TableRenderer {
     fn full_render(&self, ts: &TableState) -> String;
     fn go_to_cur_pos(&self, ts: &TableState) -> String;
     fn render_command(&self, ts: &TableState) -> String;
+    fn render_status(&self, ts: &TableState) -> String;
+    fn render_pane(&self, ts: &TableState) -> String;
+    /// Clears the window and leaves the alternate screen, showing what was there before.
     fn reset_window(&self) -> String;
+    /// Sets up the terminal for the viewer: switches to the alternate screen, so that the
+    /// shell's contents come back on exit, turns on bracketed paste and the keyboard protocol
+    /// and sets the window title, remembering the previous one.
+    fn init(&self, ts: &TableState) -> String {
+        format!(
+            "{}{}{}{}{}",
+            screen::ToAlternateScreen,
+            input::ENABLE_PASTE,
+            input::ENABLE_KEYBOARD_PROTOCOL,
+            self.save_title(),
+            self.render_title(ts)
+        )
+    }
+    /// Undoes `init` when the viewer quits or is suspended.
+    fn teardown(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            self.restore_title(),
+            input::DISABLE_PASTE,
+            input::DISABLE_KEYBOARD_PROTOCOL,
+            self.reset_window()
+        )
+    }
+    /// Remembers the terminal's window title, so that it can be restored on exit.
+    fn save_title(&self) -> String;
+    fn render_title(&self, ts: &TableState) -> String;
+    fn restore_title(&self) -> String;
+}
+
+impl TableRenderer for Box<dyn TableRenderer> {
+    fn render(&self, ts: &TableState, action: &RenderingAction) -> Option<String> {
+        self.as_ref().render(ts, action)
+    }
+    fn window_size(&self) -> CharCoord {
+        self.as_ref().window_size()
+    }
+    fn full_render(&self, ts: &TableState) -> String {
+        self.as_ref().full_render(ts)
+    }
+    fn go_to_cur_pos(&self, ts: &TableState) -> String {
+        self.as_ref().go_to_cur_pos(ts)
+    }
+    fn render_command(&self, ts: &TableState) -> String {
+        self.as_ref().render_command(ts)
+    }
+    fn render_status(&self, ts: &TableState) -> String {
+        self.as_ref().render_status(ts)
+    }
+    fn render_pane(&self, ts: &TableState) -> String {
+        self.as_ref().render_pane(ts)
+    }
+    fn reset_window(&self) -> String {
+        self.as_ref().reset_window()
+    }
+    fn save_title(&self) -> String {
+        self.as_ref().save_title()
+    }
+    fn render_title(&self, ts: &TableState) -> String {
+        self.as_ref().render_title(ts)
+    }
+    fn restore_title(&self) -> String {
+        self.as_ref().restore_title()
+    }
 }
 
 /// A table renderer for TTY terminals.
 pub struct TerminalTableRenderer;
 
 impl TerminalTableRenderer {
+    fn clear_window(&self) -> String {
+        format!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1))
+    }
+
     fn generate_frame(&self, ts: &TableState) -> String {
-        let mut lines: Vec<String> = Vec::with_capacity(ts.rows.len() + 1);
-        lines.push(self.format_header(ts, &ts.header));
-        let stop = min(ts.offsets.row + ts.terminal_size.y - 1, ts.rows.len());
-        lines.extend(
-            (ts.rows[ts.offsets.row..stop])
-                .iter()
-                .map(|row| self.format_row(ts, row)),
-        );
-        format!("{}", lines.join("\r\n"))
+        self.frame_lines(ts).join("\r\n")
+    }
+
+    /// Header and displayed data rows, each at most as wide as the terminal.
+    pub fn frame_lines(&self, ts: &TableState) -> Vec<String> {
+        let stop = min(ts.offsets.row + ts.displayable_data_rows(), ts.row_count());
+        let mut lines: Vec<String> = Vec::with_capacity(stop - ts.offsets.row + 3);
+        lines.extend(self.group_line(ts));
+        lines.push(self.format_header(ts, &sort_indicators(ts)));
+        if let Some(rule) = self.header_rule(ts) {
+            lines.push(rule);
+        }
+        lines.extend((ts.offsets.row..stop).map(|i| {
+            let line = self.format_row(ts, &ts.edited_row(ts.view[i]), true);
+            match ts.is_highlighted(ts.view[i]) {
+                true => format!("{}{}{}", style::Bold, line, style::Reset),
+                false => line,
+            }
+        }));
+        lines
+    }
+
+    fn status_line(&self, ts: &TableState) -> String {
+        format!(
+            "{}{}{}{}",
+            termion::cursor::Goto(1, ts.terminal_size.y as u16),
+            style::Invert,
+            fixed_width(&ts.status_line(), ts.terminal_size.x),
+            style::Reset
+        )
     }
 
     fn format_header(&self, ts: &TableState, row: &[String]) -> String {
         format!(
             "{}{}{}",
             style::Bold,
-            self.format_row(ts, row),
+            self.format_row(ts, row, false),
             style::Reset
         )
     }
-    fn format_row(&self, ts: &TableState, row: &[String]) -> String {
+    // Names of the column groups above the header, each underlined across the shown columns of
+    // its group. Columns scrolled out of view shorten the span, so that it starts with the
+    // first shown column.
+    fn group_line(&self, ts: &TableState) -> Option<String> {
+        if !ts.has_groups() {
+            return None;
+        }
+        // start and end on the screen and group of runs of neighboring columns
+        let mut spans: Vec<(usize, usize, &str)> = Vec::new();
+        let mut previous: Option<usize> = None;
+        for position in (0..ts.frozen).chain(ts.offsets.col..ts.columns.len()) {
+            let column = &ts.columns[position];
+            if column.width == 0 {
+                continue;
+            }
+            let x_offset = match position < ts.frozen {
+                true => 0,
+                false => ts.x_offset(),
+            };
+            let start = column.index - x_offset;
+            if start >= ts.terminal_size.x {
+                break;
+            }
+            let end = min(start + column.width, ts.terminal_size.x);
+            let group = ts.groups.get(ts.order[position]).map_or("", String::as_str);
+            // neighbors in the table, not only on the screen next to the frozen columns
+            let adjacent = previous.is_some_and(|previous| {
+                (previous + 1..position).all(|between| ts.columns[between].width == 0)
+            });
+            match spans.last_mut() {
+                Some(span) if span.2 == group && adjacent => span.1 = end,
+                _ => spans.push((start, end, group)),
+            }
+            previous = Some(position);
+        }
+        let mut line = String::new();
+        let mut x = 0;
+        for (start, end, group) in spans {
+            line.push_str(&" ".repeat(start - x));
+            x = end;
+            match end - start {
+                // a space separates the groups
+                width if width > 1 && !group.is_empty() => line.push_str(&format!(
+                    "{}{}{} ",
+                    style::Underline,
+                    fixed_width(group, width - 1),
+                    style::NoUnderline
+                )),
+                width => line.push_str(&" ".repeat(width)),
+            }
+        }
+        Some(format!("{}{}{}", style::Bold, line, style::Reset))
+    }
+
+    // Faint line under the header, crossing the line after the frozen columns.
+    fn header_rule(&self, ts: &TableState) -> Option<String> {
+        let (horizontal, _, crossing) = ts.rules.chars()?;
+        let mut line = vec![horizontal; ts.terminal_size.x];
+        if let Some(x) = frozen_rule_x(ts).filter(|&x| x < line.len()) {
+            line[x] = crossing;
+        }
+        Some(format!(
+            "{}{}{}",
+            style::Faint,
+            line.into_iter().collect::<String>(),
+            style::Reset
+        ))
+    }
+
+    // Formats the visible cells of a row; for data rows (`align_numbers`), numeric columns are
+    // right-aligned and matches of the last search are highlighted.
+    fn format_row(&self, ts: &TableState, row: &[String], align_numbers: bool) -> String {
         let mut cells: Vec<String> = Vec::with_capacity(ts.columns.len() - ts.offsets.col);
-        for i in ts.offsets.col..ts.columns.len() {
-            let column = &ts.columns[i];
-            let value = &row[i];
-            if column.index >= ts.terminal_size.x + ts.x_offset() {
+        let accent = ts.color.then(|| ts.theme.accent());
+        let rule = ts.rules.chars().map(|(_, vertical, _)| vertical);
+        let last_frozen = last_frozen_position(ts);
+        for position in (0..ts.frozen).chain(ts.offsets.col..ts.columns.len()) {
+            let (column, col) = (&ts.columns[position], ts.order[position]);
+            let (value, col_type) = (&row[col], &ts.types[col]);
+            // frozen columns stay in place while the others scroll
+            let x_offset = match position < ts.frozen {
+                true => 0,
+                false => ts.x_offset(),
+            };
+            if column.index >= ts.terminal_size.x + x_offset {
                 break;
             }
-            let last_col_pos = column.index + column.width - ts.x_offset();
-            let width = if last_col_pos > ts.terminal_size.x {
+            // hidden column
+            if column.width == 0 {
+                continue;
+            }
+            let last_col_pos = column.index + column.width - x_offset;
+            let mut width = if last_col_pos > ts.terminal_size.x {
                 column.width - (last_col_pos - ts.terminal_size.x)
             } else {
                 column.width
             };
-            cells.push(fixed_width(value, width));
+            // the rule after the frozen columns takes the last space of the last one
+            let rule = rule.filter(|_| Some(position) == last_frozen && width > 0);
+            if rule.is_some() {
+                width -= 1;
+            }
+            let cell = if align_numbers && col_type.is_numeric() {
+                clipped(&align_right(value, column.width, ts.padding), width, accent)
+            } else {
+                clipped(value, width, accent)
+            };
+            if align_numbers && ts.is_match(col, value) {
+                cells.push(format!("{}{}{}", style::Invert, cell, style::NoInvert));
+            } else {
+                cells.push(cell);
+            }
+            cells.extend(rule.map(String::from));
         }
         cells.join("")
     }
@@ -76,45 +296,294 @@ impl TerminalTableRenderer {
 
 impl TableRenderer for TerminalTableRenderer {
     fn window_size(&self) -> CharCoord {
-        let (x, y) = termion::terminal_size().unwrap();
+        // without a terminal, e.g. when the output is redirected
+        let (x, y) = termion::terminal_size().unwrap_or((80, 24));
         CharCoord {
             x: x as usize,
             y: y as usize,
         }
     }
 
+    // Clears the alternate screen first, for terminals without one.
     fn reset_window(&self) -> String {
-        format!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1))
+        format!("{}{}", self.clear_window(), screen::ToMainScreen)
+    }
+
+    // Pushes the title on the terminal's title stack (XTWINOPS), supported by xterm, VTE and
+    // most other terminals; others ignore it.
+    fn save_title(&self) -> String {
+        "\x1b[22;0t".to_string()
+    }
+
+    // Sets the window title with OSC 2, leaving out control characters that would end it early.
+    fn render_title(&self, ts: &TableState) -> String {
+        let title: String = ts
+            .window_title()
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        format!("\x1b]2;{}\x07", title)
+    }
+
+    fn restore_title(&self) -> String {
+        "\x1b[23;0t".to_string()
     }
 
     fn full_render(&self, ts: &TableState) -> String {
-        format!("{}{}{}", self.reset_window(), self.generate_frame(ts), self.go_to_cur_pos(ts))
+        if ts.record_view.is_some() {
+            let mut lines = ts.record_lines();
+            lines.resize(ts.displayable_data_rows() + 1, String::new());
+            let lines: Vec<String> = lines
+                .iter()
+                .map(|line| fixed_width(line, ts.terminal_size.x))
+                .collect();
+            return format!(
+                "{}{}{}{}{}{}{}",
+                self.clear_window(),
+                termion::cursor::Hide,
+                style::Bold,
+                lines[0],
+                style::Reset,
+                lines[1..]
+                    .iter()
+                    .map(|line| format!("\r\n{}", line))
+                    .collect::<String>(),
+                self.status_line(ts)
+            );
+        }
+        if ts.presentation.is_some() {
+            return format!(
+                "{}{}{}",
+                self.clear_window(),
+                termion::cursor::Hide,
+                self.generate_frame(ts)
+            );
+        }
+        format!(
+            "{}{}{}{}{}",
+            self.clear_window(),
+            termion::cursor::Show,
+            self.generate_frame(ts),
+            self.status_line(ts),
+            self.go_to_cur_pos(ts)
+        )
+    }
+
+    fn render_status(&self, ts: &TableState) -> String {
+        format!("{}{}", self.status_line(ts), self.go_to_cur_pos(ts))
     }
 
     fn go_to_cur_pos(&self, ts: &TableState) -> String {
         format!(
             "{}",
-            termion::cursor::Goto(
-                (ts.columns[ts.offsets.col + ts.cur_pos.col].index - ts.x_offset() + 1) as u16,
-                ts.cur_pos.row as u16 + 1
-            )
+            termion::cursor::Goto((ts.cursor_x() + 1) as u16, ts.cursor_y() as u16 + 1)
         )
     }
 
     fn render_command(&self, ts: &TableState) -> String {
+        let (command, before_cursor): (String, String) = match &ts.prompt {
+            Some(prompt) => (format!("{} (y/n) ", prompt.question), String::new()),
+            None => (
+                ts.command_buffer.iter().collect(),
+                ts.command_buffer[..ts.command_cursor()].iter().collect(),
+            ),
+        };
+        // the suggested completion is shown dimmed after the cursor
+        let suggestion = match ts.command_suggestion().filter(|_| ts.prompt.is_none()) {
+            Some(suffix) => {
+                let width = ts
+                    .terminal_size
+                    .x
+                    .saturating_sub(display_width(&command) + 1);
+                format!(
+                    "{}{}{}",
+                    style::Faint,
+                    suffix.chars().take(width).collect::<String>(),
+                    style::Reset,
+                )
+            }
+            None => String::new(),
+        };
+        // the cursor stays at the end of a prompt and goes to the edit point of a command
+        let cursor = match ts.prompt {
+            Some(_) => String::new(),
+            None => termion::cursor::Goto(
+                display_width(&before_cursor) as u16 + 1,
+                ts.terminal_size.y as u16,
+            )
+            .to_string(),
+        };
         format!(
-            "{}{}{}{}",
-            termion::cursor::Goto(1 as u16, ts.terminal_size.y as u16),
+            "{}{}{}{}{}{}",
+            termion::cursor::Goto(1, ts.terminal_size.y as u16),
             (0..ts.terminal_size.x).map(|_| " ").collect::<String>(),
-            termion::cursor::Goto(1 as u16, ts.terminal_size.y as u16),
-            ts.command_buffer.iter().collect::<String>(),
+            termion::cursor::Goto(1, ts.terminal_size.y as u16),
+            command,
+            suggestion,
+            cursor
+        )
+    }
+
+    fn render_pane(&self, ts: &TableState) -> String {
+        #[cfg(feature = "csv")]
+        if let Some(picker) = &ts.picker {
+            let row = picker.selected.saturating_sub(picker.offset) + 2;
+            return format!(
+                "{}{}{}",
+                self.clear_window(),
+                picker.lines(ts.terminal_size).join("\r\n"),
+                termion::cursor::Goto(1, row as u16)
+            );
+        }
+        if let Some(builder) = &ts.filter_builder {
+            // over the last rows of the table, leaving out the title if the terminal is too low
+            let lines = builder.lines(&ts.header, ts.terminal_size.x);
+            let skipped = lines.len().saturating_sub(ts.terminal_size.y);
+            let top = ts.terminal_size.y + skipped - lines.len();
+            let form: String = lines[skipped..]
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    format!("{}{}", termion::cursor::Goto(1, (top + i + 1) as u16), line)
+                })
+                .collect();
+            let (line, x) = builder.cursor();
+            return format!(
+                "{}{}{}",
+                self.full_render(ts),
+                form,
+                termion::cursor::Goto(x as u16 + 1, (top + line + 1 - skipped) as u16)
+            );
+        }
+        if let Some(editor) = &ts.column_editor {
+            let row = editor.selected.saturating_sub(editor.offset) + 2;
+            return format!(
+                "{}{}{}",
+                self.clear_window(),
+                editor
+                    .lines(&ts.header, &ts.widths(), ts.terminal_size)
+                    .join("\r\n"),
+                termion::cursor::Goto(1, row as u16)
+            );
+        }
+        let pane = match &ts.pane {
+            Some(pane) => pane,
+            None => return self.full_render(ts),
+        };
+        let width = ts.terminal_size.x;
+        let stop = min(pane.offset + ts.displayable_data_rows(), pane.entries.len());
+        let lines: Vec<String> = once(format!(
+            "{}{}{}",
+            style::Bold,
+            fixed_width(&pane.title, width),
+            style::Reset
+        ))
+        .chain(
+            pane.entries[pane.offset..stop]
+                .iter()
+                .map(|entry| match entry.highlight {
+                    true => format!(
+                        "{}{}{}",
+                        style::Bold,
+                        fixed_width(&entry.text, width),
+                        style::Reset
+                    ),
+                    false => fixed_width(&entry.text, width),
+                }),
+        )
+        .collect();
+        format!(
+            "{}{}{}",
+            self.clear_window(),
+            lines.join("\r\n"),
+            termion::cursor::Goto(1, (pane.selected - pane.offset + 2) as u16)
         )
     }
 }
 
-fn fixed_width(value: &str, col_width: usize) -> String {
-    if value.len() > col_width {
-        format!("{}…", &value[0..col_width - 1])
+/// The header and all displayed rows as plain text without escape sequences, for output that
+/// does not go to a terminal. Hidden columns are left out, numbers are right-aligned and cells
+/// wider than their column are truncated.
+pub fn plain_lines(ts: &TableState) -> Vec<String> {
+    let format_row = |row: &[String], align_numbers: bool| {
+        let mut line = String::new();
+        for (column, &col) in ts.columns.iter().zip(&ts.order) {
+            if column.width == 0 {
+                continue;
+            }
+            if align_numbers && ts.types[col].is_numeric() {
+                let value = align_right(&row[col], column.width, ts.padding);
+                line.push_str(&fixed_width(&value, column.width));
+            } else {
+                line.push_str(&fixed_width(&row[col], column.width));
+            }
+        }
+        line.trim_end().to_string()
+    };
+    let mut lines = Vec::with_capacity(ts.row_count() + 1);
+    lines.push(format_row(&sort_indicators(ts), false));
+    lines.extend((0..ts.row_count()).map(|i| format_row(&ts.edited_row(ts.view[i]), true)));
+    lines
+}
+
+// Position of the last shown frozen column, after which the rule is drawn.
+fn last_frozen_position(ts: &TableState) -> Option<usize> {
+    (0..ts.frozen)
+        .rev()
+        .find(|&position| ts.columns[position].width > 0)
+}
+
+// Screen column of the rule after the frozen columns, None if there are none.
+fn frozen_rule_x(ts: &TableState) -> Option<usize> {
+    let column = &ts.columns[last_frozen_position(ts)?];
+    Some(column.index + column.width - 1)
+}
+
+// Header with arrows after the names of the sorted columns, numbered if there are several.
+fn sort_indicators(ts: &TableState) -> Vec<String> {
+    let mut header = ts.header.clone();
+    for (i, &(col, direction)) in ts.sort_order.iter().enumerate() {
+        let arrow = if direction == Ordering::Less {
+            '▲'
+        } else {
+            '▼'
+        };
+        header[col].push(arrow);
+        if ts.sort_order.len() > 1 {
+            header[col].push_str(&(i + 1).to_string());
+        }
+    }
+    header
+}
+
+// Right-aligns a value within the column width minus padding if it fits.
+fn align_right(value: &str, col_width: usize, padding: usize) -> String {
+    let content_width = col_width.saturating_sub(padding);
+    if display_width(value) <= content_width {
+        format!("{:>width$}", value, width = content_width)
+    } else {
+        value.to_string()
+    }
+}
+
+// Like `fixed_width`, but with the ellipsis of truncated cells in the accent color, so that they
+// stand out from values that fill the column exactly or end with an ellipsis themselves.
+fn clipped(value: &str, col_width: usize, accent: Option<AnsiValue>) -> String {
+    let mut cell = fixed_width(value, col_width);
+    match accent {
+        Some(accent) if display_text(value).chars().count() > col_width => {
+            cell.pop();
+            format!("{}{}…{}", cell, color::Fg(accent), color::Fg(color::Reset))
+        }
+        _ => cell,
+    }
+}
+
+pub(crate) fn fixed_width(value: &str, col_width: usize) -> String {
+    let value = display_text(value);
+    if value.chars().count() > col_width {
+        let truncated: String = value.chars().take(col_width.saturating_sub(1)).collect();
+        format!("{}…", truncated)
     } else {
         format!("{:width$}", value, width = col_width)
     }
@@ -0,0 +1,66 @@
+//! Random samples of the displayed rows. Each row gets a pseudo-random rank from its position
+//! in the table as loaded and the seed, and the rows with the lowest ranks are kept, so the
+//! sample stays the same when the rows are sorted and shrinks consistently with further filters.
+use std::collections::{BTreeMap, HashSet};
+
+/// A sample of at most `size` rows, reproducible with the same seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub size: usize,
+    pub seed: u64,
+    /// Column whose values keep their share of the rows in the sample
+    pub stratify: Option<usize>,
+}
+
+impl Sample {
+    /// Selects the sampled rows of `view`, keeping their order. `origin` holds the position of
+    /// each row in the table as loaded.
+    pub fn select(&self, view: &[usize], origin: &[usize], rows: &[Vec<String>]) -> Vec<usize> {
+        if view.len() <= self.size {
+            return view.to_vec();
+        }
+        let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for &i in view {
+            let value = self.stratify.map_or("", |col| rows[i][col].as_str());
+            groups.entry(value).or_default().push(i);
+        }
+        let groups: Vec<Vec<usize>> = groups.into_values().collect();
+        let sizes: Vec<usize> = groups.iter().map(Vec::len).collect();
+        let mut selected = HashSet::with_capacity(self.size);
+        for (mut group, quota) in groups.into_iter().zip(quotas(&sizes, self.size)) {
+            if quota < group.len() {
+                group.select_nth_unstable_by_key(quota, |&i| self.rank(origin[i]));
+                group.truncate(quota);
+            }
+            selected.extend(group);
+        }
+        view.iter()
+            .copied()
+            .filter(|i| selected.contains(i))
+            .collect()
+    }
+
+    // SplitMix64 of the row's origin and the seed.
+    fn rank(&self, origin: usize) -> u64 {
+        let mut z = self
+            .seed
+            .wrapping_add((origin as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+// Splits `total` among groups proportionally to their sizes, giving the rows left after rounding
+// down to the groups with the largest remainders.
+fn quotas(sizes: &[usize], total: usize) -> Vec<usize> {
+    let rows: usize = sizes.iter().sum();
+    let mut quotas: Vec<usize> = sizes.iter().map(|size| size * total / rows).collect();
+    let mut by_remainder: Vec<usize> = (0..sizes.len()).collect();
+    by_remainder.sort_by_key(|&i| std::cmp::Reverse(sizes[i] * total % rows));
+    let left = total - quotas.iter().sum::<usize>();
+    for &i in by_remainder.iter().take(left) {
+        quotas[i] += 1;
+    }
+    quotas
+}
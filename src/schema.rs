@@ -0,0 +1,134 @@
+//! Validation of tables against a schema describing the expected columns.
+use crate::cell::{is_null, Cell, ColType};
+use crate::pane::PaneEntry;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Maximum number of issues reported, so broken files don't flood the issues view.
+const MAX_ISSUES: usize = 10_000;
+
+/// Expected columns of a table, read from a TOML file:
+///
+/// ```toml
+/// [[column]]
+/// name = "id"
+/// type = "int"
+/// nullable = false
+/// group = "keys"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    #[serde(rename = "column", default)]
+    pub columns: Vec<ColumnSchema>,
+}
+
+/// Name, type, nullability and group of an expected column.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(rename = "type", default = "default_type")]
+    pub col_type: String,
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+    /// Name shown above the column and its neighbors of the same group
+    pub group: Option<String>,
+}
+
+fn default_type() -> String {
+    "str".to_string()
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+impl Schema {
+    pub fn from_file(path: &Path) -> Result<Schema, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let schema: Schema = toml::from_str(&content)?;
+        for column in &schema.columns {
+            column.col_type.parse::<ColType>()?;
+        }
+        Ok(schema)
+    }
+
+    /// Declared types of the header's columns, None for columns not in the schema.
+    pub fn types(&self, header: &[String]) -> Vec<Option<ColType>> {
+        header
+            .iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .find(|column| &column.name == name)
+                    .and_then(|column| column.col_type.parse().ok())
+            })
+            .collect()
+    }
+
+    /// Groups of the header's columns, empty for columns without one.
+    pub fn groups(&self, header: &[String]) -> Vec<String> {
+        header
+            .iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .find(|column| &column.name == name)
+                    .and_then(|column| column.group.clone())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Checks the table against the schema and returns one entry per violation.
+    pub fn validate(&self, header: &[String], rows: &[Vec<String>]) -> Vec<PaneEntry> {
+        let mut issues = Vec::new();
+        for column in &self.columns {
+            if !header.contains(&column.name) {
+                issues.push(PaneEntry {
+                    row: None,
+                    col: 0,
+                    text: format!("missing column '{}'", column.name),
+                    highlight: false,
+                });
+            }
+        }
+        let checks: Vec<(usize, &ColumnSchema, ColType)> = header
+            .iter()
+            .enumerate()
+            .filter_map(|(col, name)| {
+                let column = self.columns.iter().find(|column| &column.name == name)?;
+                Some((col, column, column.col_type.parse().ok()?))
+            })
+            .collect();
+        for (i, row) in rows.iter().enumerate() {
+            for &(col, column, col_type) in &checks {
+                let value = &row[col];
+                let problem = if is_null(value) {
+                    (!column.nullable).then(|| "missing value".to_string())
+                } else if col_type != ColType::Str
+                    && matches!(Cell::parse(value, col_type), Cell::Str(_))
+                {
+                    Some(format!("'{}' is not {}", value, col_type))
+                } else {
+                    None
+                };
+                if let Some(problem) = problem {
+                    issues.push(PaneEntry {
+                        row: Some(i),
+                        col,
+                        text: format!("{}: {}: {}", row[0], column.name, problem),
+                        highlight: false,
+                    });
+                    if issues.len() >= MAX_ISSUES {
+                        return issues;
+                    }
+                }
+            }
+        }
+        issues
+    }
+}
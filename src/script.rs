@@ -0,0 +1,85 @@
+//! Scripting hook: custom commands, computed columns and key handlers written in Rhai.
+//!
+//! Scripts never get direct access to the table. Functions receive maps describing the
+//! current cell or row and return values (or command batches) that the viewer applies.
+use rhai::{Dynamic, Engine, FuncArgs, Map, Scope, AST};
+use std::error::Error;
+use std::path::Path;
+
+/// Upper bound of operations per function call, guards against endless loops.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Compiled user script together with the sandboxed engine running it.
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptHost {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile_file(path.into())?;
+        Ok(ScriptHost { engine, ast })
+    }
+
+    pub fn from_source(source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(source)?;
+        Ok(ScriptHost { engine, ast })
+    }
+
+    /// Does the script define a function with the given name and number of parameters?
+    pub fn has_function(&self, name: &str, arity: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == arity)
+    }
+
+    /// Calls a script function and converts its result into a string (unit becomes empty).
+    pub fn call(&self, name: &str, args: impl FuncArgs) -> Result<String, Box<dyn Error>> {
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, name, args)?;
+        Ok(if result.is_unit() {
+            String::new()
+        } else {
+            result.to_string()
+        })
+    }
+}
+
+/// Maps header names to the values of a row.
+pub fn row_map(header: &[String], row: &[String]) -> Map {
+    header
+        .iter()
+        .zip(row)
+        .map(|(name, value)| (name.as_str().into(), value.clone().into()))
+        .collect()
+}
+
+/// Context passed to command and key handlers: cursor position, current column and row.
+pub fn context_map(
+    header: &[String],
+    row: Option<&Vec<String>>,
+    row_index: usize,
+    col: usize,
+) -> Map {
+    let mut ctx = Map::new();
+    ctx.insert("col".into(), (col as i64).into());
+    ctx.insert("column".into(), header[col].clone().into());
+    match row {
+        Some(row) => {
+            ctx.insert("row".into(), (row_index as i64).into());
+            ctx.insert("value".into(), row[col].clone().into());
+            ctx.insert("cells".into(), row_map(header, row).into());
+        }
+        None => {
+            ctx.insert("row".into(), (-1_i64).into());
+            ctx.insert("value".into(), header[col].clone().into());
+            ctx.insert("cells".into(), Map::new().into());
+        }
+    }
+    ctx
+}
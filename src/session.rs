@@ -0,0 +1,177 @@
+//! Review state of a table file (notes, tags and pending edits), persisted in a sidecar file
+//! next to it.
+//!
+//! Rows are identified by a hash of their values, so notes and tags stay attached to their rows
+//! when the file is sorted or rows are added elsewhere. Pending edits refer to rows by their
+//! number, since they change the values.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Extension appended to the table path to get the sidecar path.
+const SIDECAR_EXTENSION: &str = "tv-session";
+
+#[derive(Debug, Default)]
+pub struct Session {
+    /// Table file, None if the table was not read from a file
+    table: Option<PathBuf>,
+    /// Modification time and size of the table file when it was loaded or last written
+    table_stamp: Option<(SystemTime, u64)>,
+    /// Sidecar file, None if the table was not read from a file
+    path: Option<PathBuf>,
+    /// Notes by row hash
+    pub notes: HashMap<u64, String>,
+    /// Tags by row hash
+    pub tags: HashMap<u64, BTreeSet<String>>,
+    /// Edited values not yet saved to the table file, by row number and column
+    pub journal: BTreeMap<(usize, usize), String>,
+}
+
+impl Session {
+    /// Path of the sidecar file belonging to a table file.
+    pub fn sidecar_path(table: &Path) -> PathBuf {
+        let mut name = table.as_os_str().to_owned();
+        name.push(".");
+        name.push(SIDECAR_EXTENSION);
+        PathBuf::from(name)
+    }
+
+    /// Loads the session of a table file; a missing sidecar yields an empty session.
+    pub fn load(table: &Path) -> Result<Session, Box<dyn Error>> {
+        let path = Session::sidecar_path(table);
+        let mut session = Session {
+            table: Some(table.to_path_buf()),
+            table_stamp: file_stamp(table),
+            path: Some(path.clone()),
+            ..Default::default()
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(session),
+            Err(err) => return Err(err.into()),
+        };
+        for (i, line) in content.lines().enumerate() {
+            let mut fields = line.splitn(3, '\t');
+            let invalid = || format!("{}:{}: invalid entry", path.display(), i + 1);
+            let (kind, key, text) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(kind), Some(key), Some(text)) => (kind, key, unescape(text)),
+                _ => return Err(invalid().into()),
+            };
+            if kind == "edit" {
+                let cell = key
+                    .split_once(':')
+                    .and_then(|(row, col)| Some((row.parse().ok()?, col.parse().ok()?)))
+                    .ok_or_else(invalid)?;
+                session.journal.insert(cell, text);
+                continue;
+            }
+            let hash = u64::from_str_radix(key, 16).map_err(|_| invalid())?;
+            match kind {
+                "note" => {
+                    session.notes.insert(hash, text);
+                }
+                "tag" => {
+                    session.tags.entry(hash).or_default().insert(text);
+                }
+                _ => return Err(invalid().into()),
+            }
+        }
+        Ok(session)
+    }
+
+    /// Table file the session belongs to.
+    pub fn table_path(&self) -> Option<&Path> {
+        self.table.as_deref()
+    }
+
+    /// Whether the table file changed on disk since it was loaded or last written.
+    pub fn table_changed(&self) -> bool {
+        match &self.table {
+            Some(table) => file_stamp(table) != self.table_stamp,
+            None => false,
+        }
+    }
+
+    /// Records the state of the table file after writing it.
+    pub fn table_written(&mut self) {
+        self.table_stamp = self.table.as_deref().and_then(file_stamp);
+    }
+
+    /// Writes the session to its sidecar file; the file is removed if the session is empty.
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if self.notes.is_empty() && self.tags.is_empty() && self.journal.is_empty() {
+            return match fs::remove_file(path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        }
+        let mut entries: Vec<(&u64, &String)> = self.notes.iter().collect();
+        entries.sort();
+        let mut content: String = entries
+            .into_iter()
+            .map(|(hash, text)| format!("note\t{:016x}\t{}\n", hash, escape(text)))
+            .collect();
+        let mut tags: Vec<(&u64, &BTreeSet<String>)> = self.tags.iter().collect();
+        tags.sort();
+        for (hash, tags) in tags {
+            for tag in tags {
+                content.push_str(&format!("tag\t{:016x}\t{}\n", hash, escape(tag)));
+            }
+        }
+        for ((row, col), value) in &self.journal {
+            content.push_str(&format!("edit\t{}:{}\t{}\n", row, col, escape(value)));
+        }
+        fs::write(path, content)
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Stable hash of row values (FNV-1a), used to identify rows across sessions.
+pub fn row_hash(values: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for value in values {
+        // separate values, so that ["ab", "c"] and ["a", "bc"] differ
+        for byte in value.bytes().chain(Some(0x1f)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
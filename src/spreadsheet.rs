@@ -0,0 +1,115 @@
+//! Excel (`.xlsx`, `.xlsm`, `.xls`) and OpenDocument (`.ods`) spreadsheets. The first row of a
+//! sheet is its header; numbers and dates are formatted as they would be written in a CSV file.
+pub use crate::registry::Table;
+use crate::registry::{DataSource, LoadOptions};
+use calamine::{open_workbook_auto_from_rs, Data, ExcelDateTime, Reader, Sheets};
+use std::error::Error;
+use std::io::{Cursor, Read};
+use std::iter::once;
+
+/// Workbooks read with calamine, which detects the file type from its content.
+pub struct SpreadsheetSource;
+
+impl DataSource for SpreadsheetSource {
+    fn name(&self) -> &str {
+        "spreadsheet"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xlsx", "xlsm", "xls", "ods"]
+    }
+
+    /// Reads the sheet given in the options or else the first one.
+    fn load(&self, reader: Box<dyn Read>, options: &LoadOptions) -> Result<Table, Box<dyn Error>> {
+        let mut workbook = open_workbook(reader)?;
+        let sheet = match &options.sheet {
+            Some(sheet) => sheet.clone(),
+            None => workbook
+                .sheet_names()
+                .into_iter()
+                .next()
+                .ok_or("Workbook has no sheets")?,
+        };
+        read_sheet(
+            &mut workbook,
+            &sheet,
+            options.max_rows.unwrap_or(usize::MAX),
+        )
+    }
+}
+
+fn open_workbook(mut reader: Box<dyn Read>) -> Result<Sheets<Cursor<Vec<u8>>>, Box<dyn Error>> {
+    // the formats are zip archives, which need to be seekable
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(open_workbook_auto_from_rs(Cursor::new(data))?)
+}
+
+/// Names of the sheets of a workbook in their order in the file.
+pub fn sheet_names<R: Read + 'static>(reader: R) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(open_workbook(Box::new(reader))?.sheet_names())
+}
+
+fn read_sheet(
+    workbook: &mut Sheets<Cursor<Vec<u8>>>,
+    sheet: &str,
+    limit: usize,
+) -> Result<Table, Box<dyn Error>> {
+    if !workbook.sheet_names().iter().any(|name| name == sheet) {
+        return Err(format!("No sheet named '{}'", sheet).into());
+    }
+    let range = workbook.worksheet_range(sheet)?;
+    let mut lines = range.rows();
+    let header = match lines.next() {
+        Some(cells) => once("#".to_string())
+            .chain(cells.iter().map(format_cell))
+            .collect(),
+        None => vec!["#".to_string()],
+    };
+    let rows = lines
+        .take(limit)
+        .enumerate()
+        .map(|(i, cells)| {
+            once(format!("{}", i + 1))
+                .chain(cells.iter().map(format_cell))
+                .collect()
+        })
+        .collect();
+    Ok((header, rows))
+}
+
+/// Text of a cell: whole numbers without decimals, dates as `YYYY-MM-DD` with the time of day
+/// if there is one, times and durations as `HH:MM:SS` and errors like `#DIV/0!`.
+pub fn format_cell(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(text) | Data::DateTimeIso(text) | Data::DurationIso(text) => text.clone(),
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => format_number(*f),
+        Data::Bool(b) => b.to_string(),
+        Data::DateTime(date) => format_date_time(date),
+        Data::Error(error) => error.to_string(),
+    }
+}
+
+fn format_number(number: f64) -> String {
+    match number.fract() == 0.0 && number.abs() < 1e15 {
+        true => format!("{}", number as i64),
+        false => number.to_string(),
+    }
+}
+
+fn format_date_time(date: &ExcelDateTime) -> String {
+    let (year, month, day, hours, minutes, seconds, _) = date.to_ymd_hms_milli();
+    let time = format!("{:02}:{:02}:{:02}", hours, minutes, seconds);
+    if date.is_duration() {
+        let hours = (date.as_f64() * 24.0).trunc() as i64;
+        return format!("{:02}:{:02}:{:02}", hours, minutes, seconds);
+    }
+    match (date.as_f64() < 1.0, date.as_f64().fract() == 0.0) {
+        // times of day without date
+        (true, _) => time,
+        (false, true) => format!("{:04}-{:02}-{:02}", year, month, day),
+        (false, false) => format!("{:04}-{:02}-{:02} {}", year, month, day, time),
+    }
+}
@@ -1,60 +1,345 @@
 //! Table state without external side-effects.
-use crate::renderer::RenderingAction;
+use crate::cell::{
+    display_text, display_width, format_date, infer_type, is_null, parse_date, parse_duration,
+    wrap, Cell, ColType,
+};
+use crate::clipboard;
+use crate::collation::{self, text_order};
+use crate::column_editor::ColumnEditor;
+use crate::command;
+use crate::config::Profile;
+use crate::expr::{Expr, Value};
+use crate::filter::{Filter, Op};
+use crate::filter_builder::{Field, FilterBuilder};
+use crate::history::History;
+use crate::lint;
+use crate::pane::{ListPane, PaneEntry};
+#[cfg(feature = "csv")]
+use crate::picker::FilePicker;
+use crate::plot;
+use crate::renderer::{fixed_width, RenderingAction};
+use crate::sample::Sample;
+#[cfg(feature = "config")]
+use crate::schema::Schema;
+#[cfg(feature = "scripting")]
+use crate::script::{self, ScriptHost};
+use crate::session::{row_hash, Session};
+use crate::stats::{self, ColumnSummary};
+use crate::theme::{Rules, Theme};
 use core::cmp::Ordering;
-use std::cmp::min;
+use std::borrow::Cow;
+use std::cmp::{min, Reverse};
+use std::collections::{HashMap, HashSet};
 use std::iter::once;
+use std::path::Path;
+#[cfg(feature = "csv")]
+use std::time::Instant;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "log")]
+use tracing::debug;
+
+/// Work run in the background by the viewer: receives a callback for progress messages
+/// and returns a final message for the status line.
+pub type Task = Box<dyn FnOnce(&dyn Fn(String)) -> String + Send>;
+
+/// A yes/no question shown on the command line before a destructive action, which only runs
+/// if the answer is yes.
+pub struct Prompt {
+    pub question: String,
+    action: Box<dyn FnOnce(&mut TableState) -> RenderingAction>,
+}
+
+/// Number of spaces separating columns.
+pub const PADDING: usize = 2;
+
+/// Row number of rows added by duplicating, which have no number in the file until it is saved.
+pub const ADDED_ROW: &str = "+";
 
 /// Keeps data and state for rendering.
 pub struct TableState {
     pub header: Vec<String>,
     pub rows: Vec<Vec<String>>,
+    /// Indices of the rows passing all filters, in display order
+    pub view: Vec<usize>,
+    // Position of each row in the table as loaded, reordered along with the rows
+    origin: Vec<usize>,
+    pub filters: Vec<Filter>,
+    /// Random sample of the rows passing the filters shown instead of all of them
+    pub sample: Option<Sample>,
+    /// Columns the rows are sorted by, with `Less` for ascending and `Greater` for descending;
+    /// empty in the original order
+    pub sort_order: Vec<(usize, Ordering)>,
+    /// Expressions used instead of the values when sorting by a column
+    pub sort_keys: HashMap<usize, Expr>,
+    /// Sort text by Unicode collation instead of code points
+    pub collation: bool,
     pub columns: Vec<ColFormat>,
+    /// Spaces after the content of each column
+    pub padding: usize,
+    pub types: Vec<ColType>,
     pub terminal_size: CharCoord,
     pub cur_pos: TableCoord,
     pub offsets: TableCoord,
     pub command_buffer: Vec<char>,
+    /// Number of characters of the command line after the cursor, 0 when typing at its end
+    pub command_tail: usize,
+    /// Entered commands and searches, oldest first
+    pub command_history: Vec<String>,
+    pub last_search: Option<Search>,
+    /// Highlight the matches of the last search, until `:noh`
+    pub highlight_matches: bool,
+    pub search_case: SearchCase,
+    /// Take search patterns as typed instead of resolving backslash escapes like `\;`
+    pub literal_search: bool,
+    /// Window offsets and cursor position before the search being typed, restored if it is
+    /// cancelled
+    search_origin: Option<(TableCoord, TableCoord)>,
+    pub pane: Option<ListPane>,
+    pub issues: Vec<PaneEntry>,
+    pub status_message: Option<String>,
+    pub presentation: Option<Presentation>,
+    /// Shows the current row with one field per line instead of the table, scrolled down by
+    /// the given number of fields
+    pub record_view: Option<usize>,
+    pub summaries: HashMap<usize, ColumnSummary>,
+    pub aliases: HashMap<String, String>,
+    /// Commands run by keys without a built-in binding, by key name
+    pub keys: HashMap<String, String>,
+    /// Rows for which one of these holds are highlighted
+    pub highlights: Vec<Expr>,
+    /// Task to be run in the background, picked up by the viewer
+    pub task: Option<Task>,
+    /// Question to be answered before running an action, shown by the viewer
+    pub prompt: Option<Prompt>,
+    /// Text to be copied to the clipboard, picked up by the viewer
+    pub clipboard: Option<String>,
+    pub session: Session,
+    /// Delimiter and quote character for saving the table file, None if it cannot be saved
+    pub delimited: Option<(u8, u8)>,
+    /// Keep the previous version of a saved file as `<file>.bak`
+    pub backup: bool,
+    /// Number of times the table was re-read; results computed before refer to old data
+    pub reloads: usize,
+    /// File picker shown instead of the table
+    #[cfg(feature = "csv")]
+    pub picker: Option<FilePicker>,
+    /// Recently opened files
+    pub history: History,
+    /// Time it took to read the table, if known
+    pub load_time: Option<Duration>,
+    /// Number of rows the table was cut off after when loading, None if it was read completely
+    pub row_cap: Option<usize>,
+    /// Number of the row marked for comparison with the row under the cursor
+    pub anchor: Option<usize>,
+    /// Virtual column showing the notes of the session
+    pub note_col: Option<usize>,
+    /// Virtual column showing the tags of the session
+    pub tag_col: Option<usize>,
+    /// Columns hidden with `zc`, laid out with zero width and skipped by the cursor
+    pub hidden: HashSet<usize>,
+    /// Editor of the order and visibility of the columns shown instead of the table
+    pub column_editor: Option<ColumnEditor>,
+    /// Form for building a filter shown over the table
+    pub filter_builder: Option<FilterBuilder>,
+    /// Order in which the columns are shown, by index into the header; `columns`, `offsets` and
+    /// `cur_pos` refer to positions in this order
+    pub order: Vec<usize>,
+    /// Number of leading columns in display order that stay in view when scrolling horizontally
+    pub frozen: usize,
+    /// Use colors, e.g. for the ellipsis of truncated values
+    pub color: bool,
+    /// Colors matching the terminal's background
+    pub theme: Theme,
+    /// Lines under the header and after the frozen columns
+    pub rules: Rules,
+    /// Name of the group each column belongs to, shown above the header across its columns;
+    /// empty for columns without a group
+    pub groups: Vec<String>,
+    // Groups the header as loaded would name if it were a line above the actual header, before
+    // empty and repeated names are replaced; see `promote_group_header`
+    group_line: Vec<String>,
+    // Number of columns at load, the ones identifying a row
+    data_width: usize,
+    /// Table this one was derived from, e.g. by `:dedup`, shown again with `Esc`
+    pub parent: Option<Box<TableState>>,
+    /// How this table was derived from its parent, e.g. `freq country`, for the breadcrumb
+    pub derivation: Option<String>,
+    /// Derived table returned from with `Ctrl-o`, shown again with `Tab`
+    pub forward: Option<Box<TableState>>,
+    /// Column of the parent table whose values are counted in this one by `:freq`
+    pub counted_col: Option<usize>,
+    /// Number of rows deleted or duplicated since the table was loaded or saved
+    pub row_changes: usize,
+    #[cfg(feature = "scripting")]
+    pub script: Option<ScriptHost>,
+    #[cfg(feature = "scripting")]
+    script_depth: usize,
 }
 
 // Factory methods
 impl TableState {
-    pub fn new(header: Vec<String>, rows: Vec<Vec<String>>, terminal_size: CharCoord) -> Self {
-        let col_widths =
-            compute_col_widths(once(&header).chain((&rows).iter()), 2, terminal_size.x);
-        let columns = col_widths
-            .iter()
-            .scan(0, |acc, &width| {
-                let index = *acc;
-                *acc += width;
-                Some(ColFormat { width, index })
-            })
+    pub fn new(
+        mut header: Vec<String>,
+        mut rows: Vec<Vec<String>>,
+        terminal_size: CharCoord,
+    ) -> Self {
+        let skipped = skip_header_rows(&header, &mut rows);
+        let group_line = group_names(&header);
+        let renamed = disambiguate_header(&mut header);
+        let columns = layout_columns(&header, &rows, PADDING, terminal_size.x);
+        let types = (0..header.len())
+            .map(|col| infer_type(rows.iter().map(|row| row[col].as_str())))
             .collect();
         let width = terminal_size.x;
+        let data_width = header.len();
+        let view = (0..rows.len()).collect();
+        let origin = (0..rows.len()).collect();
+        let mut notices = Vec::new();
+        if skipped > 0 {
+            notices.push(format!("Skipped {} rows repeating the header", skipped));
+        }
+        if renamed > 0 {
+            notices.push(format!(
+                "Renamed {} duplicate or empty column names",
+                renamed
+            ));
+        }
+        if notices.is_empty() && header_like(&rows) {
+            notices.push("The first row looks like a header, :promote-header makes it one".into());
+        }
+        let status_message = (!notices.is_empty()).then(|| notices.join("; "));
         TableState {
             header,
             rows,
+            view,
+            origin,
+            filters: Vec::new(),
+            sample: None,
+            sort_order: Vec::new(),
+            sort_keys: HashMap::new(),
+            collation: false,
             columns,
+            padding: PADDING,
+            types,
             terminal_size,
             cur_pos: Default::default(),
             offsets: Default::default(),
             command_buffer: Vec::with_capacity(width),
+            command_tail: 0,
+            command_history: Vec::new(),
+            last_search: None,
+            highlight_matches: false,
+            search_case: SearchCase::Sensitive,
+            literal_search: false,
+            search_origin: None,
+            pane: None,
+            issues: Vec::new(),
+            status_message,
+            presentation: None,
+            record_view: None,
+            summaries: HashMap::new(),
+            aliases: HashMap::new(),
+            keys: HashMap::new(),
+            highlights: Vec::new(),
+            task: None,
+            prompt: None,
+            clipboard: None,
+            session: Session::default(),
+            delimited: None,
+            backup: false,
+            reloads: 0,
+            #[cfg(feature = "csv")]
+            picker: None,
+            history: History::default(),
+            load_time: None,
+            row_cap: None,
+            anchor: None,
+            note_col: None,
+            tag_col: None,
+            hidden: HashSet::new(),
+            column_editor: None,
+            filter_builder: None,
+            order: (0..data_width).collect(),
+            frozen: 0,
+            color: true,
+            theme: Theme::default(),
+            rules: Rules::default(),
+            groups: Vec::new(),
+            group_line,
+            data_width,
+            parent: None,
+            derivation: None,
+            forward: None,
+            counted_col: None,
+            row_changes: 0,
+            #[cfg(feature = "scripting")]
+            script: None,
+            #[cfg(feature = "scripting")]
+            script_depth: 0,
         }
     }
 }
 
 /// Table cell-based coordinates (columns and rows).
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct TableCoord {
     pub col: usize,
     pub row: usize,
 }
 
 /// Character-based coordinates in x and y direction.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct CharCoord {
     pub x: usize,
     pub y: usize,
 }
 
+/// The most recent search: pattern and column it was executed on.
+#[derive(Debug)]
+pub struct Search {
+    pub pattern: String,
+    pub col: usize,
+    /// Match whole values instead of substrings
+    pub exact: bool,
+    /// Search towards the start of the table
+    pub backward: bool,
+}
+
+impl Search {
+    /// Does the value contain (or equal, if exact) the pattern, given the case sensitivity?
+    pub fn matches(&self, value: &str, case: SearchCase) -> bool {
+        let ignore_case = match case {
+            SearchCase::Sensitive => false,
+            SearchCase::Insensitive => true,
+            SearchCase::Smart => !self.pattern.chars().any(char::is_uppercase),
+        };
+        match (ignore_case, self.exact) {
+            (true, true) => value.to_lowercase() == self.pattern.to_lowercase(),
+            (true, false) => value.to_lowercase().contains(&self.pattern.to_lowercase()),
+            (false, true) => value == self.pattern,
+            (false, false) => value.contains(&self.pattern),
+        }
+    }
+}
+
+/// Case sensitivity of searches.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchCase {
+    #[default]
+    Sensitive,
+    Insensitive,
+    /// Case-insensitive unless the pattern contains uppercase letters, as in Vim
+    Smart,
+}
+
+/// Settings of the presentation mode, which shows the table without cursor, row numbers and
+/// status line.
+#[derive(Debug)]
+pub struct Presentation {
+    /// Time between scrolling down by one row, None to not scroll automatically
+    pub interval: Option<Duration>,
+}
+
 /// Formatting information about a column: width and index in characters.
 #[derive(Debug, Default)]
 pub struct ColFormat {
@@ -64,18 +349,58 @@ pub struct ColFormat {
 
 // Implement some helper methods for accessing state.
 impl TableState {
+    /// Number of characters the scrolling columns are shifted to the left.
     pub fn x_offset(&self) -> usize {
-        self.columns[self.offsets.col].index
+        self.columns[self.offsets.col].index - self.frozen_width()
+    }
+
+    // Width taken by the frozen columns.
+    fn frozen_width(&self) -> usize {
+        self.columns.get(self.frozen).map_or(0, |col| col.index)
+    }
+
+    /// Screen column at which the current column starts.
+    pub fn cursor_x(&self) -> usize {
+        let position = self.current_position();
+        match position < self.frozen {
+            true => self.columns[position].index,
+            false => self.columns[position].index - self.x_offset(),
+        }
+    }
+
+    /// Screen line at which the current row is shown, below the column groups and the rule
+    /// under the header.
+    pub fn cursor_y(&self) -> usize {
+        match self.cur_pos.row {
+            0 => self.has_groups() as usize,
+            row => row + self.header_height() - 1,
+        }
+    }
+
+    /// Lines above the data rows: the column groups, the header and the rule under it.
+    pub fn header_height(&self) -> usize {
+        self.has_groups() as usize + 1 + self.rules.chars().is_some() as usize
+    }
+
+    /// Are there column groups to show above the header?
+    pub fn has_groups(&self) -> bool {
+        self.groups.iter().any(|group| !group.is_empty())
     }
 
     pub fn displayable_data_rows(&self) -> usize {
-        // need to subtract the header
-        self.terminal_size.y - 1
+        // need to subtract the header and the status line, which is hidden in presentations
+        match self.presentation {
+            Some(_) => self.terminal_size.y.saturating_sub(self.header_height()),
+            None => self
+                .terminal_size
+                .y
+                .saturating_sub(self.header_height() + 1),
+        }
     }
 
     // Is the final data row visible in the current window?
     pub fn final_row_visible(&self) -> bool {
-        self.offsets.row + self.displayable_data_rows() >= self.rows.len()
+        self.offsets.row + self.displayable_data_rows() >= self.row_count()
     }
 
     // Is the first data row visible in the current window?
@@ -91,239 +416,3859 @@ impl TableState {
 
     // Is the current row at the bottom of the displayed window?
     pub fn is_bottom(&self) -> bool {
-        let bottom_row = min(self.displayable_data_rows(), self.rows.len());
+        let bottom_row = min(self.displayable_data_rows(), self.row_count());
         self.cur_pos.row == bottom_row
     }
 
     // Absolute index of current column
     pub fn current_column(&self) -> usize {
-        self.offsets.col + self.cur_pos.col
+        self.order[self.current_position()]
+    }
+
+    // Position of the current column in the display order, counting the frozen columns on the
+    // left of the window
+    fn current_position(&self) -> usize {
+        match self.cur_pos.col < self.frozen {
+            true => self.cur_pos.col,
+            false => self.offsets.col + self.cur_pos.col - self.frozen,
+        }
+    }
+
+    // Position of a column in the display order
+    fn position(&self, col: usize) -> usize {
+        self.order.iter().position(|&i| i == col).unwrap_or(0)
+    }
+
+    /// Widths of the columns in the order of the header.
+    pub fn widths(&self) -> Vec<usize> {
+        let mut widths = vec![0; self.order.len()];
+        for (&col, format) in self.order.iter().zip(&self.columns) {
+            widths[col] = format.width;
+        }
+        widths
+    }
+
+    /// Number of displayed data rows, i.e. rows passing all filters.
+    pub fn row_count(&self) -> usize {
+        self.view.len()
+    }
+
+    /// Data row at the given display position.
+    pub fn view_row(&self, position: usize) -> &Vec<String> {
+        &self.rows[self.view[position]]
     }
 
     // Absolute index of current row
     pub fn current_row(&self) -> usize {
         self.offsets.row + self.cur_pos.row
     }
-}
 
-fn compare_str(a: &str, b: &str) -> Ordering {
-    a.cmp(b)
-}
+    /// Window title showing the file and the position in it, e.g.
+    /// `file.csv — row 1.234/56.789 — table-viewer`.
+    pub fn window_title(&self) -> String {
+        let position = format!(
+            "row {}/{}",
+            group_digits(self.current_row().clamp(1, self.row_count().max(1))),
+            group_digits(self.row_count())
+        );
+        let name = self
+            .session
+            .table_path()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy());
+        match name {
+            Some(name) => format!("{} — {} — table-viewer", name, position),
+            None => format!("{} — table-viewer", position),
+        }
+    }
+
+    /// Text of the status line: a message if there is one, else a summary of the current column.
+    pub fn status_line(&self) -> String {
+        if let Some(message) = &self.status_message {
+            return message.clone();
+        }
+        let col = self.current_column();
+        let summary = match self.summaries.get(&col) {
+            Some(summary) => format!(
+                "{} | {} | {} distinct | {:.1}% null",
+                self.header[col],
+                summary.col_type,
+                summary.distinct,
+                summary.null_percentage()
+            ),
+            None => self.header[col].clone(),
+        };
+        match self.parent {
+            Some(_) => format!("{} | {}", self.breadcrumb(), summary),
+            None => summary,
+        }
+    }
+
+    /// The views from the loaded table to this one, e.g. `sales.csv › freq country`.
+    pub fn breadcrumb(&self) -> String {
+        let mut views = Vec::new();
+        let mut state = self;
+        while let Some(parent) = &state.parent {
+            views.push(state.derivation.clone().unwrap_or_default());
+            state = parent;
+        }
+        let name = state
+            .session
+            .table_path()
+            .and_then(|path| path.file_name())
+            .map_or("table".into(), |name| name.to_string_lossy());
+        views.push(name.to_string());
+        views.reverse();
+        views.join(" › ")
+    }
+
+    /// Columns written on export: all but the row numbers, notes and tags only if requested.
+    pub fn export_columns(&self, notes: bool) -> Vec<usize> {
+        (1..self.header.len())
+            .filter(|&col| notes || (Some(col) != self.note_col && Some(col) != self.tag_col))
+            .collect()
+    }
 
-fn compare_int(a: &str, b: &str) -> Ordering {
-    let a: usize = a.parse().unwrap();
-    let b: usize = b.parse().unwrap();
-    a.cmp(&b)
+    /// Row with the pending edits of the session applied.
+    pub fn edited_row(&self, row: usize) -> Cow<'_, Vec<String>> {
+        let mut edits = match self.row_number(row) {
+            Some(number) => self
+                .session
+                .journal
+                .range((number, 0)..(number, self.data_width))
+                .peekable(),
+            None => return Cow::Borrowed(&self.rows[row]),
+        };
+        if edits.peek().is_none() {
+            return Cow::Borrowed(&self.rows[row]);
+        }
+        let mut edited = self.rows[row].clone();
+        for (&(_, col), value) in edits {
+            edited[col] = value.clone();
+        }
+        Cow::Owned(edited)
+    }
+
+    // Number of a data row as shown in the first column, None for generated tables
+    fn row_number(&self, row: usize) -> Option<usize> {
+        self.rows[row][0].parse().ok()
+    }
+
+    // Hash identifying a data row in the session, based on the loaded values without row number
+    fn row_hash(&self, row: usize) -> u64 {
+        row_hash(&self.rows[row][1..self.data_width])
+    }
+
+    /// Index of the column with the given name.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.header.iter().position(|column| column == name)
+    }
+
+    /// Typed value of a data cell.
+    pub fn cell(&self, row: usize, col: usize) -> Cell<'_> {
+        Cell::parse(&self.rows[row][col], self.types[col])
+    }
+
+    // Display position of the data row under the cursor, None if the cursor is on the header
+    pub fn current_data_row(&self) -> Option<usize> {
+        if self.cur_pos.row == 0 {
+            None
+        } else {
+            Some(self.current_row() - 1)
+        }
+    }
 }
 
 // Implement user actions. Each methods returns a RenderingAction.
 impl TableState {
     pub fn ascending(&mut self, col: usize) -> RenderingAction {
-        let comp = if col == 0 { compare_int } else { compare_str };
-        self.rows.sort_by(|r1, r2| comp(&r1[col], &r2[col]));
+        self.sort_by_columns(&[(col, Ordering::Less)]);
         RenderingAction::Rerender
     }
 
     pub fn descending(&mut self, col: usize) -> RenderingAction {
-        let comp = if col == 0 { compare_int } else { compare_str };
-        self.rows.sort_by(|r1, r2| comp(&r2[col], &r1[col]));
+        self.sort_by_columns(&[(col, Ordering::Greater)]);
         RenderingAction::Rerender
     }
 
-    pub fn execute_command(&mut self) -> RenderingAction {
-        if self.command_buffer.len() > 1 && self.command_buffer[0] == '/' {
-            self.search(&self.command_buffer[1..].iter().collect::<String>())
-        } else {
-            RenderingAction::None
-        }
+    /// Restores the order in which the rows were loaded.
+    pub fn original_order(&mut self) -> RenderingAction {
+        let mut indexed: Vec<_> = std::mem::take(&mut self.origin)
+            .into_iter()
+            .zip(std::mem::take(&mut self.rows))
+            .collect();
+        indexed.sort_by_key(|(origin, _)| *origin);
+        (self.origin, self.rows) = indexed.into_iter().unzip();
+        self.refresh_view();
+        self.sort_order.clear();
+        RenderingAction::Rerender
     }
 
-    fn jump_to_row(&mut self, row: usize) {
-        // first window position
-        if row < self.displayable_data_rows() {
-            self.offsets.row = 0;
-            self.cur_pos.row = row + 1;
-        }
-        // last window position
-        else if self.rows.len() - row < self.displayable_data_rows() {
-            self.offsets.row = self.rows.len() - self.displayable_data_rows();
-            self.cur_pos.row = row - self.offsets.row + 1;
-        }
-        // middle
-        else {
-            self.offsets.row = row;
-            self.cur_pos.row = 1;
-        }
+    /// Adds the column to the current sort order, breaking ties of the columns sorted by before.
+    /// `Less` sorts it ascending, `Greater` descending.
+    pub fn then_sort(&mut self, col: usize, direction: Ordering) -> RenderingAction {
+        let mut keys = self.sort_order.clone();
+        keys.retain(|&(sorted, _)| sorted != col);
+        keys.push((col, direction));
+        self.sort_by_columns(&keys);
+        RenderingAction::Rerender
     }
 
-    pub fn search(&mut self, pattern: &str) -> RenderingAction {
-        let col = self.current_column();
-        let cur_row = self.current_row();
-        for row in (cur_row..self.rows.len()).chain(0..cur_row) {
-            let cell = &self.rows[row][col];
-            if cell.contains(pattern) {
-                self.jump_to_row(row);
-                break;
+    // Sorts by the typed values of the given columns or their sort keys, later columns breaking
+    // ties; `Less` sorts ascending, `Greater` descending.
+    fn sort_by_columns(&mut self, keys: &[(usize, Ordering)]) {
+        let mut text_order = text_order(self.collation);
+        // sort key expressions are evaluated once per row
+        let evaluated: Vec<Option<Vec<Value>>> = keys
+            .iter()
+            .map(|(col, _)| {
+                self.sort_keys.get(col).map(|key| {
+                    self.rows
+                        .iter()
+                        .map(|row| key.eval(row, &self.types))
+                        .collect()
+                })
+            })
+            .collect();
+        let types = &self.types;
+        let mut indexed: Vec<_> = std::mem::take(&mut self.rows)
+            .into_iter()
+            .enumerate()
+            .collect();
+        indexed.sort_by(|(i, r1), (j, r2)| {
+            for ((col, direction), values) in keys.iter().zip(&evaluated) {
+                let (ordering, ranks) = match values {
+                    Some(values) => {
+                        let (v1, v2) = (&values[*i], &values[*j]);
+                        let ordering = match (v1, v2) {
+                            (Value::Str(a), Value::Str(b)) => text_order(a, b),
+                            (v1, v2) => v1.total_cmp(v2),
+                        };
+                        let rank = |value: &Value| u8::from(*value == Value::Null) * 2;
+                        (ordering, (rank(v1), rank(v2)))
+                    }
+                    None => {
+                        let c1 = Cell::parse(&r1[*col], types[*col]);
+                        let c2 = Cell::parse(&r2[*col], types[*col]);
+                        let ranks = (sort_rank(&c1, types[*col]), sort_rank(&c2, types[*col]));
+                        let ordering = match (c1, c2) {
+                            (Cell::Str(a), Cell::Str(b)) => text_order(a, b),
+                            (c1, c2) => c1.total_cmp(&c2),
+                        };
+                        (ordering, ranks)
+                    }
+                };
+                // values that do not fit the column type and nulls come last in both directions
+                let ordering = match ranks {
+                    (rank1, rank2) if rank1 != rank2 => rank1.cmp(&rank2),
+                    (0, _) if *direction == Ordering::Greater => ordering.reverse(),
+                    _ => ordering,
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+        self.origin = indexed.iter().map(|(i, _)| self.origin[*i]).collect();
+        self.rows = indexed.into_iter().map(|(_, row)| row).collect();
+        self.refresh_view();
+        self.sort_order = keys.to_vec();
+    }
+
+    /// `:sort -price, +name` sorts by several columns, each prefixed with `+` (ascending, the
+    /// default) or `-` (descending).
+    fn sort(&mut self, args: &str) -> RenderingAction {
+        let mut keys = Vec::new();
+        for (name, direction) in sort_spec(args) {
+            if name.is_empty() {
+                return self.show_error("Usage: sort [+|-]<column>, ...");
+            }
+            match self.column_index(name) {
+                Some(col) => keys.push((col, direction)),
+                None => return self.show_error(&format!("Unknown column: {}", name)),
             }
         }
+        self.sort_by_columns(&keys);
         RenderingAction::Rerender
     }
 
-    pub fn move_down(&mut self) -> RenderingAction {
-        if self.is_bottom() {
-            if !self.final_row_visible() {
-                self.offsets.row += 1;
-                return RenderingAction::Rerender;
-            }
-        } else {
-            self.cur_pos.row += 1;
-            return RenderingAction::MoveCursor;
-        };
-        RenderingAction::None
+    /// Sorts by a spec as for `:sort` given in the config, ignoring columns the table does not
+    /// have.
+    pub fn default_sort(&mut self, spec: &str) {
+        let keys: Vec<_> = sort_spec(spec)
+            .filter_map(|(name, direction)| Some((self.column_index(name)?, direction)))
+            .collect();
+        if !keys.is_empty() {
+            self.sort_by_columns(&keys);
+        }
     }
 
-    pub fn move_page_down(&mut self) -> RenderingAction {
-        // from the header, we jump to the first data row
-        if self.cur_pos.row == 0 {
-            self.cur_pos.row = 1;
-            RenderingAction::MoveCursor
+    /// `:type <column> int|float|str|bool|date` overrides the type inferred for a column, which
+    /// decides how it is sorted, aligned, filtered and summarized; `auto` infers it again.
+    fn set_column_type(&mut self, args: &str) -> RenderingAction {
+        const USAGE: &str = "Usage: type <column> int|float|str|bool|date|auto";
+        let (name, type_name) = match args.rsplit_once(char::is_whitespace) {
+            Some((name, type_name)) => (name.trim(), type_name),
+            None => return self.show_error(USAGE),
+        };
+        let col = match self.column_index(name) {
+            Some(col) => col,
+            None => return self.show_error(&format!("Unknown column: {}", name)),
+        };
+        let col_type = match type_name {
+            "auto" => infer_type(self.rows.iter().map(|row| row[col].as_str())),
+            _ => match type_name.parse() {
+                Ok(col_type) => col_type,
+                Err(_) => return self.show_error(USAGE),
+            },
+        };
+        self.types[col] = col_type;
+        self.summaries.remove(&col);
+        let sort_order = self.sort_order.clone();
+        match sort_order.is_empty() {
+            true => self.refresh_view(),
+            false => self.sort_by_columns(&sort_order),
         }
-        // the final row is not yet visible, we need to shift the window to
-        else if !self.final_row_visible() {
-            self.offsets.row = min(
-                // the last window position or
-                self.rows.len() - self.displayable_data_rows(),
-                // to the next position, making the current last row the first
-                self.offsets.row + (self.displayable_data_rows() - 1),
+        // filters comparing the column may pass fewer rows now
+        if self.current_row() > self.row_count() {
+            self.offsets.row = 0;
+            self.cur_pos.row = 0;
+        }
+        let mismatches = self
+            .rows
+            .iter()
+            .filter(|row| matches!(Cell::parse(&row[col], col_type), Cell::Str(_)))
+            .count();
+        let mut message = format!("{} is {}", name, col_type);
+        if col_type != ColType::Str && mismatches > 0 {
+            message = format!(
+                "{} ({} values do not fit)",
+                message,
+                group_digits(mismatches)
             );
-            RenderingAction::Rerender
         }
-        // the final row is already within our window
-        else if self.cur_pos.row != self.displayable_data_rows() {
-            self.cur_pos.row = self.displayable_data_rows();
-            RenderingAction::MoveCursor
-        } else {
-            RenderingAction::None
+        self.show_message(&message)
+    }
+
+    /// `:collation on|off` switches between sorting text by Unicode collation and by code points.
+    fn set_collation(&mut self, args: &str) -> RenderingAction {
+        if !collation::AVAILABLE {
+            return self.show_error("Compiled without collation support");
         }
+        self.collation = match args {
+            "on" => true,
+            "off" => false,
+            "" => !self.collation,
+            _ => return self.show_error("Usage: collation [on|off]"),
+        };
+        let mode = if self.collation {
+            "collation"
+        } else {
+            "code points"
+        };
+        self.show_message(&format!("Sorting text by {}", mode))
     }
 
-    pub fn move_up(&mut self) -> RenderingAction {
-        if self.cur_pos.row == 1 {
-            if !self.first_row_visible() {
-                self.offsets.row -= 1;
-                return RenderingAction::Rerender;
-            } else {
-                self.cur_pos.row -= 1;
-                return RenderingAction::MoveCursor;
-            }
-        } else if self.cur_pos.row != 0 {
-            self.cur_pos.row -= 1;
-            return RenderingAction::MoveCursor;
+    /// `:case sensitive|insensitive|smart` sets the case sensitivity of searches.
+    fn set_search_case(&mut self, args: &str) -> RenderingAction {
+        self.search_case = match args {
+            "sensitive" => SearchCase::Sensitive,
+            "insensitive" => SearchCase::Insensitive,
+            "smart" => SearchCase::Smart,
+            "" => self.search_case,
+            _ => return self.show_error("Usage: case [sensitive|insensitive|smart]"),
         };
-        RenderingAction::None
+        let mode = match self.search_case {
+            SearchCase::Sensitive => "case-sensitive",
+            SearchCase::Insensitive => "case-insensitive",
+            SearchCase::Smart => "case-insensitive unless the pattern contains uppercase",
+        };
+        self.show_message(&format!("Searches are {}", mode))
     }
 
-    pub fn move_page_up(&mut self) -> RenderingAction {
-        if !self.first_row_visible() {
-            let new_row = self
-                .offsets
-                .row
-                .wrapping_sub(self.displayable_data_rows() - 1);
-            self.offsets.row = if new_row < self.offsets.row {
-                new_row
-            } else {
-                0
-            };
-            RenderingAction::Rerender
-        } else if self.cur_pos.row != 0 {
-            self.cur_pos.row = 0;
-            RenderingAction::MoveCursor
-        } else {
-            RenderingAction::None
+    /// `:literal on|off` switches between taking search patterns as typed and resolving escapes
+    /// like `\;` or `\t` in them.
+    fn set_literal_search(&mut self, args: &str) -> RenderingAction {
+        self.literal_search = match args {
+            "on" => true,
+            "off" => false,
+            "" => !self.literal_search,
+            _ => return self.show_error("Usage: literal [on|off]"),
+        };
+        match self.literal_search {
+            true => self.show_message("Search patterns are taken as typed"),
+            false => self.show_message("Search patterns resolve backslash escapes"),
         }
     }
 
-    pub fn move_home(&mut self) -> RenderingAction {
-        self.offsets.row = 0;
-        self.cur_pos.row = 0;
-        RenderingAction::Rerender
+    /// `:sortkey <expression>` makes sorting by the current column use the expression's value,
+    /// e.g. `lower(name)`; without expression the column's values are used again.
+    fn set_sort_key(&mut self, expression: &str) -> RenderingAction {
+        let col = self.current_column();
+        if expression.is_empty() {
+            self.sort_keys.remove(&col);
+            return self.show_message(&format!("Sorting {} by value", self.header[col]));
+        }
+        match Expr::parse(expression, &self.header) {
+            Ok(key) => {
+                self.sort_keys.insert(col, key);
+                self.show_message(&format!("Sorting {} by {}", self.header[col], expression))
+            }
+            Err(err) => self.show_error(&err),
+        }
     }
 
-    pub fn move_end(&mut self) -> RenderingAction {
-        // all data rows fit into one window
-        if self.rows.len() <= self.displayable_data_rows() {
-            self.cur_pos.row = self.rows.len();
+    /// `:jumpif <expression>` moves the cursor to the next shown row satisfying the expression,
+    /// e.g. `amount > 10000`, wrapping around at the end; the filters stay as they are.
+    fn jump_if(&mut self, expression: &str) -> RenderingAction {
+        if expression.is_empty() {
+            return self.show_error("Usage: jumpif <expression>");
         }
-        // move window to last position and cursor to last row
-        else {
-            self.offsets.row = self.rows.len() - self.displayable_data_rows();
-            self.cur_pos.row = self.terminal_size.y - 1;
+        let condition = match Expr::parse(expression, &self.header) {
+            Ok(condition) => condition,
+            Err(err) => return self.show_error(&err),
+        };
+        // data rows count from 0, the current one is `cur_row - 1`
+        let cur_row = self.current_row();
+        let found = (cur_row..self.row_count())
+            .chain(0..cur_row)
+            .find(|&row| condition.matches(self.view_row(row), &self.types));
+        match found {
+            Some(row) => {
+                self.jump_to_row(row);
+                if row < cur_row {
+                    self.status_message = Some("Jump wrapped around to the top".to_string());
+                }
+                RenderingAction::Rerender
+            }
+            None => self.show_error(&format!("No row with {}", expression)),
         }
-        RenderingAction::Rerender
     }
 
-    pub fn move_right(&mut self) -> RenderingAction {
-        // We are already in the last column
-        if self.current_column() == self.columns.len() - 1 {
-            return RenderingAction::None;
-        } else {
-            self.cur_pos.col += 1;
-            let cur_column = self.current_column();
-            let new_col = &self.columns[cur_column];
-            let new_col_end = new_col.index + new_col.width;
-            // The new column is completely within the displayed window
-            if new_col_end - self.columns[self.offsets.col].index <= self.terminal_size.x {
-                RenderingAction::MoveCursor
-            }
-            // The new column is (at least partially) outside of the displayed window
-            else {
-                // Find the first column offset for which the next column fits into the displayed window
-                for i in self.offsets.col..(cur_column + 1) {
-                    if new_col_end - self.columns[i].index <= self.terminal_size.x {
-                        self.cur_pos.col -= i - self.offsets.col;
-                        self.offsets.col = i;
-                        break;
+    // Indices of the rows passing all filters.
+    fn filtered_rows(&self) -> Vec<usize> {
+        let (rows, filters, types) = (&self.rows, &self.filters, &self.types);
+        (0..rows.len())
+            .filter(|&i| filters.iter().all(|filter| filter.matches(&rows[i], types)))
+            .collect()
+    }
+
+    // Recomputes the displayed rows from the filters and the sample.
+    fn refresh_view(&mut self) {
+        self.view = self.filtered_rows();
+        if let Some(sample) = &self.sample {
+            self.view = sample.select(&self.view, &self.origin, &self.rows);
+        }
+    }
+
+    /// `:sample <size> [stratify <column>] [seed=<number>]` shows a random sample of the rows
+    /// passing the filters, the same for the same seed; with `stratify`, each value of the column
+    /// keeps its share of the rows. `:sample off` shows all of them again.
+    fn sample(&mut self, args: &str) -> RenderingAction {
+        const USAGE: &str =
+            "Usage: :sample <size> [stratify <column>] [seed=<number>] or :sample off";
+        if args == "off" {
+            return match self.sample.take() {
+                Some(_) => self.apply_filters(),
+                None => self.show_error("No sample active"),
+            };
+        }
+        let (size, rest) = match args.split_once(char::is_whitespace) {
+            Some((size, rest)) => (size, rest.trim()),
+            None => (args, ""),
+        };
+        let size = match size.parse() {
+            Ok(size) => size,
+            Err(_) => return self.show_error(USAGE),
+        };
+        let (stratify, options) = match rest.strip_prefix("stratify ") {
+            Some(rest) => {
+                let end = rest.find(" seed=").unwrap_or(rest.len());
+                match self.column_index(rest[..end].trim()) {
+                    Some(col) => (Some(col), &rest[end..]),
+                    None => {
+                        let message = format!("Unknown column: {}", rest[..end].trim());
+                        return self.show_error(&message);
                     }
                 }
-                RenderingAction::Rerender
+            }
+            None => (None, rest),
+        };
+        let options = match parse_options(options) {
+            Ok(options) => options,
+            Err(err) => return self.show_error(&err),
+        };
+        // short enough to be typed again
+        let mut seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos() as u64 % 100_000);
+        for (key, value) in options {
+            match (key.as_str(), value.parse()) {
+                ("seed", Ok(value)) => seed = value,
+                ("seed", Err(_)) => return self.show_error("seed must be a number"),
+                _ => return self.show_error(&format!("Unknown option: {}", key)),
             }
         }
+        self.sample = Some(Sample {
+            size,
+            seed,
+            stratify,
+        });
+        let action = self.apply_filters();
+        let stratified = match stratify {
+            Some(col) => format!(", stratified by {}", self.header[col]),
+            None => String::new(),
+        };
+        self.status_message = Some(format!(
+            "Sample of {} of {} rows{} (seed {})",
+            group_digits(self.row_count()),
+            group_digits(self.filtered_rows().len()),
+            stratified,
+            seed
+        ));
+        action
     }
 
-    pub fn move_left(&mut self) -> RenderingAction {
-        if self.cur_pos.col == 0 {
-            if self.offsets.col != 0 {
-                self.offsets.col -= 1;
-                return RenderingAction::Rerender;
+    /// `:filter <column> <op> <value>` hides rows not matching the predicate; filters stack.
+    /// `:filter tag:<tag>` keeps tagged rows. Without arguments, all filters are removed.
+    pub fn filter(&mut self, expression: &str) -> RenderingAction {
+        if expression.is_empty() {
+            if !self.filters.is_empty() {
+                let question = format!("Remove all {} filters?", self.filters.len());
+                return self.confirm(question, |state| {
+                    state.filters.clear();
+                    state.apply_filters()
+                });
+            }
+        } else if let Some(tag) = expression.strip_prefix("tag:") {
+            match self.tag_col {
+                Some(col) => self.filters.push(Filter::tag(col, tag.trim())),
+                None => return self.show_error("No tagged rows"),
             }
         } else {
-            self.cur_pos.col -= 1;
-            return RenderingAction::MoveCursor;
+            match Filter::parse(expression, &self.header) {
+                Ok(filter) => self.filters.push(filter),
+                Err(err) => return self.show_error(&err),
+            }
         }
-        RenderingAction::None
+        self.apply_filters()
     }
 
-    pub fn move_start_of_line(&mut self) -> RenderingAction {
-        self.cur_pos.col = 0;
-        if self.offsets.col == 0 {
-            return RenderingAction::MoveCursor;
+    /// `F` keeps the rows whose current column equals the value under the cursor, on top of the
+    /// other filters. The cursor stays on its row.
+    pub fn filter_value(&mut self) -> RenderingAction {
+        let position = match self.current_data_row() {
+            Some(position) => position,
+            None => return self.show_error("No value under the cursor"),
+        };
+        let row = self.view[position];
+        let col = self.current_column();
+        self.filters.push(Filter {
+            col,
+            op: Op::Eq,
+            value: self.rows[row][col].clone(),
+            other: None,
+        });
+        let action = self.apply_filters();
+        if let Some(position) = self.view.iter().position(|&i| i == row) {
+            self.jump_to_row(position);
         }
-        self.offsets.col = 0;
-        RenderingAction::Rerender
+        action
     }
 
-    pub fn move_end_of_line(&mut self) -> RenderingAction {
-        let last_col = &self.columns[self.columns.len() - 1];
-        let complete_width = last_col.index + last_col.width;
-        for (i, col) in self.columns.iter().enumerate() {
-            if complete_width - col.index <= self.terminal_size.x {
-                self.offsets.col = i;
-                self.cur_pos.col = self.columns.len() - i - 1;
-                break;
+    /// `Backspace` or `:clearfilter` removes the filter added last.
+    pub fn pop_filter(&mut self) -> RenderingAction {
+        match self.filters.pop() {
+            Some(filter) => {
+                let action = self.apply_filters();
+                self.status_message = Some(format!(
+                    "Removed {}: {} of {} rows",
+                    filter.describe(&self.header),
+                    self.row_count(),
+                    self.rows.len()
+                ));
+                action
             }
+            None => self.show_error("No filter active"),
+        }
+    }
+
+    /// Starts typing a filter on the current column, e.g. `:filter price `.
+    pub fn start_column_filter(&mut self) -> RenderingAction {
+        let name = &self.header[self.current_column()];
+        self.command_buffer = format!(":filter {} ", name).chars().collect();
+        self.command_tail = 0;
+        RenderingAction::Command
+    }
+
+    /// `Ctrl-f` opens a form for choosing the column, operator and value of a filter, starting
+    /// with the column under the cursor.
+    pub fn start_filter_builder(&mut self) -> RenderingAction {
+        let columns: Vec<usize> = self.order[1..]
+            .iter()
+            .filter(|col| !self.hidden.contains(col))
+            .copied()
+            .collect();
+        if columns.is_empty() {
+            return self.show_error("No columns to filter");
+        }
+        self.filter_builder = Some(FilterBuilder::new(columns, self.current_column()));
+        self.update_builder_values();
+        RenderingAction::Pane
+    }
+
+    // Offers the values of the column chosen in the filter builder for completion.
+    fn update_builder_values(&mut self) {
+        if let Some(col) = self.filter_builder.as_ref().map(FilterBuilder::col) {
+            let values = self
+                .value_counts(col)
+                .into_iter()
+                .map(|(value, _)| value.to_string())
+                .collect();
+            if let Some(builder) = &mut self.filter_builder {
+                builder.values = values;
+            }
+        }
+    }
+
+    /// Moves to the next or previous field of the filter builder.
+    pub fn builder_move_field(&mut self, down: bool) -> RenderingAction {
+        match &mut self.filter_builder {
+            Some(builder) => builder.move_field(down),
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Chooses the next or previous column or operator in the filter builder; forward in the
+    /// value field completes the value.
+    pub fn builder_choose(&mut self, forward: bool) -> RenderingAction {
+        let builder = match &mut self.filter_builder {
+            Some(builder) => builder,
+            None => return RenderingAction::None,
+        };
+        match builder.field {
+            Field::Value if forward => builder.complete(),
+            Field::Column => {
+                let action = builder.choose(forward);
+                self.update_builder_values();
+                action
+            }
+            _ => builder.choose(forward),
+        }
+    }
+
+    /// Completes the value in the filter builder or else moves to the next field.
+    pub fn builder_complete(&mut self) -> RenderingAction {
+        match &mut self.filter_builder {
+            Some(builder) if builder.field == Field::Value => builder.complete(),
+            Some(builder) => builder.move_field(true),
+            None => RenderingAction::None,
+        }
+    }
+
+    pub fn builder_insert(&mut self, c: char) -> RenderingAction {
+        match &mut self.filter_builder {
+            Some(builder) => builder.insert(c),
+            None => RenderingAction::None,
+        }
+    }
+
+    pub fn builder_delete(&mut self) -> RenderingAction {
+        match &mut self.filter_builder {
+            Some(builder) => builder.delete(),
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Applies the filter built in the form as if typed with `:filter`, which goes into the
+    /// command history, so that it can be edited and run again from the command line.
+    pub fn apply_filter_builder(&mut self) -> RenderingAction {
+        let expression = match self.filter_builder.take() {
+            Some(builder) => builder.expression(&self.header),
+            None => return RenderingAction::None,
+        };
+        let command = format!(":filter {}", expression);
+        command::remember(&mut self.command_history, &command);
+        let action = self.filter(&expression);
+        if let Some(message) = &self.status_message {
+            self.status_message = Some(format!("{}: {}", command, message));
+        }
+        action.merge(RenderingAction::Rerender)
+    }
+
+    // Shows the rows passing the current filters from the top.
+    fn apply_filters(&mut self) -> RenderingAction {
+        self.refresh_view();
+        self.offsets.row = 0;
+        self.cur_pos.row = 0;
+        self.status_message = Some(format!("{} of {} rows", self.row_count(), self.rows.len()));
+        RenderingAction::Rerender
+    }
+
+    /// `:write [--notes] <file>` exports the table as shown: the rows passing the filters in their
+    /// sorted order and the shown columns in their order, in the format given by the extension.
+    #[cfg(feature = "csv")]
+    fn write_view(&mut self, args: &str) -> RenderingAction {
+        let (notes, path) = match args.strip_prefix("--notes") {
+            Some(path) => (true, path.trim()),
+            None => (false, args),
+        };
+        if path.is_empty() {
+            return self.show_error("Usage: write [--notes] <file>");
+        }
+        self.confirm_overwrite(path, move |state, path| state.write_view_rows(notes, path))
+    }
+
+    #[cfg(feature = "csv")]
+    fn write_view_rows(&mut self, notes: bool, path: &str) -> RenderingAction {
+        let columns: Vec<usize> = self
+            .record_fields()
+            .into_iter()
+            .filter(|&col| notes || (Some(col) != self.note_col && Some(col) != self.tag_col))
+            .collect();
+        let rows = self.view.iter().map(|&i| self.edited_row(i));
+        match crate::writer::write_table(Path::new(path), &self.header, &columns, rows) {
+            Ok(count) => self.show_message(&format!(
+                "Wrote {} rows and {} columns to {}",
+                count,
+                columns.len(),
+                path
+            )),
+            Err(err) => self.show_error(&format!("Cannot write {}: {}", path, err)),
+        }
+    }
+
+    /// `:write! [--notes] <file>` exports the rows hidden by the current filters.
+    #[cfg(feature = "csv")]
+    fn write_rejected(&mut self, args: &str) -> RenderingAction {
+        let (notes, path) = match args.strip_prefix("--notes") {
+            Some(path) => (true, path.trim()),
+            None => (false, args),
+        };
+        if path.is_empty() {
+            return self.show_error("Usage: write! [--notes] <file>");
+        }
+        if self.filters.is_empty() {
+            return self.show_error("No filter active");
+        }
+        self.confirm_overwrite(path, move |state, path| {
+            state.write_rejected_rows(notes, path)
+        })
+    }
+
+    #[cfg(feature = "csv")]
+    fn write_rejected_rows(&mut self, notes: bool, path: &str) -> RenderingAction {
+        // rows left out of a sample are not rejected
+        let mut visible = vec![false; self.rows.len()];
+        for i in self.filtered_rows() {
+            visible[i] = true;
+        }
+        let rejected = self
+            .rows
+            .iter()
+            .zip(visible)
+            .enumerate()
+            .filter(|(_, (_, visible))| !visible)
+            .map(|(i, _)| self.edited_row(i));
+        let columns = self.export_columns(notes);
+        match crate::writer::write_table(Path::new(path), &self.header, &columns, rejected) {
+            Ok(count) => self.show_message(&format!("Wrote {} rejected rows to {}", count, path)),
+            Err(err) => self.show_error(&format!("Cannot write {}: {}", path, err)),
+        }
+    }
+
+    /// `:export tagged <file>` writes all rows with a tag, `:export tag:<tag> <file>` the rows
+    /// with the given tag, including notes and tags, and `:export stats <file>` the statistics
+    /// of each column. `--clipboard` instead of a file name copies them as tab-separated values.
+    #[cfg(feature = "csv")]
+    fn export(&mut self, args: &str) -> RenderingAction {
+        let (what, target) = match args.split_once(char::is_whitespace) {
+            Some((what, target)) => (what, target.trim()),
+            None => {
+                return self.show_error("Usage: export tagged|tag:<tag>|stats <file>|--clipboard")
+            }
+        };
+        if what == "stats" {
+            if target == "--clipboard" {
+                return self.write_stats(target);
+            }
+            return self.confirm_overwrite(target, |state, target| state.write_stats(target));
+        }
+        let tag = match what {
+            "tagged" => None,
+            _ => match what.strip_prefix("tag:") {
+                Some(tag) => Some(tag),
+                None => return self.show_error(&format!("Cannot export {}", what)),
+            },
+        };
+        let col = match self.tag_col {
+            Some(col) => col,
+            None => return self.show_error("No tagged rows"),
+        };
+        if target == "--clipboard" {
+            return self.write_tagged(col, tag, target);
+        }
+        let tag = tag.map(str::to_string);
+        self.confirm_overwrite(target, move |state, target| {
+            state.write_tagged(col, tag.as_deref(), target)
+        })
+    }
+
+    // Writes the rows with the tag, or any tag, in the tag column to the target.
+    #[cfg(feature = "csv")]
+    fn write_tagged(&mut self, col: usize, tag: Option<&str>, target: &str) -> RenderingAction {
+        let tagged = |row: &&Vec<String>| match tag {
+            Some(tag) => row[col].split(", ").any(|t| t == tag),
+            None => !row[col].is_empty(),
+        };
+        let columns = self.export_columns(true);
+        let rows = (0..self.rows.len())
+            .filter(|&i| tagged(&&self.rows[i]))
+            .map(|i| self.edited_row(i));
+        if target == "--clipboard" {
+            let count = self.rows.iter().filter(tagged).count();
+            self.clipboard = Some(crate::writer::format_table(&self.header, &columns, rows));
+            return self.show_message(&format!("Copied {} rows to the clipboard", count));
+        }
+        match crate::writer::write_table(Path::new(target), &self.header, &columns, rows) {
+            Ok(count) => self.show_message(&format!("Wrote {} rows to {}", count, target)),
+            Err(err) => self.show_error(&format!("Cannot write {}: {}", target, err)),
+        }
+    }
+
+    // Writes the table of `:describe` to the target, without the column numbers.
+    #[cfg(feature = "csv")]
+    fn write_stats(&mut self, target: &str) -> RenderingAction {
+        let (header, rows) = self.profile_table();
+        let columns: Vec<usize> = (1..header.len()).collect();
+        if target == "--clipboard" {
+            self.clipboard = Some(crate::writer::format_table(&header, &columns, rows.iter()));
+            return self.show_message(&format!(
+                "Copied statistics of {} columns to the clipboard",
+                rows.len()
+            ));
+        }
+        match crate::writer::write_table(Path::new(target), &header, &columns, rows.iter()) {
+            Ok(count) => self.show_message(&format!(
+                "Wrote statistics of {} columns to {}",
+                count, target
+            )),
+            Err(err) => self.show_error(&format!("Cannot write {}: {}", target, err)),
+        }
+    }
+
+    /// `:split by <column> <dir>` writes the displayed rows to one file per distinct value of
+    /// the column. The files are written in the background.
+    #[cfg(feature = "csv")]
+    fn split(&mut self, args: &str) -> RenderingAction {
+        let (name, dir) = match args
+            .strip_prefix("by ")
+            .and_then(|args| args.trim().rsplit_once(char::is_whitespace))
+        {
+            Some((name, dir)) => (name.trim(), dir.to_string()),
+            None => return self.show_error("Usage: split by <column> <dir>"),
+        };
+        let col = match self.column_index(name) {
+            Some(col) => col,
+            None => return self.show_error(&format!("Unknown column: {}", name)),
+        };
+        let mut groups: Vec<(String, Vec<Vec<String>>)> = Vec::new();
+        let mut group_index: HashMap<&str, usize> = HashMap::new();
+        for &i in &self.view {
+            let row = &self.rows[i];
+            let index = *group_index.entry(&row[col]).or_insert_with(|| {
+                groups.push((row[col].clone(), Vec::new()));
+                groups.len() - 1
+            });
+            groups[index].1.push(self.edited_row(i).into_owned());
+        }
+        let header = self.header.clone();
+        let columns = self.export_columns(false);
+        self.task = Some(Box::new(move |progress| {
+            let dir = Path::new(&dir);
+            if let Err(err) = std::fs::create_dir_all(dir) {
+                return format!("Cannot create {}: {}", dir.display(), err);
+            }
+            let mut names = std::collections::HashSet::new();
+            for (done, (value, rows)) in groups.iter().enumerate() {
+                progress(format!("Splitting: {}/{} files", done, groups.len()));
+                let mut name = file_name(value);
+                let mut suffix = 1;
+                // compare case-insensitively, file systems may not distinguish case
+                while !names.insert(name.to_lowercase()) {
+                    suffix += 1;
+                    name = format!("{}_{}", file_name(value), suffix);
+                }
+                let path = dir.join(format!("{}.csv", name));
+                if let Err(err) = crate::writer::write_table(&path, &header, &columns, rows.iter())
+                {
+                    return format!("Cannot write {}: {}", path.display(), err);
+                }
+            }
+            format!("Wrote {} files to {}", groups.len(), dir.display())
+        }));
+        self.show_message(&format!("Splitting by {}...", name))
+    }
+
+    /// Uses the session's notes and tags, showing them in virtual columns if there are any.
+    pub fn open_session(&mut self, session: Session) {
+        self.session = session;
+        if !self.session.notes.is_empty() {
+            self.note_col = Some(self.add_session_column("note", note_text));
+        }
+        if !self.session.tags.is_empty() {
+            self.tag_col = Some(self.add_session_column("tags", tag_text));
+        }
+    }
+
+    // Adds a string column with a value from the session for each row.
+    fn add_session_column(&mut self, name: &str, value: fn(&Session, u64) -> String) -> usize {
+        let values = (0..self.rows.len())
+            .map(|row| value(&self.session, self.row_hash(row)))
+            .collect();
+        let mut unique_name = name.to_string();
+        let mut suffix = 1;
+        while self.column_index(&unique_name).is_some() {
+            suffix += 1;
+            unique_name = format!("{}_{}", name, suffix);
+        }
+        self.add_column(unique_name, values);
+        let col = self.header.len() - 1;
+        self.types[col] = ColType::Str;
+        col
+    }
+
+    // Updates a session column for all rows with the given hash and saves the session.
+    fn update_session_column(&mut self, col: usize, hash: u64, text: String) -> RenderingAction {
+        // rows with identical values share notes and tags
+        for i in 0..self.rows.len() {
+            if self.row_hash(i) == hash {
+                self.rows[i][col] = text.clone();
+            }
+        }
+        let width = min(display_width(&text) + self.padding, self.terminal_size.x);
+        let mut widths = self.widths();
+        if width > widths[col] && !self.hidden.contains(&col) {
+            widths[col] = width;
+            self.set_widths(widths);
+        }
+        self.summaries.remove(&col);
+        if let Err(err) = self.session.save() {
+            return self.show_error(&format!("Cannot save session: {}", err));
+        }
+        RenderingAction::Rerender
+    }
+
+    // Hash of the data row under the cursor
+    fn current_row_hash(&self) -> Option<u64> {
+        self.current_data_row()
+            .map(|position| self.row_hash(self.view[position]))
+    }
+
+    /// Switches between the table and the record view of the current row, starting with the
+    /// first row if the cursor is in the header.
+    pub fn toggle_record_view(&mut self) -> RenderingAction {
+        if self.record_view.take().is_some() {
+            return RenderingAction::Rerender;
+        }
+        if self.row_count() == 0 {
+            return self.show_error("No rows");
+        }
+        if self.cur_pos.row == 0 {
+            self.move_down();
+        }
+        self.record_view = Some(0);
+        RenderingAction::Rerender
+    }
+
+    /// Scrolls the fields of the record view down or up by a page.
+    pub fn scroll_record(&mut self, down: bool) -> RenderingAction {
+        let page = self.displayable_data_rows();
+        let last = self.record_fields().len().saturating_sub(page);
+        match &mut self.record_view {
+            Some(offset) if down && *offset < last => *offset = min(*offset + page, last),
+            Some(offset) if !down && *offset > 0 => *offset = offset.saturating_sub(page),
+            _ => return RenderingAction::None,
+        }
+        RenderingAction::Rerender
+    }
+
+    // Columns shown in the record view: the shown ones in display order without row numbers.
+    fn record_fields(&self) -> Vec<usize> {
+        self.order
+            .iter()
+            .copied()
+            .filter(|col| *col != 0 && !self.hidden.contains(col))
+            .collect()
+    }
+
+    /// Title and one line per field of the record view, like `psql`'s expanded mode.
+    pub fn record_lines(&self) -> Vec<String> {
+        let position = match self.current_data_row() {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+        let row = self.view[position];
+        let values = self.edited_row(row);
+        let fields = self.record_fields();
+        let name_width = fields
+            .iter()
+            .map(|&col| display_width(&self.header[col]))
+            .max()
+            .unwrap_or_default();
+        let title = format!(
+            "Row {} ({} of {})",
+            self.rows[row][0],
+            group_digits(position + 1),
+            group_digits(self.row_count())
+        );
+        once(title)
+            .chain(
+                fields
+                    .iter()
+                    .skip(self.record_view.unwrap_or_default())
+                    .take(self.displayable_data_rows())
+                    .map(|&col| {
+                        let name = display_text(&self.header[col]);
+                        let value = display_text(&values[col]);
+                        format!("{:name_width$} │ {}", name, value)
+                    }),
+            )
+            .collect()
+    }
+
+    /// Marks the row under the cursor as anchor for comparisons with `compare_rows`.
+    pub fn mark_anchor(&mut self) -> RenderingAction {
+        let number = match self.current_data_row() {
+            Some(position) => self.row_number(self.view[position]),
+            None => return self.show_error("No row selected"),
+        };
+        self.anchor = number;
+        match number {
+            Some(number) => self.show_message(&format!(
+                "Marked row {}, press c on another row to compare",
+                number
+            )),
+            None => self.show_error("Row cannot be marked"),
+        }
+    }
+
+    /// Opens a pane showing the values of the anchor row and the row under the cursor side by
+    /// side, highlighting the columns in which they differ.
+    pub fn compare_rows(&mut self) -> RenderingAction {
+        let anchor = match self.anchor.and_then(|number| {
+            (0..self.rows.len()).find(|&row| self.row_number(row) == Some(number))
+        }) {
+            Some(anchor) => anchor,
+            None => return self.show_error("No row marked, press m on a row first"),
+        };
+        let row = match self.current_data_row() {
+            Some(position) => self.view[position],
+            None => return self.show_error("No row selected"),
+        };
+        if row == anchor {
+            return self.show_error("Cannot compare the marked row with itself");
+        }
+        let (left, right) = (self.edited_row(anchor), self.edited_row(row));
+        let name_width = self.header[1..]
+            .iter()
+            .map(|name| display_width(name))
+            .max()
+            .unwrap_or_default();
+        let value_width = (self.terminal_size.x.saturating_sub(name_width + 6) / 2).max(1);
+        let entries: Vec<PaneEntry> = (1..self.header.len())
+            .map(|col| {
+                let differs = Cell::parse(&left[col], self.types[col])
+                    .total_cmp(&Cell::parse(&right[col], self.types[col]))
+                    != Ordering::Equal;
+                PaneEntry {
+                    row: Some(row),
+                    col,
+                    text: format!(
+                        "{} {} {} {}",
+                        fixed_width(&self.header[col], name_width + 1),
+                        fixed_width(&left[col], value_width),
+                        if differs { "≠" } else { " " },
+                        right[col]
+                    ),
+                    highlight: differs,
+                }
+            })
+            .collect();
+        let differences = entries.iter().filter(|entry| entry.highlight).count();
+        let title = format!(
+            "Row {} vs. row {}: {} of {} columns differ",
+            left[0],
+            right[0],
+            differences,
+            entries.len()
+        );
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// `:note <text>` attaches a note to the current row; without text the note is removed.
+    fn note(&mut self, text: &str) -> RenderingAction {
+        let hash = match self.current_row_hash() {
+            Some(hash) => hash,
+            None => return self.show_error("No row selected"),
+        };
+        if text.is_empty() {
+            self.session.notes.remove(&hash);
+        } else {
+            self.session.notes.insert(hash, text.to_string());
+        }
+        let col = match self.note_col {
+            Some(col) => col,
+            None => {
+                let col = self.add_session_column("note", note_text);
+                self.note_col = Some(col);
+                col
+            }
+        };
+        self.update_session_column(col, hash, text.to_string())
+    }
+
+    /// `e` starts typing an edit of the current cell, with its value on the command line.
+    pub fn start_edit(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        if col == 0 || col >= self.data_width {
+            return self.show_error("Column cannot be edited");
+        }
+        let value = match self.current_data_row() {
+            Some(position) => self.view_row(position)[col].clone(),
+            None => return self.show_error("No row selected"),
+        };
+        self.command_buffer = format!(":edit {}", value).chars().collect();
+        self.command_tail = 0;
+        RenderingAction::Command
+    }
+
+    /// `:edit <value>` sets the value of the current cell. The edit is kept in the session's
+    /// journal and shown instead of the loaded value until the table is saved.
+    fn edit(&mut self, value: &str) -> RenderingAction {
+        let col = self.current_column();
+        if col == 0 || col >= self.data_width {
+            return self.show_error("Column cannot be edited");
+        }
+        let row = match self.current_data_row() {
+            Some(position) => self.view[position],
+            None => return self.show_error("No row selected"),
+        };
+        match self.row_number(row) {
+            Some(number) if value == self.rows[row][col] => {
+                self.session.journal.remove(&(number, col));
+            }
+            Some(number) => {
+                self.session
+                    .journal
+                    .insert((number, col), value.to_string());
+            }
+            // added rows are not in the file yet, so they change in place
+            None if self.rows[row][0] == ADDED_ROW => self.rows[row][col] = value.to_string(),
+            None => return self.show_error("Row cannot be edited"),
+        }
+        let width = min(display_width(value) + self.padding, self.terminal_size.x);
+        let mut widths = self.widths();
+        if width > widths[col] && !self.hidden.contains(&col) {
+            widths[col] = width;
+            self.set_widths(widths);
+        }
+        if let Err(err) = self.session.save() {
+            return self.show_error(&format!("Cannot save session: {}", err));
+        }
+        RenderingAction::Rerender
+    }
+
+    /// `dd` deletes the row under the cursor, dropping its pending edits. Like the other changes
+    /// of rows, the deletion is only written to the file by `:save`.
+    pub fn delete_row(&mut self) -> RenderingAction {
+        if self.parent.is_some() {
+            return self.show_error("Rows of derived tables cannot be changed");
+        }
+        let row = match self.current_data_row() {
+            Some(position) => self.view[position],
+            None => return self.show_error("No row selected"),
+        };
+        if let Some(number) = self.row_number(row) {
+            let edited: Vec<_> = self
+                .session
+                .journal
+                .range((number, 0)..(number, self.data_width))
+                .map(|(&cell, _)| cell)
+                .collect();
+            for cell in edited {
+                self.session.journal.remove(&cell);
+            }
+            if let Err(err) = self.session.save() {
+                return self.show_error(&format!("Cannot save session: {}", err));
+            }
+        }
+        let deleted = self.rows.remove(row);
+        let origin = self.origin.remove(row);
+        for position in self
+            .origin
+            .iter_mut()
+            .filter(|position| **position > origin)
+        {
+            *position -= 1;
+        }
+        self.rows_changed();
+        if self.current_row() > self.row_count() {
+            self.move_up();
+        }
+        self.show_message(&format!("Deleted row {}, :w saves the table", deleted[0]))
+    }
+
+    /// `p` puts a copy of the row under the cursor below it and moves there. The copy is numbered
+    /// `+` until the table is saved.
+    pub fn duplicate_row(&mut self) -> RenderingAction {
+        if self.parent.is_some() {
+            return self.show_error("Rows of derived tables cannot be changed");
+        }
+        let row = match self.current_data_row() {
+            Some(position) => self.view[position],
+            None => return self.show_error("No row selected"),
+        };
+        let mut copy = self.edited_row(row).into_owned();
+        copy[0] = ADDED_ROW.to_string();
+        let origin = self.origin[row];
+        for position in self
+            .origin
+            .iter_mut()
+            .filter(|position| **position > origin)
+        {
+            *position += 1;
+        }
+        self.rows.insert(row + 1, copy);
+        self.origin.insert(row + 1, origin + 1);
+        self.rows_changed();
+        // the copy passes the same filters, but may be left out of a sample
+        match self.view.get(self.current_row()) {
+            Some(&next) if next == row + 1 => {
+                self.move_down();
+            }
+            _ => (),
+        }
+        let number = self.rows[row][0].clone();
+        self.show_message(&format!("Duplicated row {}, :w saves the table", number))
+    }
+
+    // Shows the rows after some were deleted or added, whose summaries are out of date.
+    fn rows_changed(&mut self) {
+        self.row_changes += 1;
+        self.reloads += 1;
+        self.summaries.clear();
+        self.refresh_view();
+    }
+
+    /// Opens a pane listing the pending edits.
+    fn show_changes(&mut self) -> RenderingAction {
+        let rows: HashMap<usize, usize> = (0..self.rows.len())
+            .filter_map(|row| Some((self.row_number(row)?, row)))
+            .collect();
+        let entries: Vec<PaneEntry> = self
+            .session
+            .journal
+            .iter()
+            .filter(|((_, col), _)| *col < self.data_width)
+            .map(|(&(number, col), value)| {
+                let row = rows.get(&number).copied();
+                let old = row.map(|row| self.rows[row][col].as_str()).unwrap_or("");
+                PaneEntry {
+                    row,
+                    col,
+                    text: format!("{} {}: {} -> {}", number, self.header[col], old, value),
+                    highlight: false,
+                }
+            })
+            .collect();
+        let title = format!("{} pending edits", entries.len());
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// `:save` (or `:w`) writes the table with the pending edits applied back to its file, in
+    /// the original row order, and clears the journal. The file is replaced atomically and only
+    /// if it did not change on disk since loading, unless forced with `:save!`.
+    #[cfg(feature = "csv")]
+    fn save(&mut self, force: bool) -> RenderingAction {
+        let path = match self.session.table_path() {
+            Some(path) => path.to_path_buf(),
+            None => return self.show_error("Table was not read from a file"),
+        };
+        let (delimiter, quote) = match self.delimited {
+            Some(format) => format,
+            None => return self.show_error("Cannot save tables of this format"),
+        };
+        if !force && self.session.table_changed() {
+            return self.show_error(&format!(
+                "{} changed on disk: reopen it to reload (edits are kept) or overwrite with :save!",
+                path.display()
+            ));
+        }
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        order.sort_by_key(|&row| self.origin[row]);
+        let rows = order.iter().map(|&row| self.edited_row(row));
+        let columns: Vec<usize> = (1..self.data_width).collect();
+        let header = &self.header;
+        let written = crate::writer::replace_file(&path, self.backup, |file| {
+            crate::writer::write_delimited(file, delimiter, quote, header, &columns, rows)
+                .map(|_| ())
+        });
+        if let Err(err) = written {
+            return self.show_error(&format!("Cannot write {}: {}", path.display(), err));
+        }
+        self.session.table_written();
+        let count = self.session.journal.len();
+        self.apply_journal();
+        if let Err(err) = self.session.save() {
+            return self.show_error(&format!("Cannot save session: {}", err));
+        }
+        if self.row_changes == 0 {
+            return self.show_message(&format!("Saved {} edits to {}", count, path.display()));
+        }
+        // rows are numbered as in the written file again
+        for (i, &row) in order.iter().enumerate() {
+            self.rows[row][0] = (i + 1).to_string();
+            self.origin[row] = i;
+        }
+        self.anchor = None;
+        let changes = std::mem::take(&mut self.row_changes);
+        self.show_message(&format!(
+            "Saved {} edits and {} row changes to {}",
+            count,
+            changes,
+            path.display()
+        ))
+    }
+
+    /// `:reparse delim=';' quote='\''` reads the table file again with another delimiter or
+    /// quote character, keeping the cursor on the same row. Filters and sort keys are dropped,
+    /// since columns may have changed.
+    #[cfg(feature = "csv")]
+    fn reparse(&mut self, args: &str) -> RenderingAction {
+        let (mut delimiter, mut quote) = self.delimited.unwrap_or((b',', b'"'));
+        let options = match parse_options(args) {
+            Ok(options) if !options.is_empty() => options,
+            Ok(_) => return self.show_error("Usage: reparse delim=<char> quote=<char>"),
+            Err(err) => return self.show_error(&err),
+        };
+        for (key, value) in options {
+            let target = match key.as_str() {
+                "delim" => &mut delimiter,
+                "quote" => &mut quote,
+                _ => return self.show_error(&format!("Unknown option: {}", key)),
+            };
+            match value.as_bytes() {
+                [byte] => *target = *byte,
+                _ => return self.show_error(&format!("{} must be a single character", key)),
+            }
+        }
+        if let Err(err) = self.read_again((delimiter, quote)) {
+            return self.show_error(&err);
+        }
+        let columns = self.data_width - 1;
+        self.show_message(&format!("{} columns, {} rows", columns, self.rows.len()))
+    }
+
+    /// `:loadall` reads the whole table file after loading stopped at the row cap, keeping the
+    /// cursor on the same row. Like `:reparse`, it drops filters and sort keys.
+    #[cfg(feature = "csv")]
+    fn load_all(&mut self) -> RenderingAction {
+        if self.row_cap.is_none() {
+            return self.show_error("All rows are loaded");
+        }
+        let delimited = match self.delimited {
+            Some(delimited) => delimited,
+            None => return self.show_error("Only delimited files can be read again"),
+        };
+        if let Err(err) = self.read_again(delimited) {
+            return self.show_error(&err);
+        }
+        self.show_message(&format!(
+            "Loaded all {} rows",
+            group_digits(self.rows.len())
+        ))
+    }
+
+    // Reads the whole table file again with the given delimiter and quote character in place of
+    // the current table, keeping the session and the cursor on the same row.
+    #[cfg(feature = "csv")]
+    fn read_again(&mut self, (delimiter, quote): (u8, u8)) -> Result<(), String> {
+        let path = match self.session.table_path() {
+            Some(path) => path.to_path_buf(),
+            None => return Err("Table was not read from a file".to_string()),
+        };
+        let start = Instant::now();
+        let (header, rows) = crate::csv::read_csv_from_file(&path, delimiter, quote)
+            .map_err(|err| format!("Cannot read {}: {}", path.display(), err))?;
+        let row = self.current_data_row();
+        let session = std::mem::take(&mut self.session);
+        self.replace_table(header, rows, session, (delimiter, quote));
+        self.load_time = Some(start.elapsed());
+        if let Some(row) = row.filter(|_| self.row_count() > 0) {
+            self.jump_to_row(min(row, self.row_count() - 1));
+        }
+        Ok(())
+    }
+
+    /// `:open [dir]` shows the CSV and TSV files of a directory (default: the current one)
+    /// to choose one to open instead of the current table.
+    #[cfg(feature = "csv")]
+    fn show_file_picker(&mut self, dir: &str) -> RenderingAction {
+        let dir = if dir.is_empty() { "." } else { dir };
+        match FilePicker::new(Path::new(dir)) {
+            Ok(picker) => {
+                self.picker = Some(picker);
+                RenderingAction::Pane
+            }
+            Err(err) => self.show_error(&format!("Cannot list {}: {}", dir, err)),
+        }
+    }
+
+    /// Opens a pane with the complete value of the cell under the cursor, wrapped to the width
+    /// of the terminal.
+    pub fn show_cell(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        let (row, value, title) = match self.current_data_row() {
+            Some(position) => {
+                let row = self.view[position];
+                let title = format!("{} in row {}", self.header[col], self.rows[row][0]);
+                (Some(row), self.edited_row(row)[col].clone(), title)
+            }
+            None => (None, self.header[col].clone(), "Column name".to_string()),
+        };
+        let entries = wrap(&value, self.terminal_size.x)
+            .into_iter()
+            .map(|text| PaneEntry {
+                row,
+                col,
+                text,
+                highlight: false,
+            })
+            .collect();
+        let title = format!("{} ({} characters)", title, value.chars().count());
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// `y` copies the value under the cursor, or the column name in the header, to the clipboard.
+    pub fn yank_cell(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        let (value, what) = match self.current_data_row() {
+            Some(position) => {
+                let row = self.view[position];
+                let what = format!("{} in row {}", self.header[col], self.rows[row][0]);
+                (self.edited_row(row)[col].clone(), what)
+            }
+            None => (self.header[col].clone(), "column name".to_string()),
+        };
+        self.clipboard = Some(value);
+        self.show_message(&format!("Copied {}", what))
+    }
+
+    /// `yy` copies the shown fields of the current row, or the header, tab-separated.
+    pub fn yank_row(&mut self) -> RenderingAction {
+        let fields = self.record_fields();
+        let (row, what) = match self.current_data_row() {
+            Some(position) => {
+                let row = self.view[position];
+                let what = format!("row {}", self.rows[row][0]);
+                (self.edited_row(row).into_owned(), what)
+            }
+            None => (self.header.clone(), "header".to_string()),
+        };
+        let values = fields.iter().map(|&col| row[col].as_str());
+        self.clipboard = Some(clipboard::join_fields(values, "\t"));
+        self.show_message(&format!("Copied {} ({} fields)", what, fields.len()))
+    }
+
+    /// `yc` copies the values of the current column in the shown rows, one per line.
+    pub fn yank_column(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        let values: Vec<String> = (0..self.row_count())
+            .map(|position| self.edited_row(self.view[position])[col].clone())
+            .collect();
+        let text = clipboard::join_fields(values.iter().map(String::as_str), "\n");
+        self.clipboard = Some(text);
+        self.show_message(&format!(
+            "Copied {} values of {}",
+            group_digits(values.len()),
+            self.header[col]
+        ))
+    }
+
+    /// Opens a pane with facts about the file and the loaded table, e.g. for bug reports.
+    fn show_info(&mut self) -> RenderingAction {
+        let mut lines = Vec::new();
+        match self.session.table_path() {
+            Some(path) => {
+                lines.push(format!("File: {}", path.display()));
+                match std::fs::metadata(path) {
+                    Ok(metadata) => lines.push(format!("Size: {}", format_size(metadata.len()))),
+                    Err(err) => lines.push(format!("Size: unknown ({})", err)),
+                }
+                lines.push(format!("Encoding: {}", encoding(path)));
+            }
+            None => lines.push("File: standard input".to_string()),
+        }
+        if let Some((delimiter, quote)) = self.delimited {
+            lines.push(format!(
+                "Delimiter: {}, quote: {}",
+                describe_byte(delimiter),
+                describe_byte(quote)
+            ));
+        }
+        lines.push(format!(
+            "Rows: {} ({} shown)",
+            self.rows.len(),
+            self.row_count()
+        ));
+        lines.push(format!(
+            "Columns: {} ({} added)",
+            self.data_width - 1,
+            self.header.len() - self.data_width
+        ));
+        if let Some(load_time) = self.load_time {
+            lines.push(format!("Load time: {} ms", load_time.as_millis()));
+        }
+        let memory: usize = self
+            .rows
+            .iter()
+            .map(|row| {
+                let values: usize = row.iter().map(|value| value.capacity()).sum();
+                std::mem::size_of::<Vec<String>>()
+                    + row.capacity() * std::mem::size_of::<String>()
+                    + values
+            })
+            .sum();
+        lines.push(format!("Memory: about {}", format_size(memory as u64)));
+        let col = self.current_column();
+        let entries = lines
+            .into_iter()
+            .map(|text| PaneEntry {
+                row: None,
+                col,
+                text,
+                highlight: false,
+            })
+            .collect();
+        self.pane = Some(ListPane::new("Table info".to_string(), entries));
+        RenderingAction::Pane
+    }
+
+    /// `S` or `:stats` shows statistics of the current column. With filters active, the statistics of
+    /// the shown rows are next to those of all rows, highlighting where they differ.
+    pub fn show_stats(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        if col == 0 {
+            return self.show_error("No statistics for row numbers");
+        }
+        let col_type = self.types[col];
+        let rows = &self.rows;
+        let lines = |indices: &mut dyn Iterator<Item = &usize>| -> Vec<String> {
+            let values: Vec<&str> = indices.map(|&i| rows[i][col].as_str()).collect();
+            let profile = stats::profile(values.iter().copied(), col_type);
+            let number =
+                |number: Option<f64>| number.map_or("-".to_string(), |n| format!("{:.4}", n));
+            let summary = profile.summary;
+            vec![
+                summary.col_type.to_string(),
+                group_digits(summary.count),
+                format!("{} ({:.1}%)", summary.nulls, summary.null_percentage()),
+                group_digits(summary.distinct),
+                summary.min.unwrap_or_else(|| "-".to_string()),
+                summary.max.unwrap_or_else(|| "-".to_string()),
+                number(profile.mean),
+                number(profile.median),
+                number(profile.std_dev),
+            ]
+        };
+        let names = [
+            "type", "rows", "nulls", "distinct", "min", "max", "mean", "median", "stddev",
+        ];
+        let all: Vec<usize> = (0..rows.len()).collect();
+        let all = lines(&mut all.iter());
+        let lines: Vec<(String, bool)> = match self.filters.is_empty() {
+            true => names
+                .iter()
+                .zip(all)
+                .map(|(name, value)| (format!("{:9}{}", name, value), false))
+                .collect(),
+            false => {
+                let shown = lines(&mut self.view.iter());
+                let width = shown.iter().map(|value| value.chars().count()).max();
+                let width = width.unwrap_or_default().max(8);
+                let rows = names
+                    .iter()
+                    .zip(shown)
+                    .zip(all)
+                    .map(|((name, shown), all)| {
+                        let differs = shown != all;
+                        (format!("{:9}{:width$}  {}", name, shown, all), differs)
+                    });
+                once((format!("{:9}{:width$}  all", "", "filtered"), false))
+                    .chain(rows)
+                    .collect()
+            }
+        };
+        let entries = lines
+            .into_iter()
+            .map(|(text, highlight)| PaneEntry {
+                row: None,
+                col,
+                text,
+                highlight,
+            })
+            .collect();
+        let title = format!("Statistics of {}", self.header[col]);
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// `:bucket <column> by <size> [count]` counts the shown rows per interval of a date column,
+    /// e.g. `:bucket created by 1h count`, in a pane with one line and bar per interval.
+    fn bucket(&mut self, args: &str) -> RenderingAction {
+        const USAGE: &str = "Usage: :bucket <column> by <size, e.g. 15m, 1h, 1d> [count]";
+        let (name, rest) = match args.rsplit_once(" by ") {
+            Some((name, rest)) => (name.trim(), rest.split_whitespace().collect::<Vec<_>>()),
+            None => return self.show_error(USAGE),
+        };
+        let size = match rest.as_slice() {
+            [size] | [size, "count"] => match parse_duration(size) {
+                Some(size) => size,
+                None => return self.show_error(USAGE),
+            },
+            [_, aggregate] => {
+                return self.show_error(&format!("Unknown aggregate: {} (only count)", aggregate))
+            }
+            _ => return self.show_error(USAGE),
+        };
+        let col = match self.column_index(name) {
+            Some(col) => col,
+            None => return self.show_error(&format!("Unknown column: {}", name)),
+        };
+        let times = self
+            .view
+            .iter()
+            .filter_map(|&i| Some((i, parse_date(self.rows[i][col].trim())?)));
+        let buckets = match stats::time_buckets(times, size, 100_000) {
+            Ok(buckets) if buckets.is_empty() => {
+                return self.show_error(&format!("No dates in {}", name))
+            }
+            Ok(buckets) => buckets,
+            Err(err) => return self.show_error(&err),
+        };
+        let labels: Vec<String> = buckets.iter().map(|b| format_date(b.start)).collect();
+        let counts: Vec<String> = buckets.iter().map(|b| group_digits(b.count)).collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or_default();
+        let count_width = counts.iter().map(String::len).max().unwrap_or_default();
+        let bar_width = self
+            .terminal_size
+            .x
+            .saturating_sub(label_width + count_width + 4);
+        let most = buckets
+            .iter()
+            .map(|b| b.count)
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let entries = buckets
+            .iter()
+            .zip(labels.iter().zip(&counts))
+            .map(|(bucket, (label, count))| PaneEntry {
+                row: bucket.first_row,
+                col,
+                text: format!(
+                    "{:label_width$}  {:>count_width$}  {}",
+                    label,
+                    count,
+                    "█".repeat(bucket.count * bar_width / most)
+                ),
+                highlight: false,
+            })
+            .collect();
+        let title = format!("Rows per {} of {}", rest[0], name);
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// `:plot <x column>,<y column>` shows a scatter plot of the shown rows with numeric values
+    /// in both columns.
+    fn plot(&mut self, args: &str) -> RenderingAction {
+        let (x, y) = match args.split_once(',') {
+            Some((x, y)) => (x.trim(), y.trim()),
+            None => return self.show_error("Usage: :plot <x column>,<y column>"),
+        };
+        let (x, y) = match (self.column_index(x), self.column_index(y)) {
+            (Some(x), Some(y)) => (x, y),
+            (None, _) => return self.show_error(&format!("Unknown column: {}", x)),
+            (_, None) => return self.show_error(&format!("Unknown column: {}", y)),
+        };
+        // columns with some text can still be plotted by their numbers
+        let value = |row: &[String], col: usize| {
+            let col_type = match self.types[col] {
+                ColType::Str => ColType::Float,
+                col_type => col_type,
+            };
+            Cell::parse(&row[col], col_type).as_f64()
+        };
+        let points: Vec<(f64, f64)> = self
+            .view
+            .iter()
+            .filter_map(|&i| Some((value(&self.rows[i], x)?, value(&self.rows[i], y)?)))
+            .collect();
+        if points.is_empty() {
+            let message = format!(
+                "No rows with numbers in {} and {}",
+                self.header[x], self.header[y]
+            );
+            return self.show_error(&message);
+        }
+        let lines = plot::scatter(&points, self.terminal_size.x, self.displayable_data_rows());
+        let entries = lines
+            .into_iter()
+            .map(|text| PaneEntry {
+                row: None,
+                col: y,
+                text,
+                highlight: false,
+            })
+            .collect();
+        let title = format!(
+            "{} by {}: {} points",
+            self.header[y],
+            self.header[x],
+            group_digits(points.len())
+        );
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// Shows the recently opened files to choose one to open instead of the current table.
+    #[cfg(feature = "csv")]
+    pub fn show_recent_files(&mut self) -> RenderingAction {
+        self.picker = Some(FilePicker::recent(self.history.files()));
+        RenderingAction::Pane
+    }
+
+    /// Opens a CSV or TSV file with its session instead of the current table.
+    #[cfg(feature = "csv")]
+    pub fn open_file(&mut self, path: &Path) -> RenderingAction {
+        let delimited = crate::csv::detect_format(path);
+        let start = Instant::now();
+        let (header, rows) = match crate::csv::read_csv_from_file(path, delimited.0, delimited.1) {
+            Ok(table) => table,
+            Err(err) => {
+                return self.show_error(&format!("Cannot read {}: {}", path.display(), err))
+            }
+        };
+        let session = match Session::load(path) {
+            Ok(session) => session,
+            Err(err) => return self.show_error(&format!("Cannot read session: {}", err)),
+        };
+        self.replace_table(header, rows, session, delimited);
+        self.load_time = Some(start.elapsed());
+        self.history.record(path);
+        if let Err(err) = self.history.save() {
+            return self.show_error(&format!("Cannot save history: {}", err));
+        }
+        RenderingAction::Rerender
+    }
+
+    /// `:describe` shows a table with the statistics of `:stats` for each column over the shown
+    /// rows, which can be sorted, filtered and searched like any other table, e.g. by the share of
+    /// nulls.
+    fn describe(&mut self) -> RenderingAction {
+        let (header, rows) = self.profile_table();
+        let shown = self.row_count();
+        self.show_derived("describe".to_string(), header, rows);
+        self.status_message = Some(format!(
+            "{} columns of {} rows, Esc returns",
+            self.row_count(),
+            group_digits(shown)
+        ));
+        RenderingAction::Rerender
+    }
+
+    // Table shown by `:describe` and written by `:export stats`, one row per column with its
+    // number, name and statistics over the shown rows.
+    fn profile_table(&self) -> (Vec<String>, Vec<Vec<String>>) {
+        let header = ["#", "column"]
+            .into_iter()
+            .chain(stats::PROFILE_FIELDS)
+            .map(String::from)
+            .collect();
+        let rows = (1..self.header.len())
+            .map(|col| {
+                let values = self.view.iter().map(|&i| self.rows[i][col].as_str());
+                let profile = stats::profile(values, self.types[col]);
+                [col.to_string(), self.header[col].clone()]
+                    .into_iter()
+                    .chain(profile.fields())
+                    .collect()
+            })
+            .collect();
+        (header, rows)
+    }
+
+    /// `:promote-header` makes the first row of the table as loaded its header, e.g. when a
+    /// title or units row comes before it; the old header is dropped.
+    fn promote_header(&mut self) -> RenderingAction {
+        if self.rows.is_empty() {
+            return self.show_error("No row to promote");
+        }
+        if !self.session.journal.is_empty() {
+            return self.show_error("Save or undo the edits first");
+        }
+        let mut values = self.data_values();
+        let names = values.remove(0);
+        self.restructure(names, values);
+        self.show_message(&format!(
+            "Promoted the first row to the header, {} rows left",
+            group_digits(self.rows.len())
+        ))
+    }
+
+    /// `:promote-header groups` makes the first row the header like `:promote-header`, but keeps
+    /// the old header as the names of column groups, for files with two header lines. A group
+    /// spans the columns up to the next name.
+    pub fn promote_group_header(&mut self) -> RenderingAction {
+        let groups = self.group_line.clone();
+        let reloads = self.reloads;
+        let action = self.promote_header();
+        // unless refused
+        if self.reloads != reloads {
+            self.groups = groups;
+        }
+        action
+    }
+
+    /// `:demote-header` makes the header the first row of the table, for files without a header;
+    /// the columns are named `col_1`, `col_2` and so on.
+    fn demote_header(&mut self) -> RenderingAction {
+        if !self.session.journal.is_empty() {
+            return self.show_error("Save or undo the edits first");
+        }
+        let mut values = self.data_values();
+        values.insert(0, self.header[1..self.data_width].to_vec());
+        // empty names are replaced by numbered ones
+        let names = vec![String::new(); self.data_width - 1];
+        self.restructure(names, values);
+        self.show_message("Demoted the header to the first row")
+    }
+
+    // Values of the loaded columns of each row without the row number, in the order loaded.
+    fn data_values(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| row[1..self.data_width].to_vec())
+            .collect()
+    }
+
+    // Shows the table with other column names and rows, numbering the rows anew and keeping
+    // settings that do not depend on the data. Notes and tags stay with their rows.
+    fn restructure(&mut self, names: Vec<String>, values: Vec<Vec<String>>) {
+        let header = once("#".to_string()).chain(names).collect();
+        let rows = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| once((i + 1).to_string()).chain(row).collect())
+            .collect();
+        let mut state = TableState::new(header, rows, self.terminal_size);
+        state.aliases = std::mem::take(&mut self.aliases);
+        state.keys = std::mem::take(&mut self.keys);
+        state.collation = self.collation;
+        state.search_case = self.search_case;
+        state.literal_search = self.literal_search;
+        state.color = self.color;
+        state.theme = self.theme;
+        state.rules = self.rules;
+        state.set_padding(self.base_padding());
+        state.backup = self.backup;
+        state.delimited = self.delimited;
+        state.load_time = self.load_time;
+        state.reloads = self.reloads + 1;
+        state.history = std::mem::take(&mut self.history);
+        state.command_history = std::mem::take(&mut self.command_history);
+        state.parent = self.parent.take();
+        #[cfg(feature = "scripting")]
+        {
+            state.script = self.script.take();
+        }
+        state.open_session(std::mem::take(&mut self.session));
+        *self = state;
+    }
+
+    // Shows a table derived from this one until `Esc` returns to this one, keeping settings that
+    // do not depend on the data. The derivation names the table in the breadcrumb.
+    fn show_derived(&mut self, derivation: String, header: Vec<String>, rows: Vec<Vec<String>>) {
+        let mut state = TableState::new(header, rows, self.terminal_size);
+        state.aliases = self.aliases.clone();
+        state.keys = self.keys.clone();
+        state.set_padding(self.base_padding());
+        state.color = self.color;
+        state.theme = self.theme;
+        state.rules = self.rules;
+        state.collation = self.collation;
+        state.search_case = self.search_case;
+        state.literal_search = self.literal_search;
+        state.reloads = self.reloads + 1;
+        state.history = std::mem::take(&mut self.history);
+        state.command_history = std::mem::take(&mut self.command_history);
+        #[cfg(feature = "scripting")]
+        {
+            state.script = self.script.take();
+        }
+        state.derivation = Some(derivation);
+        let mut parent = std::mem::replace(self, state);
+        // a new view replaces the ones returned from
+        parent.forward = None;
+        self.parent = Some(Box::new(parent));
+    }
+
+    /// `Esc` returns from a derived table to the one it was derived from, which keeps it for
+    /// going forward again with `Tab`.
+    pub fn close_derived(&mut self) -> RenderingAction {
+        let parent = match self.parent.take() {
+            Some(parent) => *parent,
+            None => return RenderingAction::None,
+        };
+        let derived = self.switch_to(parent);
+        self.forward = Some(Box::new(derived));
+        RenderingAction::Rerender
+    }
+
+    /// `Ctrl-o` (or `:back`) goes back to the previous view like `Esc`.
+    pub fn go_back(&mut self) -> RenderingAction {
+        match self.parent {
+            Some(_) => self.close_derived(),
+            None => self.show_error("No previous view"),
+        }
+    }
+
+    /// `Tab` (or `:forward`) shows the derived table last returned from again, as it was.
+    pub fn go_forward(&mut self) -> RenderingAction {
+        let derived = match self.forward.take() {
+            Some(derived) => *derived,
+            None => return self.show_error("No next view"),
+        };
+        let parent = self.switch_to(derived);
+        self.parent = Some(Box::new(parent));
+        RenderingAction::Rerender
+    }
+
+    /// `Backspace` removes the filter added last or, if there is none, goes back to the previous
+    /// view.
+    pub fn pop_filter_or_view(&mut self) -> RenderingAction {
+        match (self.filters.is_empty(), &self.parent) {
+            (true, Some(_)) => self.close_derived(),
+            _ => self.pop_filter(),
+        }
+    }
+
+    // Shows another view of the stack instead of this one, handing over what belongs to the
+    // session rather than to a table, and returns this one.
+    fn switch_to(&mut self, mut state: TableState) -> TableState {
+        state.reloads = self.reloads + 1;
+        state.history = std::mem::take(&mut self.history);
+        state.command_history = std::mem::take(&mut self.command_history);
+        #[cfg(feature = "scripting")]
+        {
+            state.script = self.script.take();
+        }
+        state.recompute_layout(self.terminal_size);
+        std::mem::replace(self, state)
+    }
+
+    /// `:dedup [<column>, ...]` shows each distinct row of the shown ones once, or the first row
+    /// of each distinct combination of the given columns, with a `count` column of how many rows
+    /// it stands for.
+    fn dedup(&mut self, args: &str) -> RenderingAction {
+        let mut key = Vec::new();
+        for name in args
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match self.column_index(name) {
+                Some(col) => key.push(col),
+                None => return self.show_error(&format!("Unknown column: {}", name)),
+            }
+        }
+        if key.is_empty() {
+            key = (1..self.data_width).collect();
+        }
+        // first row and number of rows of each distinct key, in the order they are shown
+        let mut kept: Vec<(usize, usize)> = Vec::new();
+        let mut positions: HashMap<Vec<&str>, usize> = HashMap::new();
+        for &i in &self.view {
+            let values = key.iter().map(|&col| self.rows[i][col].as_str()).collect();
+            let position = *positions.entry(values).or_insert_with(|| {
+                kept.push((i, 0));
+                kept.len() - 1
+            });
+            kept[position].1 += 1;
+        }
+        let rows = kept
+            .into_iter()
+            .map(|(i, count)| {
+                self.rows[i][..self.data_width]
+                    .iter()
+                    .cloned()
+                    .chain(once(count.to_string()))
+                    .collect()
+            })
+            .collect();
+        let header = self.header[..self.data_width]
+            .iter()
+            .cloned()
+            .chain(once("count".to_string()))
+            .collect();
+        let shown = self.row_count();
+        let derivation = format!("dedup {}", args.trim()).trim_end().to_string();
+        self.show_derived(derivation, header, rows);
+        self.status_message = Some(format!(
+            "{} distinct of {} rows, Esc returns",
+            group_digits(self.row_count()),
+            group_digits(shown)
+        ));
+        RenderingAction::Rerender
+    }
+
+    /// `C` or `:freq` shows how often each value of the current column occurs in the shown rows,
+    /// most frequent first, as a table with the value, its count and percentage.
+    pub fn show_frequencies(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        let counts = self.value_counts(col);
+        let shown = self.row_count();
+        let rows = counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, (value, count))| {
+                vec![
+                    (i + 1).to_string(),
+                    value.to_string(),
+                    count.to_string(),
+                    format!("{:.1}", 100.0 * count as f64 / shown as f64),
+                ]
+            })
+            .collect();
+        let header = vec![
+            "#".to_string(),
+            self.header[col].clone(),
+            "count".to_string(),
+            "%".to_string(),
+        ];
+        let name = self.header[col].clone();
+        self.show_derived(format!("freq {}", name), header, rows);
+        self.counted_col = Some(col);
+        self.status_message = Some(format!(
+            "{} distinct values of {} in {} rows, Enter filters by one, Esc returns",
+            group_digits(self.row_count()),
+            name,
+            group_digits(shown)
+        ));
+        RenderingAction::Rerender
+    }
+
+    // Values of the column in the shown rows with their count, most frequent first.
+    fn value_counts(&self, col: usize) -> Vec<(&str, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for &i in &self.view {
+            *counts.entry(self.rows[i][col].as_str()).or_default() += 1;
+        }
+        let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+        counts.sort_by(|(v1, c1), (v2, c2)| c2.cmp(c1).then_with(|| v1.cmp(v2)));
+        counts
+    }
+
+    /// `Enter` on a row of the `:freq` table returns to the counted table, keeping only the rows
+    /// with that value.
+    pub fn filter_by_frequency(&mut self) -> RenderingAction {
+        let (col, position) = match (self.counted_col, self.current_data_row()) {
+            (Some(col), Some(position)) => (col, position),
+            _ => return RenderingAction::None,
+        };
+        let value = self.rows[self.view[position]][1].clone();
+        self.close_derived();
+        self.filters.push(Filter {
+            col,
+            op: Op::Eq,
+            value,
+            other: None,
+        });
+        self.apply_filters()
+    }
+
+    // Shows another table, keeping settings that do not depend on the data.
+    #[cfg(feature = "csv")]
+    fn replace_table(
+        &mut self,
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+        session: Session,
+        delimited: (u8, u8),
+    ) {
+        let mut state = TableState::new(header, rows, self.terminal_size);
+        state.aliases = std::mem::take(&mut self.aliases);
+        state.keys = std::mem::take(&mut self.keys);
+        state.collation = self.collation;
+        state.search_case = self.search_case;
+        state.literal_search = self.literal_search;
+        state.color = self.color;
+        state.theme = self.theme;
+        state.rules = self.rules;
+        state.set_padding(self.base_padding());
+        state.backup = self.backup;
+        state.delimited = Some(delimited);
+        state.reloads = self.reloads + 1;
+        state.history = std::mem::take(&mut self.history);
+        state.command_history = std::mem::take(&mut self.command_history);
+        #[cfg(feature = "scripting")]
+        {
+            state.script = self.script.take();
+        }
+        state.open_session(session);
+        *self = state;
+    }
+
+    // Applies the pending edits to the rows, moving notes and tags to the new row hashes.
+    #[cfg(feature = "csv")]
+    fn apply_journal(&mut self) {
+        let mut edited_cols = Vec::new();
+        for row in 0..self.rows.len() {
+            let edited = match self.edited_row(row) {
+                Cow::Owned(edited) => edited,
+                Cow::Borrowed(_) => continue,
+            };
+            let old_hash = self.row_hash(row);
+            for (col, value) in edited.into_iter().enumerate() {
+                if self.rows[row][col] != value {
+                    self.rows[row][col] = value;
+                    edited_cols.push(col);
+                }
+            }
+            let new_hash = self.row_hash(row);
+            if let Some(note) = self.session.notes.remove(&old_hash) {
+                self.session.notes.insert(new_hash, note);
+            }
+            if let Some(tags) = self.session.tags.remove(&old_hash) {
+                self.session.tags.insert(new_hash, tags);
+            }
+        }
+        self.session.journal.clear();
+        edited_cols.sort_unstable();
+        edited_cols.dedup();
+        for col in edited_cols {
+            self.types[col] = infer_type(self.rows.iter().map(|row| row[col].as_str()));
+            self.summaries.remove(&col);
+        }
+    }
+
+    /// Adds the tag to the current row or removes it if the row already has it. Tags cannot
+    /// contain commas, which separate them in the tags column.
+    pub fn toggle_tag(&mut self, tag: &str) -> RenderingAction {
+        if tag.contains(',') {
+            return self.show_error("Tags cannot contain commas");
+        }
+        let hash = match self.current_row_hash() {
+            Some(hash) => hash,
+            None => return self.show_error("No row selected"),
+        };
+        let tags = self.session.tags.entry(hash).or_default();
+        if !tags.remove(tag) {
+            tags.insert(tag.to_string());
+        }
+        if tags.is_empty() {
+            self.session.tags.remove(&hash);
+        }
+        let col = match self.tag_col {
+            Some(col) => col,
+            None => {
+                let col = self.add_session_column("tags", tag_text);
+                self.tag_col = Some(col);
+                col
+            }
+        };
+        let text = tag_text(&self.session, hash);
+        self.update_session_column(col, hash, text)
+    }
+
+    pub fn execute_command(&mut self) -> RenderingAction {
+        let command: String = self.command_buffer.iter().collect();
+        command::remember(&mut self.command_history, &command);
+        // the search starts where it started while typing
+        self.restore_search_origin();
+        // a typed value is taken as it is, including semicolons and spaces
+        if let Some(value) = command.strip_prefix(":edit ") {
+            return self.edit(value);
+        }
+        match command::search_pattern(&command) {
+            Some((pattern, backward)) if !pattern.is_empty() => self.find(pattern, backward),
+            Some(_) => RenderingAction::None,
+            None => self.execute_batch(&command),
+        }
+    }
+
+    /// Suggested completion of the command line (the missing suffix) from the command history
+    /// and the column names.
+    pub fn command_suggestion(&self) -> Option<String> {
+        if self.command_tail > 0 {
+            return None;
+        }
+        let input: String = self.command_buffer.iter().collect();
+        command::suggest(&input, &self.command_history, &self.header[1..])
+    }
+
+    /// Completes the command line with the suggestion, if any.
+    pub fn accept_suggestion(&mut self) -> RenderingAction {
+        match self.command_suggestion() {
+            Some(suffix) => {
+                self.command_buffer.extend(suffix.chars());
+                RenderingAction::Command
+            }
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Inserts pasted text into the command line, with line breaks turned into spaces since
+    /// commands are single lines. Searches jump to the first match as if the text was typed.
+    pub fn paste(&mut self, text: &str) -> RenderingAction {
+        let line = text
+            .trim_end_matches(['\r', '\n'])
+            .replace("\r\n", " ")
+            .replace(['\r', '\n'], " ");
+        let cursor = self.command_cursor();
+        self.command_buffer.splice(cursor..cursor, line.chars());
+        self.preview_search()
+    }
+
+    /// Position of the cursor in the command line, after the command character at the least.
+    pub fn command_cursor(&self) -> usize {
+        let len = self.command_buffer.len();
+        len.saturating_sub(self.command_tail).max(len.min(1))
+    }
+
+    /// Starts a command line with the command character, `:`, `/` or `?`.
+    pub fn start_command(&mut self, c: char) {
+        self.command_buffer = vec![c];
+        self.command_tail = 0;
+    }
+
+    /// Inserts a typed character at the cursor.
+    pub fn insert_command_char(&mut self, c: char) -> RenderingAction {
+        let cursor = self.command_cursor();
+        self.command_buffer.insert(cursor, c);
+        self.preview_search()
+    }
+
+    /// Deletes the character before the cursor; the command character only if nothing follows
+    /// it, which leaves the command line.
+    pub fn delete_command_char(&mut self) -> RenderingAction {
+        let cursor = self.command_cursor();
+        if cursor > 1 || self.command_buffer.len() == 1 {
+            self.command_buffer.remove(cursor - 1);
+        }
+        self.preview_search()
+    }
+
+    /// `Ctrl-w` deletes the word before the cursor along with the spaces after it.
+    pub fn delete_command_word(&mut self) -> RenderingAction {
+        let cursor = self.command_cursor();
+        let text = &self.command_buffer[1.min(cursor)..cursor];
+        let spaces = text.iter().rev().take_while(|c| c.is_whitespace()).count();
+        let word = text[..text.len() - spaces]
+            .iter()
+            .rev()
+            .take_while(|c| !c.is_whitespace())
+            .count();
+        self.command_buffer.drain(cursor - spaces - word..cursor);
+        self.preview_search()
+    }
+
+    /// `Ctrl-u` deletes everything after the command character.
+    pub fn clear_command(&mut self) -> RenderingAction {
+        self.command_buffer.truncate(1);
+        self.command_tail = 0;
+        self.preview_search()
+    }
+
+    /// Moves the cursor in the command line by the given number of characters, within the text
+    /// after the command character.
+    pub fn move_command_cursor(&mut self, offset: isize) -> RenderingAction {
+        let cursor = self.command_cursor().saturating_add_signed(offset);
+        let cursor = cursor.clamp(1.min(self.command_buffer.len()), self.command_buffer.len());
+        self.command_tail = self.command_buffer.len() - cursor;
+        RenderingAction::Command
+    }
+
+    /// `Ctrl-a` and `Ctrl-e` move the cursor to the start or end of the command line.
+    pub fn move_command_cursor_to(&mut self, start: bool) -> RenderingAction {
+        self.command_tail = match start {
+            true => self.command_buffer.len().saturating_sub(1),
+            false => 0,
+        };
+        RenderingAction::Command
+    }
+
+    /// Executes a `;`-separated batch of commands, expanding aliases.
+    pub fn execute_batch(&mut self, batch: &str) -> RenderingAction {
+        command::expand(batch, &self.aliases).iter().fold(
+            RenderingAction::None,
+            |action, command| {
+                #[cfg(feature = "log")]
+                debug!(command = command.as_str(), "command");
+                let next = match command::search_pattern(command) {
+                    Some((pattern, backward)) => self.find(pattern, backward),
+                    None => self.run_command(command.trim_start_matches(':').trim()),
+                };
+                action.merge(next)
+            },
+        )
+    }
+
+    fn run_command(&mut self, command: &str) -> RenderingAction {
+        let (name, args) = match command.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => (command, ""),
+        };
+        if let Ok(number) = name.parse() {
+            return self.go_to_row(number);
+        }
+        match name {
+            "matches" => self.show_matches(),
+            "issues" => self.show_issues(),
+            "lint" => self.show_lint(),
+            "fit" => self.fit_columns(),
+            "fit!" => self.unfit_columns(),
+            "unhide" => self.unhide_columns(),
+            "columns" => self.edit_columns(),
+            "present" => self.present(args),
+            "filter" => self.filter(args),
+            "clearfilter" => self.pop_filter(),
+            "back" => self.go_back(),
+            "forward" => self.go_forward(),
+            "sample" => self.sample(args),
+            "dedup" => self.dedup(args),
+            "freq" => self.show_frequencies(),
+            "describe" => self.describe(),
+            "promote-header" if args == "groups" => self.promote_group_header(),
+            "promote-header" => self.promote_header(),
+            "demote-header" => self.demote_header(),
+            "sort" => self.sort(args),
+            "sortkey" => self.set_sort_key(args),
+            "jumpif" => self.jump_if(args),
+            "type" => self.set_column_type(args),
+            "collation" => self.set_collation(args),
+            "case" => self.set_search_case(args),
+            "literal" => self.set_literal_search(args),
+            "noh" | "nohlsearch" => self.clear_highlight(),
+            "col" => self.go_to_column(args),
+            #[cfg(feature = "csv")]
+            "write" => self.write_view(args),
+            #[cfg(feature = "csv")]
+            "write!" => self.write_rejected(args),
+            "note" => self.note(args),
+            "edit" => self.edit(args),
+            "delete-row" => self.delete_row(),
+            "duplicate-row" => self.duplicate_row(),
+            "changes" => self.show_changes(),
+            #[cfg(feature = "csv")]
+            "save" | "w" => self.save(false),
+            #[cfg(feature = "csv")]
+            "save!" | "w!" => self.save(true),
+            #[cfg(feature = "csv")]
+            "reparse" => self.reparse(args),
+            #[cfg(feature = "csv")]
+            "loadall" => self.load_all(),
+            #[cfg(feature = "csv")]
+            "open" => self.show_file_picker(args),
+            #[cfg(feature = "csv")]
+            "recent" => self.show_recent_files(),
+            "info" => self.show_info(),
+            "stats" => self.show_stats(),
+            "plot" => self.plot(args),
+            "bucket" => self.bucket(args),
+            "tag" if !args.is_empty() => self.toggle_tag(args),
+            #[cfg(feature = "csv")]
+            "split" => self.split(args),
+            #[cfg(feature = "csv")]
+            "export" => self.export(args),
+            #[cfg(feature = "scripting")]
+            "compute" => self.compute_column(args),
+            _ => self.run_script_command(name, args),
+        }
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    fn run_script_command(&mut self, name: &str, _args: &str) -> RenderingAction {
+        self.show_error(&format!("Unknown command: {}", name))
+    }
+
+    /// Asks a yes/no question on the command line and runs the action if the answer is yes.
+    pub fn confirm(
+        &mut self,
+        question: String,
+        action: impl FnOnce(&mut TableState) -> RenderingAction + 'static,
+    ) -> RenderingAction {
+        self.prompt = Some(Prompt {
+            question,
+            action: Box::new(action),
+        });
+        RenderingAction::Command
+    }
+
+    /// Runs the action of the open prompt if the answer is yes.
+    pub fn answer_prompt(&mut self, yes: bool) -> RenderingAction {
+        match self.prompt.take() {
+            Some(prompt) if yes => (prompt.action)(self),
+            Some(_) => self.show_message("Cancelled"),
+            None => RenderingAction::None,
+        }
+    }
+
+    // Writes a file right away if it does not exist yet, else after confirming to overwrite it.
+    #[cfg(feature = "csv")]
+    fn confirm_overwrite(
+        &mut self,
+        path: &str,
+        write: impl FnOnce(&mut TableState, &str) -> RenderingAction + 'static,
+    ) -> RenderingAction {
+        let path = path.to_string();
+        match Path::new(&path).exists() {
+            true => self.confirm(format!("Overwrite {}?", path), move |state| {
+                write(state, &path)
+            }),
+            false => write(self, &path),
+        }
+    }
+
+    /// Shows a message in the status line, e.g. to confirm a command.
+    pub fn show_message(&mut self, message: &str) -> RenderingAction {
+        self.status_message = Some(message.to_string());
+        RenderingAction::Status
+    }
+
+    /// Shows why a command or key failed in the status line.
+    pub fn show_error(&mut self, message: &str) -> RenderingAction {
+        self.status_message = Some(message.to_string());
+        RenderingAction::Status
+    }
+
+    /// Is the value in the given column a match of the last search to be highlighted?
+    pub fn is_match(&self, col: usize, value: &str) -> bool {
+        match &self.last_search {
+            Some(search) if self.highlight_matches && search.col == col => {
+                search.matches(value, self.search_case)
+            }
+            _ => false,
+        }
+    }
+
+    /// `:noh` stops highlighting the matches of the last search until it is repeated.
+    fn clear_highlight(&mut self) -> RenderingAction {
+        self.highlight_matches = false;
+        RenderingAction::Rerender
+    }
+
+    /// Repeats the last search in the column it was made in, starting from the cursor position,
+    /// in the same direction (`n`) or the opposite one (`N`). The direction of the last search
+    /// stays the same.
+    pub fn repeat_search(&mut self, reverse: bool) -> RenderingAction {
+        let search = match self.last_search.take() {
+            Some(search) => search,
+            None => return self.show_error("No previous search"),
+        };
+        let backward = search.backward;
+        let search = Search {
+            backward: backward != reverse,
+            ..search
+        };
+        self.jump_to_col(search.col);
+        self.status_message = self.jump_to_match(&search);
+        self.last_search = Some(Search { backward, ..search });
+        self.highlight_matches = true;
+        RenderingAction::Rerender
+    }
+
+    /// Opens a pane listing all rows matching the last search.
+    pub fn show_matches(&mut self) -> RenderingAction {
+        let search = match &self.last_search {
+            Some(search) => search,
+            None => return RenderingAction::Rerender,
+        };
+        let entries: Vec<PaneEntry> = self
+            .view
+            .iter()
+            .map(|&i| (i, &self.rows[i]))
+            .filter(|(_, row)| search.matches(&row[search.col], self.search_case))
+            .map(|(i, row)| PaneEntry {
+                row: Some(i),
+                col: search.col,
+                text: format!("{}: {}", row[0], row[search.col]),
+                highlight: false,
+            })
+            .collect();
+        let title = format!(
+            "{} matches for '{}' in column '{}'",
+            entries.len(),
+            search.pattern,
+            self.header[search.col]
+        );
+        self.pane = Some(ListPane::new(title, entries));
+        RenderingAction::Pane
+    }
+
+    /// Applies the types, hidden columns, highlights and keys of a config profile. Columns the
+    /// table does not have are ignored.
+    pub fn apply_profile(&mut self, profile: &Profile) -> Result<(), String> {
+        for (name, col_type) in &profile.types {
+            let col_type = col_type.parse::<ColType>()?;
+            if let Some(col) = self.column_index(name) {
+                self.types[col] = col_type;
+            }
+        }
+        for expr in &profile.highlight {
+            let expr = Expr::parse(expr, &self.header)
+                .map_err(|err| format!("Invalid highlight '{}': {}", expr, err))?;
+            self.highlights.push(expr);
+        }
+        self.keys.extend(profile.keys.clone());
+        let hidden: Vec<usize> = profile
+            .hidden
+            .iter()
+            .filter_map(|name| self.column_index(name))
+            .collect();
+        if hidden.len() < self.order.len() {
+            self.hidden.extend(hidden);
+            let widths = self.content_widths();
+            self.set_widths(widths);
+            if self.hidden.contains(&self.current_column()) {
+                self.move_start_of_line();
+            }
+        }
+        Ok(())
+    }
+
+    /// Is the row one of those highlighted by the profile?
+    pub fn is_highlighted(&self, row: usize) -> bool {
+        self.highlights
+            .iter()
+            .any(|expr| expr.matches(&self.rows[row], &self.types))
+    }
+
+    /// Runs the commands the config or profile maps to the key, None if it maps none.
+    pub fn mapped_key(&mut self, key: &str) -> Option<RenderingAction> {
+        let batch = self.keys.get(key)?.clone();
+        Some(self.execute_batch(&batch))
+    }
+
+    /// Runs the commands mapped to a key without a built-in binding, or else passes the key to
+    /// the script's key handler.
+    pub fn unbound_key(&mut self, key: &str) -> RenderingAction {
+        if let Some(action) = self.mapped_key(key) {
+            return action;
+        }
+        #[cfg(feature = "scripting")]
+        return self.script_key(key);
+        #[cfg(not(feature = "scripting"))]
+        RenderingAction::None
+    }
+
+    /// Validates the table against a schema, whose types override inferred ones and whose groups
+    /// are shown above the header.
+    /// Opens the issues pane if there are violations.
+    #[cfg(feature = "config")]
+    pub fn apply_schema(&mut self, schema: &Schema) {
+        for (col, col_type) in schema.types(&self.header).into_iter().enumerate() {
+            if let Some(col_type) = col_type {
+                self.types[col] = col_type;
+            }
+        }
+        let groups = schema.groups(&self.header);
+        if groups.iter().any(|group| !group.is_empty()) {
+            self.groups = groups;
+        }
+        self.issues = schema.validate(&self.header, &self.rows);
+        if !self.issues.is_empty() {
+            self.show_issues();
+        }
+    }
+
+    /// Opens a pane listing schema violations.
+    pub fn show_issues(&mut self) -> RenderingAction {
+        let title = format!("{} schema issues", self.issues.len());
+        self.pane = Some(ListPane::new(title, self.issues.clone()));
+        RenderingAction::Pane
+    }
+
+    /// Opens a pane listing cells that deviate from the dominant pattern of their column.
+    pub fn show_lint(&mut self) -> RenderingAction {
+        let findings = lint::lint(&self.header, &self.rows);
+        let title = format!("{} suspicious cells", findings.len());
+        self.pane = Some(ListPane::new(title, findings));
+        RenderingAction::Pane
+    }
+
+    pub fn pane_down(&mut self) -> RenderingAction {
+        #[cfg(feature = "csv")]
+        if let Some(picker) = &mut self.picker {
+            return picker.move_down(FilePicker::list_height(self.terminal_size.y));
+        }
+        let height = self.displayable_data_rows();
+        if let Some(editor) = &mut self.column_editor {
+            return editor.move_down(height);
+        }
+        match &mut self.pane {
+            Some(pane) => pane.move_down(height),
+            None => RenderingAction::None,
+        }
+    }
+
+    pub fn pane_up(&mut self) -> RenderingAction {
+        #[cfg(feature = "csv")]
+        if let Some(picker) = &mut self.picker {
+            return picker.move_up();
+        }
+        let height = self.displayable_data_rows();
+        if let Some(editor) = &mut self.column_editor {
+            return editor.move_up(height);
+        }
+        match &mut self.pane {
+            Some(pane) => pane.move_up(),
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Shows or hides the column selected in the column editor.
+    pub fn pane_toggle(&mut self) -> RenderingAction {
+        match &mut self.column_editor {
+            Some(editor) => editor.toggle(),
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Moves the column selected in the column editor down or up.
+    pub fn pane_shift(&mut self, down: bool) -> RenderingAction {
+        let height = self.displayable_data_rows();
+        match &mut self.column_editor {
+            Some(editor) => editor.shift(down, height),
+            None => RenderingAction::None,
+        }
+    }
+
+    /// Closes the pane and jumps to the cell of the selected entry, opens the file selected
+    /// in the file picker or applies the column editor.
+    pub fn pane_select(&mut self) -> RenderingAction {
+        #[cfg(feature = "csv")]
+        if let Some(picker) = self.picker.take() {
+            return match picker.selected_file() {
+                Some(path) => self.open_file(path),
+                None => RenderingAction::Rerender,
+            };
+        }
+        if let Some(editor) = self.column_editor.take() {
+            return self.apply_column_editor(editor);
+        }
+        let entry = self
+            .pane
+            .take()
+            .and_then(|pane| pane.selected_entry().cloned());
+        if let Some(entry) = entry {
+            // entries about a whole column, e.g. statistics, keep the cursor in its row
+            if let Some(row) = entry.row {
+                match self.view.iter().position(|&i| i == row) {
+                    Some(position) => self.jump_to_row(position),
+                    None => return self.show_error("Row is hidden by a filter"),
+                }
+            }
+            self.jump_to_col(entry.col);
+        }
+        RenderingAction::Rerender
+    }
+
+    pub fn close_pane(&mut self) -> RenderingAction {
+        self.pane = None;
+        self.column_editor = None;
+        self.filter_builder = None;
+        #[cfg(feature = "csv")]
+        {
+            self.picker = None;
+        }
+        RenderingAction::Rerender
+    }
+
+    // Moves the cursor to the data row at the given display position.
+    /// `:123` or `123G` moves the cursor to the given row of the shown ones, counting from 1,
+    /// or the last one if there are fewer rows.
+    pub fn go_to_row(&mut self, number: usize) -> RenderingAction {
+        if self.row_count() == 0 {
+            return self.show_error("No rows");
+        }
+        self.jump_to_row(number.clamp(1, self.row_count()) - 1);
+        RenderingAction::Rerender
+    }
+
+    /// `:col <name>` moves the cursor to the shown column best matching the name: equal to it,
+    /// starting with it, containing it or else containing its letters in order, ignoring case.
+    /// Of equally good matches the next one right of the cursor is taken, so repeating the
+    /// command cycles through them.
+    pub fn go_to_column(&mut self, name: &str) -> RenderingAction {
+        if name.is_empty() {
+            return self.show_error("Usage: :col <name>");
+        }
+        let ranked: Vec<(usize, u8)> = (0..self.order.len())
+            .filter_map(|i| Some((i, column_match(&self.header[self.order[i]], name)?)))
+            .collect();
+        let best = match ranked.iter().map(|&(_, rank)| rank).min() {
+            Some(best) => best,
+            None => return self.show_error(&format!("No column matches '{}'", name)),
+        };
+        let matches: Vec<usize> = ranked
+            .into_iter()
+            .filter(|&(i, rank)| rank == best && self.is_shown(i))
+            .map(|(i, _)| i)
+            .collect();
+        if matches.is_empty() {
+            return self.show_error(&format!(
+                "Columns matching '{}' are hidden, zR shows all",
+                name
+            ));
+        }
+        let current = self.current_position();
+        let next = matches.iter().position(|&i| i > current).unwrap_or(0);
+        self.jump_to_position(matches[next]);
+        if matches.len() > 1 {
+            self.status_message = Some(format!(
+                "Column {} of {} matching '{}'",
+                next + 1,
+                matches.len(),
+                name
+            ));
+        }
+        RenderingAction::Rerender
+    }
+
+    fn jump_to_row(&mut self, row: usize) {
+        // first window position
+        if row < self.displayable_data_rows() {
+            self.offsets.row = 0;
+            self.cur_pos.row = row + 1;
+        }
+        // last window position
+        else if self.row_count() - row < self.displayable_data_rows() {
+            self.offsets.row = self.row_count() - self.displayable_data_rows();
+            self.cur_pos.row = row - self.offsets.row + 1;
+        }
+        // middle
+        else {
+            self.offsets.row = row;
+            self.cur_pos.row = 1;
+        }
+    }
+
+    fn jump_to_col(&mut self, col: usize) {
+        self.jump_to_position(self.position(col));
+    }
+
+    fn jump_to_position(&mut self, col: usize) {
+        // frozen columns are always shown
+        if col < self.frozen {
+            self.cur_pos.col = col;
+            return;
+        }
+        let target = &self.columns[col];
+        let target_end = target.index + target.width + self.frozen_width();
+        // left of the window: make it the first displayed column
+        if col < self.offsets.col {
+            self.offsets.col = col;
+        }
+        // right of the window: find the first offset for which the column fits, or show it
+        // first if it is wider than the window
+        else if target_end - self.columns[self.offsets.col].index > self.terminal_size.x {
+            self.offsets.col = (self.offsets.col..col)
+                .find(|&i| target_end - self.columns[i].index <= self.terminal_size.x)
+                .unwrap_or(col);
+        }
+        self.cur_pos.col = col - self.offsets.col + self.frozen;
+    }
+
+    // Shows the column with as many columns left of it as fit.
+    fn scroll_to_col(&mut self, col: usize) {
+        self.offsets.col = self.frozen;
+        self.cur_pos.col = self.frozen;
+        self.jump_to_col(col);
+    }
+
+    /// Keeps the first `count` columns in display order in view when scrolling horizontally,
+    /// none for 0; at least one column is left to scroll.
+    pub fn freeze_columns(&mut self, count: usize) {
+        let col = self.current_column();
+        self.frozen = min(count, self.order.len().saturating_sub(1));
+        self.scroll_to_col(col);
+    }
+
+    /// Freezes the columns up to the one under the cursor, or unfreezes them if they are frozen
+    /// already.
+    pub fn toggle_frozen(&mut self) -> RenderingAction {
+        let count = self.current_position() + 1;
+        if self.frozen == count {
+            self.freeze_columns(0);
+            self.status_message = Some("Unfroze columns".to_string());
+        } else {
+            self.freeze_columns(count);
+            let name = &self.header[self.current_column()];
+            self.status_message = Some(format!("Froze columns up to {}", name));
+        }
+        RenderingAction::Rerender
+    }
+
+    pub fn search(&mut self, pattern: &str) -> RenderingAction {
+        self.find(pattern, false)
+    }
+
+    /// Searches the current column for the previous row containing the pattern (`?pattern`).
+    pub fn search_backward(&mut self, pattern: &str) -> RenderingAction {
+        self.find(pattern, true)
+    }
+
+    fn find(&mut self, pattern: &str, backward: bool) -> RenderingAction {
+        let search = Search {
+            pattern: self.search_text(pattern),
+            col: self.current_column(),
+            exact: false,
+            backward,
+        };
+        self.status_message = self.jump_to_match(&search);
+        self.last_search = Some(search);
+        self.highlight_matches = true;
+        RenderingAction::Rerender
+    }
+
+    /// `*` (or `#` backward) searches the current column for other rows with exactly the value
+    /// under the cursor and shows how many there are.
+    pub fn search_value(&mut self, backward: bool) -> RenderingAction {
+        let row = match self.current_data_row() {
+            Some(row) => row,
+            None => return self.show_error("No value under the cursor"),
+        };
+        let col = self.current_column();
+        let search = Search {
+            pattern: self.view_row(row)[col].clone(),
+            col,
+            exact: true,
+            backward,
+        };
+        let count = (0..self.row_count())
+            .filter(|&row| search.matches(&self.view_row(row)[col], self.search_case))
+            .count();
+        let mut message = format!(
+            "{} rows with {} = '{}'",
+            count, self.header[col], search.pattern
+        );
+        if let Some(wrapped) = self.jump_to_match(&search) {
+            message = format!("{} ({})", message, wrapped.to_lowercase());
+        }
+        self.status_message = Some(message);
+        self.last_search = Some(search);
+        self.highlight_matches = true;
+        RenderingAction::Rerender
+    }
+
+    // Moves the cursor to the next (or previous) row matching the search, wrapping around at
+    // the end (or start). Returns a notice if the search wrapped around or found nothing.
+    fn jump_to_match(&mut self, search: &Search) -> Option<String> {
+        let cur_row = self.current_row();
+        // data rows count from 0, the current one is `cur_row - 1`
+        let (rows, wrapped): (Box<dyn Iterator<Item = usize>>, usize) = match search.backward {
+            true => {
+                let before = cur_row.saturating_sub(1);
+                let rows = (0..before).rev().chain((before..self.row_count()).rev());
+                (Box::new(rows), before)
+            }
+            false => (
+                Box::new((cur_row..self.row_count()).chain(0..cur_row)),
+                cur_row,
+            ),
+        };
+        for row in rows {
+            if search.matches(&self.view_row(row)[search.col], self.search_case) {
+                self.jump_to_row(row);
+                return match (search.backward, row >= wrapped) {
+                    (true, true) => Some("Search wrapped around to the bottom".to_string()),
+                    (false, false) => Some("Search wrapped around to the top".to_string()),
+                    _ => None,
+                };
+            }
+        }
+        Some(format!(
+            "No match for '{}' in {}",
+            search.pattern, self.header[search.col]
+        ))
+    }
+
+    /// Remembers the cursor position when starting to type a search.
+    pub fn start_search(&mut self) {
+        self.search_origin = Some((self.offsets, self.cur_pos));
+    }
+
+    /// Jumps to the first match of the search being typed, starting from where the search
+    /// started, without making it the last search.
+    pub fn preview_search(&mut self) -> RenderingAction {
+        let input: String = self.command_buffer.iter().collect();
+        let (pattern, backward) = match (command::search_pattern(&input), self.search_origin) {
+            (Some((pattern, backward)), Some(_)) => (self.search_text(pattern), backward),
+            _ => return RenderingAction::Command,
+        };
+        self.restore_search_origin();
+        self.start_search();
+        if !pattern.is_empty() {
+            let search = Search {
+                pattern,
+                col: self.current_column(),
+                exact: false,
+                backward,
+            };
+            self.jump_to_match(&search);
+        }
+        RenderingAction::Rerender
+    }
+
+    // Text searched for by a typed pattern.
+    fn search_text(&self, pattern: &str) -> String {
+        match self.literal_search {
+            true => pattern.to_string(),
+            false => command::unescape(pattern),
+        }
+    }
+
+    /// Returns to the position where the search being typed started.
+    pub fn cancel_search(&mut self) -> RenderingAction {
+        match self.restore_search_origin() {
+            true => RenderingAction::Rerender,
+            false => RenderingAction::None,
+        }
+    }
+
+    fn restore_search_origin(&mut self) -> bool {
+        match self.search_origin.take() {
+            Some((offsets, cur_pos)) => {
+                self.offsets = offsets;
+                self.cur_pos = cur_pos;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resizes all columns so that they exactly fill the terminal width: wide columns are
+    /// shrunk first, narrow tables are widened proportionally.
+    pub fn fit_columns(&mut self) -> RenderingAction {
+        let widths = fit_widths(&self.widths(), self.terminal_size.x);
+        self.set_widths(widths);
+        RenderingAction::Rerender
+    }
+
+    /// Hides the column under the cursor, moving the cursor to the next shown column.
+    pub fn hide_column(&mut self) -> RenderingAction {
+        let col = self.current_column();
+        let position = self.current_position();
+        let next = (position + 1..self.order.len())
+            .chain((0..position).rev())
+            .find(|&i| self.is_shown(i));
+        let next = match next {
+            Some(next) => next,
+            None => return self.show_error("Cannot hide the last shown column"),
+        };
+        self.hidden.insert(col);
+        let widths = self.widths();
+        self.lay_out(&widths);
+        self.jump_to_position(next);
+        self.status_message = Some(format!(
+            "Hid {} ({} hidden, zR shows all)",
+            self.header[col],
+            self.hidden.len()
+        ));
+        RenderingAction::Rerender
+    }
+
+    /// Shows all hidden columns again.
+    pub fn unhide_columns(&mut self) -> RenderingAction {
+        if self.hidden.is_empty() {
+            return RenderingAction::None;
+        }
+        self.hidden.clear();
+        let widths = self.content_widths();
+        self.set_widths(widths);
+        RenderingAction::Rerender
+    }
+
+    /// `:columns` lists all columns for showing, hiding and reordering them.
+    fn edit_columns(&mut self) -> RenderingAction {
+        self.column_editor = Some(ColumnEditor::new(
+            &self.order,
+            &self.hidden,
+            self.current_column(),
+            self.displayable_data_rows(),
+        ));
+        RenderingAction::Pane
+    }
+
+    // Shows the columns in the order and with the visibility set in the editor, keeping the
+    // cursor on its column unless it is hidden now.
+    fn apply_column_editor(&mut self, editor: ColumnEditor) -> RenderingAction {
+        let shown: Vec<usize> = editor
+            .entries
+            .iter()
+            .filter(|(_, shown)| *shown)
+            .map(|&(col, _)| col)
+            .collect();
+        let col = match shown.first() {
+            Some(_) if shown.contains(&self.current_column()) => self.current_column(),
+            Some(&first) => first,
+            None => return self.show_error("Cannot hide all columns"),
+        };
+        let mut widths = self.widths();
+        let content_widths = self.content_widths();
+        for &col in &self.hidden {
+            widths[col] = content_widths[col];
+        }
+        self.order = editor.entries.iter().map(|&(col, _)| col).collect();
+        self.hidden = editor
+            .entries
+            .iter()
+            .filter(|(_, shown)| !shown)
+            .map(|&(col, _)| col)
+            .collect();
+        self.lay_out(&widths);
+        self.scroll_to_col(col);
+        RenderingAction::Rerender
+    }
+
+    /// Adapts the layout to a new terminal size, keeping the cursor on its cell.
+    pub fn resize(&mut self, size: CharCoord) -> RenderingAction {
+        self.recompute_layout(size);
+        RenderingAction::Rerender
+    }
+
+    /// Recomputes the column widths for a terminal of the given size and moves window and
+    /// cursor to valid positions: the cursor stays on its cell if it still exists, else on the
+    /// last row or column. The window shows as many columns left of the cursor as fit and
+    /// scrolls vertically as little as possible.
+    pub fn recompute_layout(&mut self, new_size: CharCoord) {
+        // room for at least the header, one row and the status line
+        self.terminal_size = CharCoord {
+            x: new_size.x.max(1),
+            y: new_size.y.max(3),
+        };
+        let col = min(self.current_column(), self.header.len().saturating_sub(1));
+        let row = match self.cur_pos.row {
+            0 => None,
+            _ => min(self.current_row(), self.row_count()).checked_sub(1),
+        };
+        let widths = self.content_widths();
+        self.lay_out(&widths);
+        self.scroll_to_col(col);
+        let page = self.displayable_data_rows();
+        let mut offset = min(self.offsets.row, self.row_count().saturating_sub(page));
+        if let Some(row) = row {
+            offset = offset.clamp((row + 1).saturating_sub(page), row);
+        }
+        self.offsets.row = offset;
+        self.cur_pos.row = row.map_or(0, |row| row - offset + 1);
+    }
+
+    /// Sets the spaces after the content of each column, laying out the columns anew.
+    pub fn set_padding(&mut self, padding: usize) {
+        if padding == self.padding {
+            return;
+        }
+        self.padding = padding;
+        let widths = self.content_widths();
+        self.set_widths(widths);
+    }
+
+    // Padding without the extra space of presentations
+    fn base_padding(&self) -> usize {
+        match self.presentation {
+            Some(_) => self.padding / 2,
+            None => self.padding,
+        }
+    }
+
+    /// Restores the content-based column widths.
+    pub fn unfit_columns(&mut self) -> RenderingAction {
+        let widths = self.content_widths();
+        self.set_widths(widths);
+        RenderingAction::Rerender
+    }
+
+    // Column widths based on the content; the row numbers are hidden in presentations.
+    fn content_widths(&self) -> Vec<usize> {
+        let mut widths = compute_col_widths(
+            once(&self.header).chain(self.rows.iter()),
+            self.padding,
+            self.terminal_size.x,
+        );
+        if self.presentation.is_some() {
+            widths[0] = 0;
+        }
+        widths
+    }
+
+    /// `:present [seconds]` shows the table without cursor, row numbers and status line and
+    /// with more space between columns, scrolling down every given number of seconds.
+    pub fn present(&mut self, args: &str) -> RenderingAction {
+        let interval = match args {
+            "" => None,
+            _ => match args.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => Some(Duration::from_secs_f64(seconds)),
+                _ => return self.show_error("Usage: present [seconds]"),
+            },
+        };
+        if self.presentation.is_none() {
+            self.padding *= 2;
+        }
+        self.presentation = Some(Presentation { interval });
+        let widths = self.content_widths();
+        self.set_widths(widths);
+        if self.current_column() == 0 && self.columns.len() > 1 {
+            self.jump_to_col(1);
+        }
+        RenderingAction::Rerender
+    }
+
+    /// Leaves the presentation mode.
+    pub fn stop_presenting(&mut self) -> RenderingAction {
+        if self.presentation.take().is_some() {
+            self.padding /= 2;
+        }
+        let widths = self.content_widths();
+        self.set_widths(widths);
+        // the window has one data row less with the status line
+        if self.cur_pos.row > self.displayable_data_rows() {
+            self.offsets.row += 1;
+            self.cur_pos.row -= 1;
+        }
+        RenderingAction::Rerender
+    }
+
+    /// Scrolls down by one row, starting over at the top after the last row.
+    pub fn auto_scroll(&mut self) -> RenderingAction {
+        if self.final_row_visible() {
+            self.offsets.row = 0;
+        } else {
+            self.offsets.row += 1;
+        }
+        RenderingAction::Rerender
+    }
+
+    /// Sets the widths of all columns, keeping the current column in view.
+    pub fn set_widths(&mut self, widths: Vec<usize>) {
+        self.lay_out(&widths);
+        let col = self.current_column();
+        self.scroll_to_col(col);
+    }
+
+    /// Shows another page of a table read on demand in place of the current rows, keeping the
+    /// column types and widening columns for longer values.
+    pub fn show_page(&mut self, rows: Vec<Vec<String>>) {
+        let widths = self.widths();
+        self.rows = rows;
+        self.view = (0..self.rows.len()).collect();
+        self.origin = self.view.clone();
+        self.cur_pos.row = min(self.cur_pos.row, self.rows.len());
+        let widths = self
+            .content_widths()
+            .into_iter()
+            .zip(widths)
+            .map(|(width, shown)| width.max(shown))
+            .collect();
+        self.set_widths(widths);
+    }
+
+    /// Appends a column to the table and recomputes the column layout.
+    pub fn add_column(&mut self, name: String, values: Vec<String>) {
+        self.header.push(name);
+        for (row, value) in self.rows.iter_mut().zip(values) {
+            row.push(value);
+        }
+        let col = self.header.len() - 1;
+        self.types
+            .push(infer_type(self.rows.iter().map(|row| row[col].as_str())));
+        self.order.push(col);
+        let widths = self.content_widths();
+        self.lay_out(&widths);
+    }
+
+    // Lays out the columns in display order from their widths in header order, with zero width
+    // for hidden columns.
+    fn lay_out(&mut self, widths: &[usize]) {
+        let widths: Vec<usize> = self
+            .order
+            .iter()
+            .map(|col| match self.hidden.contains(col) {
+                true => 0,
+                false => widths[*col],
+            })
+            .collect();
+        self.columns = widths_to_columns(&widths);
+    }
+
+    pub fn move_down(&mut self) -> RenderingAction {
+        if self.is_bottom() {
+            if !self.final_row_visible() {
+                self.offsets.row += 1;
+                return RenderingAction::Rerender;
+            }
+        } else {
+            self.cur_pos.row += 1;
+            return RenderingAction::MoveCursor;
+        };
+        RenderingAction::None
+    }
+
+    pub fn move_page_down(&mut self) -> RenderingAction {
+        // no data rows to move to
+        if self.row_count() == 0 {
+            RenderingAction::None
+        }
+        // from the header, we jump to the first data row
+        else if self.cur_pos.row == 0 {
+            self.cur_pos.row = 1;
+            RenderingAction::MoveCursor
+        }
+        // the final row is not yet visible, we need to shift the window to
+        else if !self.final_row_visible() {
+            self.offsets.row = min(
+                // the last window position or
+                self.row_count() - self.displayable_data_rows(),
+                // to the next position, making the current last row the first
+                self.offsets.row + (self.displayable_data_rows() - 1),
+            );
+            RenderingAction::Rerender
+        }
+        // the final row is already within our window
+        else if self.cur_pos.row != self.displayable_data_rows() {
+            self.cur_pos.row = self.displayable_data_rows();
+            RenderingAction::MoveCursor
+        } else {
+            RenderingAction::None
+        }
+    }
+
+    pub fn move_up(&mut self) -> RenderingAction {
+        if self.cur_pos.row == 1 {
+            if !self.first_row_visible() {
+                self.offsets.row -= 1;
+                return RenderingAction::Rerender;
+            } else {
+                self.cur_pos.row -= 1;
+                return RenderingAction::MoveCursor;
+            }
+        } else if self.cur_pos.row != 0 {
+            self.cur_pos.row -= 1;
+            return RenderingAction::MoveCursor;
+        };
+        RenderingAction::None
+    }
+
+    pub fn move_page_up(&mut self) -> RenderingAction {
+        if !self.first_row_visible() {
+            let new_row = self
+                .offsets
+                .row
+                .wrapping_sub(self.displayable_data_rows() - 1);
+            self.offsets.row = if new_row < self.offsets.row {
+                new_row
+            } else {
+                0
+            };
+            RenderingAction::Rerender
+        } else if self.cur_pos.row != 0 {
+            self.cur_pos.row = 0;
+            RenderingAction::MoveCursor
+        } else {
+            RenderingAction::None
+        }
+    }
+
+    /// Scrolls the window so that the current data row is at the given window position
+    /// (0 is the first data row of the window) without moving the cursor's data row.
+    fn scroll_current_row_to(&mut self, position: usize) -> RenderingAction {
+        let row = match self.current_data_row() {
+            Some(row) => row,
+            None => return RenderingAction::None,
+        };
+        let max_offset = self
+            .row_count()
+            .saturating_sub(self.displayable_data_rows());
+        let offset = min(row.saturating_sub(position), max_offset);
+        if offset == self.offsets.row {
+            return RenderingAction::None;
+        }
+        self.offsets.row = offset;
+        self.cur_pos.row = row - offset + 1;
+        RenderingAction::Rerender
+    }
+
+    // zt: current row at the top of the window
+    pub fn scroll_top(&mut self) -> RenderingAction {
+        self.scroll_current_row_to(0)
+    }
+
+    // zz: current row in the center of the window
+    pub fn scroll_center(&mut self) -> RenderingAction {
+        self.scroll_current_row_to(self.displayable_data_rows() / 2)
+    }
+
+    // zb: current row at the bottom of the window
+    pub fn scroll_bottom(&mut self) -> RenderingAction {
+        self.scroll_current_row_to(self.displayable_data_rows() - 1)
+    }
+
+    pub fn move_home(&mut self) -> RenderingAction {
+        self.offsets.row = 0;
+        self.cur_pos.row = 0;
+        RenderingAction::Rerender
+    }
+
+    pub fn move_end(&mut self) -> RenderingAction {
+        // all data rows fit into one window
+        if self.row_count() <= self.displayable_data_rows() {
+            self.cur_pos.row = self.row_count();
+        }
+        // move window to last position and cursor to last row
+        else {
+            self.offsets.row = self.row_count() - self.displayable_data_rows();
+            self.cur_pos.row = self.displayable_data_rows();
+        }
+        RenderingAction::Rerender
+    }
+
+    pub fn move_right(&mut self) -> RenderingAction {
+        let next = (self.current_position() + 1..self.order.len()).find(|&i| self.is_shown(i));
+        self.move_to_position(next)
+    }
+
+    pub fn move_left(&mut self) -> RenderingAction {
+        let prev = (0..self.current_position())
+            .rev()
+            .find(|&i| self.is_shown(i));
+        self.move_to_position(prev)
+    }
+
+    pub fn move_start_of_line(&mut self) -> RenderingAction {
+        let first = (0..self.order.len()).find(|&i| self.is_shown(i));
+        self.move_to_position(first)
+    }
+
+    pub fn move_end_of_line(&mut self) -> RenderingAction {
+        let last = (0..self.order.len()).rev().find(|&i| self.is_shown(i));
+        self.move_to_position(last)
+    }
+
+    // Is the column at this position in the display order not hidden?
+    fn is_shown(&self, position: usize) -> bool {
+        !self.hidden.contains(&self.order[position])
+    }
+
+    // Moves the cursor to a shown column, if there is one in that direction.
+    fn move_to_position(&mut self, position: Option<usize>) -> RenderingAction {
+        let position = match position {
+            Some(position) if position != self.current_position() => position,
+            _ => return RenderingAction::None,
+        };
+        let offset = self.offsets.col;
+        self.jump_to_position(position);
+        // The window only scrolls if the new column is (partially) outside of it
+        if self.offsets.col == offset {
+            RenderingAction::MoveCursor
+        } else {
+            RenderingAction::Rerender
+        }
+    }
+}
+
+/// Maximum nesting depth when script commands return other script commands.
+#[cfg(feature = "scripting")]
+const MAX_SCRIPT_DEPTH: usize = 8;
+
+// Mouse actions, at screen positions counted from 0
+impl TableState {
+    // Screen column at which the shown column at the display position starts.
+    fn screen_x(&self, position: usize) -> usize {
+        match position < self.frozen {
+            true => self.columns[position].index,
+            false => self.columns[position].index - self.x_offset(),
+        }
+    }
+
+    // Display positions of the columns on the screen, from left to right.
+    fn shown_positions(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.frozen)
+            .chain(self.offsets.col..self.columns.len())
+            .filter(|&position| self.columns[position].width > 0)
+    }
+
+    /// Display position of the column shown at the screen column `x`.
+    pub fn position_at(&self, x: usize) -> Option<usize> {
+        self.shown_positions().find(|&position| {
+            let start = self.screen_x(position);
+            (start..start + self.columns[position].width).contains(&x)
+        })
+    }
+
+    /// Display position of the column whose right edge, its last screen column, is at `x` on the
+    /// header line, where dragging it changes the width of the column.
+    pub fn column_edge_at(&self, x: usize, y: usize) -> Option<usize> {
+        if y != self.has_groups() as usize {
+            return None;
+        }
+        self.position_at(x)
+            .filter(|&position| self.screen_x(position) + self.columns[position].width == x + 1)
+    }
+
+    /// A click on a cell moves the cursor there, a click on the header also sorts by the column,
+    /// ascending and then descending.
+    pub fn click(&mut self, x: usize, y: usize) -> RenderingAction {
+        let position = match self.position_at(x) {
+            Some(position) => position,
+            None => return RenderingAction::None,
+        };
+        let row = match y.checked_sub(self.header_height()) {
+            Some(line) => line + 1,
+            None if y == self.has_groups() as usize => 0,
+            None => return RenderingAction::None,
+        };
+        if row > self.displayable_data_rows() || self.offsets.row + row > self.row_count() {
+            return RenderingAction::None;
+        }
+        let offset = self.offsets.col;
+        self.cur_pos.row = row;
+        self.jump_to_position(position);
+        if row == 0 {
+            let col = self.order[position];
+            return match self.sort_order[..] {
+                [(sorted, Ordering::Less)] if sorted == col => self.descending(col),
+                _ => self.ascending(col),
+            };
+        }
+        match self.offsets.col == offset {
+            true => RenderingAction::MoveCursor,
+            false => RenderingAction::Rerender,
+        }
+    }
+
+    /// Scrolls the rows by the given number, keeping the cursor on its row while it is in view.
+    pub fn scroll_rows(&mut self, delta: isize) -> RenderingAction {
+        let height = self.displayable_data_rows();
+        let max_offset = self.row_count().saturating_sub(height);
+        let offset = min(self.offsets.row.saturating_add_signed(delta), max_offset);
+        if offset == self.offsets.row || height == 0 {
+            return RenderingAction::None;
+        }
+        let row = self.current_data_row();
+        self.offsets.row = offset;
+        if let Some(row) = row {
+            self.cur_pos.row = row.clamp(offset, offset + height - 1) - offset + 1;
+        }
+        RenderingAction::Rerender
+    }
+
+    /// Makes the column at the display position end at the screen column `x` while its edge is
+    /// dragged, keeping the window where it is.
+    pub fn drag_column_edge(&mut self, position: usize, x: usize) -> RenderingAction {
+        let width = (x + 1).saturating_sub(self.screen_x(position)).max(1);
+        let mut widths = self.widths();
+        let col = self.order[position];
+        if widths[col] == width {
+            return RenderingAction::None;
+        }
+        widths[col] = width;
+        self.lay_out(&widths);
+        RenderingAction::Rerender
+    }
+}
+
+#[cfg(feature = "scripting")]
+// Script hooks
+impl TableState {
+    fn script_context(&self) -> rhai::Map {
+        let row = self.current_data_row().map(|row| self.view_row(row));
+        let row_index = self.current_data_row().unwrap_or(0);
+        script::context_map(&self.header, row, row_index, self.current_column())
+    }
+
+    /// Runs the result of a script function as command batch.
+    fn run_script_result(
+        &mut self,
+        result: Result<String, Box<dyn std::error::Error>>,
+    ) -> RenderingAction {
+        match result {
+            Ok(batch) => {
+                self.script_depth += 1;
+                let action = self.execute_batch(&batch);
+                self.script_depth -= 1;
+                action
+            }
+            Err(err) => self.show_error(&format!("Script error: {}", err)),
+        }
+    }
+
+    /// Calls the script function `name(ctx, args)` and executes the returned command batch.
+    fn run_script_command(&mut self, name: &str, args: &str) -> RenderingAction {
+        match &self.script {
+            Some(script)
+                if self.script_depth < MAX_SCRIPT_DEPTH && script.has_function(name, 2) =>
+            {
+                let result = script.call(name, (self.script_context(), args.to_string()));
+                self.run_script_result(result)
+            }
+            _ => self.show_error(&format!("Unknown command: {}", name)),
+        }
+    }
+
+    /// Passes an unbound key to the script function `on_key(key, ctx)`.
+    pub fn script_key(&mut self, key: &str) -> RenderingAction {
+        match &self.script {
+            Some(script) if script.has_function("on_key", 2) => {
+                let result = script.call("on_key", (key.to_string(), self.script_context()));
+                self.run_script_result(result)
+            }
+            _ => RenderingAction::None,
+        }
+    }
+
+    /// `:compute name func` adds a column with values computed by `func(row)`.
+    fn compute_column(&mut self, args: &str) -> RenderingAction {
+        let (name, func) = match args.split_once(char::is_whitespace) {
+            Some((name, func)) => (name, func.trim()),
+            None => return self.show_error("Usage: compute <name> <function>"),
+        };
+        let script = match &self.script {
+            Some(script) if script.has_function(func, 1) => script,
+            _ => return self.show_error(&format!("Unknown script function: {}", func)),
+        };
+        let mut values = Vec::with_capacity(self.rows.len());
+        for row in &self.rows {
+            match script.call(func, (script::row_map(&self.header, row),)) {
+                Ok(value) => values.push(value),
+                Err(err) => return self.show_error(&format!("Script error: {}", err)),
+            }
+        }
+        self.add_column(name.to_string(), values);
+        RenderingAction::Rerender
+    }
+}
+
+// Parses `key=value` options; values may be quoted with `'` or `"` and contain `\t`, `\\` and
+// escaped quotes.
+fn parse_options(args: &str) -> Result<Vec<(String, String)>, String> {
+    let mut options = Vec::new();
+    let mut chars = args.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(options);
+        }
+        let key: String =
+            std::iter::from_fn(|| chars.next_if(|&c| c != '=' && !c.is_whitespace())).collect();
+        if chars.next() != Some('=') {
+            return Err(format!("Expected <option>=<value> instead of {}", key));
+        }
+        let mut value = String::new();
+        match chars.next_if(|&c| c == '\'' || c == '"') {
+            Some(quote) => loop {
+                match chars.next() {
+                    Some('\\') => match chars.next() {
+                        Some('t') => value.push('\t'),
+                        Some(c) => value.push(c),
+                        None => return Err(format!("Missing closing {}", quote)),
+                    },
+                    Some(c) if c == quote => break,
+                    Some(c) => value.push(c),
+                    None => return Err(format!("Missing closing {}", quote)),
+                }
+            },
+            None => {
+                let raw: String =
+                    std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+                value = raw.replace("\\t", "\t");
+            }
+        }
+        options.push((key, value));
+    }
+}
+
+// File name for a value: characters that are unsafe in paths are replaced by `_`.
+#[cfg(feature = "csv")]
+fn file_name(value: &str) -> String {
+    if crate::cell::is_null(value) {
+        return "empty".to_string();
+    }
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// Size in bytes with a unit, e.g. `1.5 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit + 1 < UNITS.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// Encoding of a file as far as the viewer reads it: UTF-8, possibly with byte order mark.
+fn encoding(path: &Path) -> &'static str {
+    use std::io::Read;
+    let mut start = [0; 3];
+    let bom = std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut start))
+        .is_ok_and(|_| start == [0xEF, 0xBB, 0xBF]);
+    if bom {
+        "UTF-8 with BOM"
+    } else {
+        "UTF-8"
+    }
+}
+
+// Delimiter or quote character for display, naming invisible ones.
+fn describe_byte(byte: u8) -> String {
+    match byte {
+        b'\t' => "tab".to_string(),
+        b' ' => "space".to_string(),
+        _ => format!("'{}'", byte as char),
+    }
+}
+
+// Formats a number with dots between groups of three digits, e.g. 56.789.
+pub(crate) fn group_digits(number: usize) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+// Position of a value when sorting a column: values of the column type first, then text that
+// cannot be parsed as that type, then nulls.
+fn sort_rank(cell: &Cell, col_type: ColType) -> u8 {
+    match cell {
+        Cell::Null => 2,
+        Cell::Str(_) if col_type != ColType::Str => 1,
+        _ => 0,
+    }
+}
+
+// How well a column name matches the name given to `:col`, lower is better: 0 for equal, 1 for
+// equal ignoring case, 2 for a prefix, 3 for a part and 4 for the letters in order.
+fn column_match(column: &str, name: &str) -> Option<u8> {
+    if column == name {
+        return Some(0);
+    }
+    let (column, name) = (column.to_lowercase(), name.to_lowercase());
+    let mut letters = column.chars();
+    if column == name {
+        Some(1)
+    } else if column.starts_with(&name) {
+        Some(2)
+    } else if column.contains(&name) {
+        Some(3)
+    } else if name.chars().all(|c| letters.any(|l| l == c)) {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+fn note_text(session: &Session, hash: u64) -> String {
+    session.notes.get(&hash).cloned().unwrap_or_default()
+}
+
+fn tag_text(session: &Session, hash: u64) -> String {
+    match session.tags.get(&hash) {
+        Some(tags) => tags.iter().cloned().collect::<Vec<String>>().join(", "),
+        None => String::new(),
+    }
+}
+
+// Removes rows repeating the header, as left by concatenating files with a header each, keeping
+// the row numbers of the others. Returns the number of removed rows.
+fn skip_header_rows(header: &[String], rows: &mut Vec<Vec<String>>) -> usize {
+    if header.iter().skip(1).all(String::is_empty) {
+        return 0;
+    }
+    let before = rows.len();
+    rows.retain(|row| !row.iter().skip(1).eq(header.iter().skip(1)));
+    before - rows.len()
+}
+
+// Does the first row look like a second header or a row of types, with text in all columns whose
+// other values are numbers?
+fn header_like(rows: &[Vec<String>]) -> bool {
+    let (first, rest) = match rows.split_first() {
+        Some((first, rest)) if !rest.is_empty() => (first, rest),
+        _ => return false,
+    };
+    let mut numeric = (1..first.len())
+        .filter(|&col| infer_type(rest.iter().map(|row| row[col].as_str())).is_numeric())
+        .peekable();
+    numeric.peek().is_some()
+        && numeric
+            .all(|col| !is_null(&first[col]) && !infer_type(once(first[col].as_str())).is_numeric())
+}
+
+/// Makes column names unique: empty names become `col_<index>`, repeated names get a
+/// numeric suffix (`name`, `name_2`, ...). Returns the number of renamed columns.
+fn disambiguate_header(header: &mut [String]) -> usize {
+    let mut renamed = 0;
+    for i in 0..header.len() {
+        let original = header[i].trim().to_string();
+        let base = if original.is_empty() {
+            format!("col_{}", i)
+        } else {
+            original.clone()
+        };
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while header[..i].contains(&name) {
+            suffix += 1;
+            name = format!("{}_{}", base, suffix);
+        }
+        // surrounding whitespace is dropped silently, only new names are reported
+        if name != original {
+            renamed += 1;
+        }
+        header[i] = name;
+    }
+    renamed
+}
+
+// Group of each column for a header naming the first column of each group, with empty names
+// continuing the group before; the row numbers have no group.
+fn group_names(header: &[String]) -> Vec<String> {
+    let mut groups: Vec<String> = Vec::with_capacity(header.len());
+    for (col, name) in header.iter().enumerate() {
+        let group = match name.trim() {
+            _ if col == 0 => "",
+            "" => groups.last().map_or("", String::as_str),
+            name => name,
+        };
+        groups.push(group.to_string());
+    }
+    groups
+}
+
+// Column names and directions of a sort spec like `-price, name`; `-` sorts descending.
+fn sort_spec(spec: &str) -> impl Iterator<Item = (&str, Ordering)> {
+    spec.split(',')
+        .map(str::trim)
+        .map(|key| match key.strip_prefix('-') {
+            Some(name) => (name.trim(), Ordering::Greater),
+            None => (key.strip_prefix('+').unwrap_or(key).trim(), Ordering::Less),
+        })
+}
+
+fn layout_columns(
+    header: &Vec<String>,
+    rows: &[Vec<String>],
+    padding: usize,
+    width: usize,
+) -> Vec<ColFormat> {
+    widths_to_columns(&compute_col_widths(
+        once(header).chain(rows.iter()),
+        padding,
+        width,
+    ))
+}
+
+fn widths_to_columns(widths: &[usize]) -> Vec<ColFormat> {
+    widths
+        .iter()
+        .scan(0, |acc, &width| {
+            let index = *acc;
+            *acc += width;
+            Some(ColFormat { width, index })
+        })
+        .collect()
+}
+
+/// Distributes the available width among columns: if they are too wide, the widest are
+/// capped first; if there is space left, all grow proportionally to their width.
+pub fn fit_widths(widths: &[usize], available: usize) -> Vec<usize> {
+    let min_width = PADDING + 1;
+    let total: usize = widths.iter().sum();
+    if widths.is_empty() || total == available {
+        return widths.to_vec();
+    }
+    if total < available {
+        let extra = available - total;
+        let mut fitted: Vec<usize> = widths.iter().map(|w| w + extra * w / total).collect();
+        // hand out what is left due to rounding, widest columns first
+        let mut order: Vec<usize> = (0..widths.len()).collect();
+        order.sort_by_key(|&i| Reverse(widths[i]));
+        let rest = available - fitted.iter().sum::<usize>();
+        for &i in order.iter().cycle().take(rest) {
+            fitted[i] += 1;
+        }
+        return fitted;
+    }
+    // find the largest cap for which the capped widths fit
+    let capped_total = |cap: usize| -> usize {
+        widths
+            .iter()
+            .map(|&w| w.min(cap).max(min_width.min(w)))
+            .sum()
+    };
+    let (mut low, mut high) = (min_width, *widths.iter().max().unwrap());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if capped_total(mid) <= available {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let mut fitted: Vec<usize> = widths
+        .iter()
+        .map(|&w| w.min(low).max(min_width.min(w)))
+        .collect();
+    // columns that were cut may use the remaining space
+    let mut rest = available.saturating_sub(fitted.iter().sum());
+    for (fitted, &width) in fitted.iter_mut().zip(widths) {
+        if rest == 0 {
+            break;
+        }
+        if width > *fitted {
+            *fitted += 1;
+            rest -= 1;
         }
-        RenderingAction::Rerender
     }
+    fitted
 }
 
 fn compute_col_widths<'a, I>(mut rows: I, padding: usize, window_width: usize) -> Vec<usize>
@@ -331,12 +4276,12 @@ where
     I: Iterator<Item = &'a Vec<String>>,
 {
     let mut widths: Vec<usize> = match rows.next() {
-        Some(header) => header.iter().map(|value| value.chars().count()).collect(),
+        Some(header) => header.iter().map(|value| display_width(value)).collect(),
         None => return vec![],
     };
     for row in rows {
         for (i, value) in row.iter().enumerate() {
-            let length = value.chars().count();
+            let length = display_width(value);
             if length > widths[i] {
                 widths[i] = length;
             }
@@ -346,8 +4291,8 @@ where
     for w in &mut widths {
         *w += padding;
         if *w > window_width {
-            *w -= *w - window_width;
+            *w = window_width;
         }
     }
-    return widths;
+    widths
 }
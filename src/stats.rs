@@ -0,0 +1,321 @@
+//! Column statistics.
+use crate::cell::{infer_type, is_null, Cell, ColType};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+/// Basic profile of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSummary {
+    pub col_type: ColType,
+    pub count: usize,
+    pub nulls: usize,
+    pub distinct: usize,
+    /// Smallest and largest non-null value by the column type
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+impl ColumnSummary {
+    /// Share of null values in percent.
+    pub fn null_percentage(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            100.0 * self.nulls as f64 / self.count as f64
+        }
+    }
+}
+
+/// Computes the summary of a column's values.
+pub fn summarize<'a, I>(values: I, col_type: ColType) -> ColumnSummary
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut distinct = HashSet::new();
+    let mut count = 0;
+    let mut nulls = 0;
+    let mut min: Option<&str> = None;
+    let mut max: Option<&str> = None;
+    let compare = |a: &str, b: &str| Cell::parse(a, col_type).total_cmp(&Cell::parse(b, col_type));
+    for value in values {
+        count += 1;
+        if is_null(value) {
+            nulls += 1;
+            continue;
+        }
+        if distinct.insert(value) {
+            if min.is_none_or(|min| compare(value, min) == Ordering::Less) {
+                min = Some(value);
+            }
+            if max.is_none_or(|max| compare(value, max) == Ordering::Greater) {
+                max = Some(value);
+            }
+        }
+    }
+    ColumnSummary {
+        col_type,
+        count,
+        nulls,
+        distinct: distinct.len(),
+        min: min.map(str::to_string),
+        max: max.map(str::to_string),
+    }
+}
+
+/// Mean of the non-null values of a numeric column, None for other columns or without values.
+pub fn mean<'a, I>(values: I, col_type: ColType) -> Option<f64>
+where
+    I: Iterator<Item = &'a str>,
+{
+    if !col_type.is_numeric() {
+        return None;
+    }
+    let (sum, count) = values
+        .filter_map(|value| Cell::parse(value, col_type).as_f64())
+        .fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Median of the non-null values of a numeric column, the mean of the two middle ones for an even
+/// number of values; None for other columns or without values.
+pub fn median<'a, I>(values: I, col_type: ColType) -> Option<f64>
+where
+    I: Iterator<Item = &'a str>,
+{
+    if !col_type.is_numeric() {
+        return None;
+    }
+    let mut numbers: Vec<f64> = values
+        .filter_map(|value| Cell::parse(value, col_type).as_f64())
+        .collect();
+    numbers.sort_by(f64::total_cmp);
+    let middle = numbers.len() / 2;
+    match numbers.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((numbers[middle - 1] + numbers[middle]) / 2.0),
+        _ => Some(numbers[middle]),
+    }
+}
+
+/// Sample standard deviation of the non-null values of a numeric column, None for other columns
+/// or fewer than two values.
+pub fn std_dev<'a, I>(values: I, col_type: ColType) -> Option<f64>
+where
+    I: Iterator<Item = &'a str>,
+{
+    if !col_type.is_numeric() {
+        return None;
+    }
+    let numbers: Vec<f64> = values
+        .filter_map(|value| Cell::parse(value, col_type).as_f64())
+        .collect();
+    if numbers.len() < 2 {
+        return None;
+    }
+    let mean = numbers.iter().sum::<f64>() / numbers.len() as f64;
+    let squares: f64 = numbers.iter().map(|number| (number - mean).powi(2)).sum();
+    Some((squares / (numbers.len() - 1) as f64).sqrt())
+}
+
+/// Full profile of a column as shown by `:stats` and `:describe` and printed by `--describe`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub summary: ColumnSummary,
+    /// Only for numeric columns with values, see `mean`, `median` and `std_dev`
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub std_dev: Option<f64>,
+}
+
+/// Names of the fields of a profile in the order of `ColumnProfile::fields`.
+pub const PROFILE_FIELDS: [&str; 10] = [
+    "type", "rows", "nulls", "null %", "distinct", "min", "max", "mean", "median", "stddev",
+];
+
+impl ColumnProfile {
+    /// Formatted values of the fields named by `PROFILE_FIELDS`, empty where there is no value.
+    pub fn fields(&self) -> Vec<String> {
+        let number = |number: Option<f64>| number.map_or(String::new(), |n| format!("{:.4}", n));
+        let summary = &self.summary;
+        vec![
+            summary.col_type.to_string(),
+            summary.count.to_string(),
+            summary.nulls.to_string(),
+            format!("{:.1}", summary.null_percentage()),
+            summary.distinct.to_string(),
+            summary.min.clone().unwrap_or_default(),
+            summary.max.clone().unwrap_or_default(),
+            number(self.mean),
+            number(self.median),
+            number(self.std_dev),
+        ]
+    }
+}
+
+/// Computes the summary, mean, median and standard deviation of a column's values.
+pub fn profile<'a, I>(values: I, col_type: ColType) -> ColumnProfile
+where
+    I: Iterator<Item = &'a str> + Clone,
+{
+    ColumnProfile {
+        summary: summarize(values.clone(), col_type),
+        mean: mean(values.clone(), col_type),
+        median: median(values.clone(), col_type),
+        std_dev: std_dev(values, col_type),
+    }
+}
+
+/// A time interval of the rows counted by `time_buckets`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeBucket {
+    /// Start of the interval in seconds since 1970
+    pub start: i64,
+    pub count: usize,
+    /// First of the rows in the interval, None if it is empty
+    pub first_row: Option<usize>,
+}
+
+/// Counts the rows per interval of `size` seconds, given their indices and times, from the
+/// earliest to the latest interval including empty ones in between. Fails if there would be
+/// more than `limit` intervals.
+pub fn time_buckets<I>(times: I, size: i64, limit: usize) -> Result<Vec<TimeBucket>, String>
+where
+    I: Iterator<Item = (usize, i64)>,
+{
+    let mut counts: BTreeMap<i64, (usize, usize)> = BTreeMap::new();
+    for (row, time) in times {
+        counts.entry(time.div_euclid(size)).or_insert((0, row)).0 += 1;
+    }
+    let (first, last) = match (counts.keys().next(), counts.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Ok(Vec::new()),
+    };
+    if (last - first) as u64 >= limit as u64 {
+        return Err(format!("More than {} buckets, choose a larger size", limit));
+    }
+    Ok((first..=last)
+        .map(|bucket| {
+            let (count, first_row) = match counts.get(&bucket) {
+                Some(&(count, row)) => (count, Some(row)),
+                None => (0, None),
+            };
+            TimeBucket {
+                start: bucket * size,
+                count,
+                first_row,
+            }
+        })
+        .collect())
+}
+
+/// Profiles of all columns of a table except the row numbers, with inferred types.
+pub fn describe(header: &[String], rows: &[Vec<String>]) -> Vec<(String, ColumnProfile)> {
+    (1..header.len())
+        .map(|col| {
+            let values = rows.iter().map(|row| row[col].as_str());
+            (
+                header[col].clone(),
+                profile(values.clone(), infer_type(values)),
+            )
+        })
+        .collect()
+}
+
+/// Column profiles as a JSON object with the number of rows and one entry per column.
+pub fn describe_json(rows: usize, profiles: &[(String, ColumnProfile)]) -> String {
+    let columns: Vec<String> = profiles
+        .iter()
+        .map(|(name, profile)| {
+            let summary = &profile.summary;
+            let value = |value: &Option<String>| match value {
+                Some(value) => json_value(value, summary.col_type),
+                None => "null".to_string(),
+            };
+            let number = |number: Option<f64>| match number {
+                Some(number) if number.is_finite() => number.to_string(),
+                _ => "null".to_string(),
+            };
+            format!(
+                "    {{\"name\": {}, \"type\": \"{}\", \"count\": {}, \"nulls\": {}, \
+                 \"distinct\": {}, \"min\": {}, \"max\": {}, \"mean\": {}, \"median\": {}, \
+                 \"stddev\": {}}}",
+                json_string(name),
+                summary.col_type,
+                summary.count,
+                summary.nulls,
+                summary.distinct,
+                value(&summary.min),
+                value(&summary.max),
+                number(profile.mean),
+                number(profile.median),
+                number(profile.std_dev)
+            )
+        })
+        .collect();
+    format!(
+        "{{\n  \"rows\": {},\n  \"columns\": [\n{}\n  ]\n}}",
+        rows,
+        columns.join(",\n")
+    )
+}
+
+/// Column profiles as an aligned plain text table.
+pub fn describe_text(profiles: &[(String, ColumnProfile)]) -> String {
+    let width = profiles
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(Some(6))
+        .max()
+        .unwrap_or_default();
+    let mut text = format!(
+        "{:width$}  type    nulls  distinct        mean      median      stddev  min .. max\n",
+        "column",
+        width = width
+    );
+    let number = |number: Option<f64>| number.map_or("-".to_string(), |n| format!("{:.4}", n));
+    for (name, profile) in profiles {
+        let summary = &profile.summary;
+        text.push_str(&format!(
+            "{:width$}  {:5}  {:5.1}%  {:8}  {:>10}  {:>10}  {:>10}  {} .. {}\n",
+            name,
+            summary.col_type.to_string(),
+            summary.null_percentage(),
+            summary.distinct,
+            number(profile.mean),
+            number(profile.median),
+            number(profile.std_dev),
+            summary.min.as_deref().unwrap_or("-"),
+            summary.max.as_deref().unwrap_or("-"),
+            width = width
+        ));
+    }
+    text
+}
+
+// Numbers are written as JSON numbers, everything else as strings.
+fn json_value(value: &str, col_type: ColType) -> String {
+    match Cell::parse(value, col_type) {
+        Cell::Int(i) => i.to_string(),
+        Cell::Float(f) if f.is_finite() => f.to_string(),
+        _ => json_string(value),
+    }
+}
+
+/// Quotes and escapes a string for JSON.
+pub fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
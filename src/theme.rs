@@ -0,0 +1,151 @@
+//! Colors readable on light and dark terminal backgrounds. The background is taken from the
+//! `COLORFGBG` variable some terminals set or else asked from the terminal with OSC 11.
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use termion::color::AnsiValue;
+use termion::raw::IntoRawMode;
+
+/// How long to wait for the terminal to report its background color.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(format!("unknown theme '{}' (light, dark or auto)", name)),
+        }
+    }
+}
+
+impl Theme {
+    /// Color of details standing out from the text, e.g. the ellipsis of truncated values.
+    pub fn accent(&self) -> AnsiValue {
+        match self {
+            Theme::Dark => AnsiValue(3),
+            Theme::Light => AnsiValue(4),
+        }
+    }
+
+    /// Theme for the terminal's background, None if it cannot be found out.
+    pub fn detect() -> Option<Theme> {
+        match env::var("COLORFGBG") {
+            Ok(value) => from_colorfgbg(&value),
+            Err(_) => query_background(),
+        }
+    }
+}
+
+/// Lines separating the header from the data rows and the frozen columns from the scrolled ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rules {
+    #[default]
+    None,
+    /// Box-drawing characters
+    Box,
+    /// Dashes and bars, for fonts without box-drawing characters
+    Ascii,
+}
+
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(Rules::None),
+            "box" => Ok(Rules::Box),
+            "ascii" => Ok(Rules::Ascii),
+            _ => Err(format!("unknown rules '{}' (box, ascii or none)", name)),
+        }
+    }
+}
+
+impl Rules {
+    /// Characters of the horizontal line, the vertical one and their crossing, None without
+    /// rules.
+    pub fn chars(&self) -> Option<(char, char, char)> {
+        match self {
+            Rules::None => None,
+            Rules::Box => Some(('─', '│', '┼')),
+            Rules::Ascii => Some(('-', '|', '+')),
+        }
+    }
+}
+
+/// Theme for a `COLORFGBG` value like `15;0`, whose last number is the background color.
+pub fn from_colorfgbg(value: &str) -> Option<Theme> {
+    match value.rsplit(';').next()?.parse::<u8>().ok()? {
+        0..=6 | 8 => Some(Theme::Dark),
+        7 | 9..=15 => Some(Theme::Light),
+        _ => None,
+    }
+}
+
+/// Theme for the terminal's answer to an OSC 11 query, e.g. `\x1b]11;rgb:ffff/ffff/ffff\x07`,
+/// by the relative luminance of the background color.
+pub fn from_background_reply(reply: &str) -> Option<Theme> {
+    let rgb = reply.split_once("rgb:")?.1;
+    let rgb = rgb.trim_end_matches(['\x07', '\\', '\x1b']);
+    let channels: Vec<f64> = rgb
+        .split('/')
+        .map(|hex| {
+            let value = u32::from_str_radix(hex, 16).ok()?;
+            let max = 16u32.checked_pow(hex.len() as u32)? - 1;
+            Some(value as f64 / max as f64)
+        })
+        .collect::<Option<_>>()?;
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    match 0.2126 * r + 0.7152 * g + 0.0722 * b {
+        luminance if luminance < 0.5 => Some(Theme::Dark),
+        _ => Some(Theme::Light),
+    }
+}
+
+// Asks the terminal for its background color, giving up if it does not answer in time.
+fn query_background() -> Option<Theme> {
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    // restores the terminal mode when dropped
+    let _raw = tty.try_clone().ok()?.into_raw_mode().ok()?;
+    write!(tty, "\x1b]11;?\x07").ok()?;
+    tty.flush().ok()?;
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    let mut buffer = [0; 64];
+    while !reply.ends_with(b"\x07") && !reply.ends_with(b"\x1b\\") {
+        let left = deadline.checked_duration_since(Instant::now())?;
+        let mut poll = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Safety: poll only writes to the one pollfd passed
+        if unsafe { libc::poll(&mut poll, 1, left.as_millis() as libc::c_int) } <= 0 {
+            return None;
+        }
+        let count = tty.read(&mut buffer).ok()?;
+        if count == 0 {
+            return None;
+        }
+        reply.extend_from_slice(&buffer[..count]);
+    }
+    from_background_reply(&String::from_utf8_lossy(&reply))
+}
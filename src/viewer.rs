@@ -1,22 +1,77 @@
 //! Handles user input and uses table state and renderer to update terminal.
-use crate::renderer::{RenderingAction, TableRenderer};
-use crate::state::TableState;
-use crate::termion::input::TermRead;
+use crate::clipboard;
+use crate::input::{self, Input, Modifiers};
+use crate::lazy::{Pager, TableDataSource};
+use crate::renderer::{self, RenderingAction, TableRenderer};
+use crate::state::{group_digits, CharCoord, TableState, Task};
+use crate::stats::{summarize, ColumnSummary};
+use crate::theme::{Rules, Theme};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::io::{stdout, Write};
-use termion::event::Key;
-use termion::raw::IntoRawMode;
+use std::fs::{File, OpenOptions};
+use std::io::{self, stdout, Read, Write};
+use std::iter::once;
+use std::os::unix::io::FromRawFd;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering as AtomicOrdering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+#[cfg(feature = "log")]
+use std::time::Instant;
+use termion::event::{Key, MouseButton, MouseEvent};
+use termion::input::MouseTerminal;
+use termion::raw::{IntoRawMode, RawTerminal};
+#[cfg(feature = "log")]
+use tracing::{debug, trace};
 
 pub struct TableViewer<T: TableRenderer> {
     state: TableState,
     renderer: T,
     mode: Mode,
+    pending_summaries: HashSet<usize>,
+    // Number typed before a key like `G`
+    count: Option<usize>,
+    // Operator key waiting for the next key to complete it, like `d` in Vim
+    operator: Option<Key>,
+    // Column whose right edge is dragged with the mouse
+    dragging: Option<usize>,
+    // Cleared to stop the timer scrolling a presentation
+    ticker: Option<Arc<AtomicBool>>,
+    // The viewer stopped itself and was set up again, so continuing needs no redraw
+    suspended: bool,
+    // Source of a table read on demand, whose rows on screen are the table's rows
+    pager: Option<Pager>,
 }
 
+/// Input of the event loop: key presses and results of background computations.
+pub enum Event {
+    Key(Key),
+    /// Key with modifiers reported by the xterm or kitty keyboard protocol
+    Modified(Modifiers, Key),
+    Paste(String),
+    Mouse(MouseEvent),
+    /// Summary of a column, computed on the table as of the given number of reloads
+    Summary(usize, usize, ColumnSummary),
+    Message(String),
+    Tick,
+    /// New size of the terminal
+    Resize(CharCoord),
+    /// The process is asked to stop, e.g. by `kill -TSTP`
+    Suspend,
+    /// The process continues after having been stopped, e.g. by `kill -STOP`
+    Continue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Mode {
     Normal,
     Command,
+    Pane,
+    Present,
+    Prompt,
+    Record,
 }
 
 impl<T: TableRenderer> TableViewer<T> {
@@ -27,100 +82,997 @@ impl<T: TableRenderer> TableViewer<T> {
             state,
             renderer,
             mode,
+            pending_summaries: HashSet::new(),
+            count: None,
+            operator: None,
+            dragging: None,
+            ticker: None,
+            suspended: false,
+            pager: None,
+        }
+    }
+
+    /// Shows a table read from the source as it is scrolled instead of loaded, read-only and
+    /// without sorting, filtering or searching. Column types are those of the first page.
+    pub fn paged(renderer: T, source: Box<dyn TableDataSource>) -> Result<Self, Box<dyn Error>> {
+        let mut pager = Pager::new(source);
+        let size = renderer.window_size();
+        // the header and the status line take two lines
+        let (header, rows) = pager.first_page(size.y.saturating_sub(2))?;
+        let mut viewer = TableViewer::new(renderer, header, rows);
+        viewer.state.status_message = Some(pager.status(&viewer.state));
+        viewer.pager = Some(pager);
+        Ok(viewer)
+    }
+
+    /// Installs a user script providing custom commands and key handlers.
+    #[cfg(feature = "scripting")]
+    pub fn with_script(mut self, script: crate::script::ScriptHost) -> Self {
+        self.state.script = Some(script);
+        self
+    }
+
+    /// Validates the table against a schema, showing violations on start.
+    #[cfg(feature = "config")]
+    pub fn with_schema(mut self, schema: &crate::schema::Schema) -> Self {
+        self.state.apply_schema(schema);
+        if self.state.pane.is_some() {
+            self.mode = Mode::Pane;
+        }
+        self
+    }
+
+    /// Starts with the file picker showing the CSV and TSV files of the directory.
+    #[cfg(feature = "csv")]
+    pub fn with_file_picker(mut self, dir: &std::path::Path) -> std::io::Result<Self> {
+        self.state.picker = Some(crate::picker::FilePicker::new(dir)?);
+        self.mode = Mode::Pane;
+        Ok(self)
+    }
+
+    /// Starts with the list of recently opened files.
+    #[cfg(feature = "csv")]
+    pub fn with_recent_files(mut self) -> Self {
+        self.state.show_recent_files();
+        self.mode = Mode::Pane;
+        self
+    }
+
+    /// Sets the case sensitivity of searches.
+    pub fn with_search_case(mut self, case: crate::state::SearchCase) -> Self {
+        self.state.search_case = case;
+        self
+    }
+
+    /// Keeps the row numbers and the first `count` columns in view when scrolling horizontally.
+    pub fn with_frozen_columns(mut self, count: Option<usize>) -> Self {
+        if let Some(count) = count {
+            self.state.freeze_columns(count + 1);
+        }
+        self
+    }
+
+    /// Applies the settings of a config profile other than the file format.
+    pub fn with_profile(mut self, profile: &crate::config::Profile) -> Result<Self, String> {
+        self.state.apply_profile(profile)?;
+        Ok(self)
+    }
+
+    /// Turns colors on or off.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.state.color = color;
+        self
+    }
+
+    /// Picks colors readable on the terminal's background.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.state.theme = theme;
+        self
+    }
+
+    /// Takes the header as the names of column groups and the first row as the header, for files
+    /// with two header lines.
+    pub fn with_group_header(mut self) -> Self {
+        self.state.promote_group_header();
+        if self.state.has_groups() {
+            self.state.status_message = None;
+        }
+        self
+    }
+
+    /// Draws lines under the header and after the frozen columns.
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.state.rules = rules;
+        self
+    }
+
+    /// Tells that the table was cut off after the given number of rows, which `:loadall` reads
+    /// completely.
+    pub fn with_row_cap(mut self, row_cap: Option<usize>) -> Self {
+        if let Some(cap) = row_cap {
+            self.state.status_message = Some(format!(
+                "Loaded the first {} rows (cap reached), :loadall reads all",
+                group_digits(cap)
+            ));
         }
+        self.state.row_cap = row_cap;
+        self
+    }
+
+    /// Records how long reading the table took, shown by `:info`.
+    pub fn with_load_time(mut self, load_time: Duration) -> Self {
+        self.state.load_time = Some(load_time);
+        self
+    }
+
+    /// Keeps track of opened files in the history.
+    pub fn with_history(mut self, history: crate::history::History) -> Self {
+        self.state.history = history;
+        self
+    }
+
+    /// Uses the notes of a review session, saving new ones to its sidecar file.
+    pub fn with_session(mut self, session: crate::session::Session) -> Self {
+        self.state.open_session(session);
+        self
+    }
+
+    /// Enables `:save`, writing the table file with the given delimiter and quote character.
+    pub fn with_save_format(mut self, delimited: Option<(u8, u8)>, backup: bool) -> Self {
+        self.state.delimited = delimited;
+        self.state.backup = backup;
+        self
+    }
+
+    /// Sets the spaces after the content of each column.
+    pub fn with_padding(mut self, padding: usize) -> Self {
+        self.state.set_padding(padding);
+        self
+    }
+
+    /// Maps keys to commands run instead of their built-in action.
+    pub fn with_keys(mut self, keys: HashMap<String, String>) -> Self {
+        self.state.keys = keys;
+        self
+    }
+
+    /// Sorts the rows on start by a spec as for `:sort`, ignoring columns the table does not
+    /// have.
+    pub fn with_sort(mut self, spec: Option<&str>) -> Self {
+        if let Some(spec) = spec {
+            self.state.default_sort(spec);
+        }
+        self
+    }
+
+    /// Registers user-defined command aliases.
+    pub fn with_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.state.aliases = aliases;
+        self
+    }
+
+    /// State of the table as changed by the events handled so far.
+    pub fn state(&self) -> &TableState {
+        &self.state
+    }
+
+    /// Writes the table as plain text instead of viewing it, e.g. when the output is redirected.
+    pub fn print(&self, out: &mut dyn Write) -> io::Result<()> {
+        for line in renderer::plain_lines(&self.state) {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let mut stdout = stdout().into_raw_mode().unwrap();
+        let mut stdout = MouseTerminal::from(stdout().into_raw_mode()?);
         let stdin = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
-        if let Some(value) = self
-            .renderer
-            .render(&self.state, &RenderingAction::Rerender)
-        {
-            print!("{}", value);
-            stdout.flush()?;
+        let (sender, receiver) = mpsc::channel();
+        let key_sender = sender.clone();
+        thread::spawn(move || {
+            for input in input::inputs(stdin) {
+                let event = match input {
+                    Input::Key(key) => Event::Key(key),
+                    Input::Modified(modifiers, key) => Event::Modified(modifiers, key),
+                    Input::Paste(text) => Event::Paste(text),
+                    Input::Mouse(mouse) => Event::Mouse(mouse),
+                };
+                if key_sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        start_resize_watcher(&sender);
+        start_signal_watcher(&sender)?;
+        self.event_loop(receiver.iter(), &sender, &mut stdout)
+    }
+
+    /// Runs the viewer on the given events instead of the terminal's input, writing to the given
+    /// screen, e.g. to drive whole sessions in tests. Results of background computations are
+    /// handled after the event that started them if they are ready by then.
+    pub fn run_events<I, S>(&mut self, events: I, screen: &mut S) -> Result<(), Box<dyn Error>>
+    where
+        I: IntoIterator<Item = Event>,
+        S: Screen,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let events = events
+            .into_iter()
+            .flat_map(|event| once(event).chain(receiver.try_iter()));
+        self.event_loop(events, &sender, screen)
+    }
+
+    // Handles events until the viewer is quit or the events run out.
+    fn event_loop<I, S>(
+        &mut self,
+        events: I,
+        sender: &Sender<Event>,
+        screen: &mut S,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        I: Iterator<Item = Event>,
+        S: Screen,
+    {
+        let initial_action = self.redraw();
+        let mut title = self.state.window_title();
+        write!(screen, "{}", self.renderer.init(&self.state))?;
+        if let Some(value) = self.renderer.render(&self.state, &initial_action) {
+            write!(screen, "{}", value)?;
+            screen.flush()?;
         }
+        self.request_summary(sender);
         let mut prev_key: Key = Key::Home;
-        for c in stdin.keys() {
-            let key = c.unwrap();
-            let action = match self.mode {
-                Mode::Normal => match key {
-                    // Quit app
-                    Key::Char('q') | Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => {
-                        RenderingAction::Reset
+        for event in events {
+            #[cfg(feature = "log")]
+            log_event(&event, self.mode);
+            #[cfg(feature = "log")]
+            let prev_mode = self.mode;
+            #[cfg(feature = "log")]
+            let prev_position = (self.state.offsets, self.state.cur_pos);
+            let prev_col = self.state.current_column();
+            let action = match event {
+                // Suspend like other programs in the shell, in any mode
+                Event::Key(Key::Ctrl('z')) | Event::Suspend => {
+                    write!(screen, "{}", self.renderer.teardown())?;
+                    screen.flush()?;
+                    screen.suspend()?;
+                    write!(screen, "{}", self.renderer.init(&self.state))?;
+                    self.suspended = true;
+                    self.redraw()
+                }
+                Event::Continue if self.suspended => {
+                    self.suspended = false;
+                    RenderingAction::None
+                }
+                // Stopped from outside, the shell may have used the terminal in the meantime
+                Event::Continue => {
+                    write!(screen, "{}", self.renderer.teardown())?;
+                    screen.resume()?;
+                    write!(screen, "{}", self.renderer.init(&self.state))?;
+                    self.redraw()
+                }
+                Event::Key(key) => {
+                    let had_message = self.state.status_message.take().is_some();
+                    let action = self.handle_key(key, prev_key);
+                    prev_key = key;
+                    if had_message {
+                        action.merge(RenderingAction::Status)
+                    } else {
+                        action
                     }
-                    // Sort by column: ascending or descending
-                    Key::Char('a') => self.state.ascending(self.state.current_column()),
-                    Key::Char('d') => self.state.descending(self.state.current_column()),
-                    Key::Char('o') => self.state.ascending(0),
-                    // Navigation
-                    Key::Down | Key::Char('j') => self.state.move_down(),
-                    Key::Up | Key::Char('k') => self.state.move_up(),
-                    Key::PageDown => self.state.move_page_down(),
-                    Key::PageUp => self.state.move_page_up(),
-                    Key::Home => self.state.move_home(),
-                    Key::Char('g') if prev_key == Key::Char('g') => self.state.move_home(),
-                    Key::End | Key::Char('G') => self.state.move_end(),
-                    Key::Right | Key::Char('l') => self.state.move_right(),
-                    Key::Left | Key::Char('h') => self.state.move_left(),
-                    Key::Char('0') => self.state.move_start_of_line(),
-                    Key::Char('$') => self.state.move_end_of_line(),
-                    // Switch to command mode
-                    Key::Char('/') => {
-                        self.mode = Mode::Command;
-                        self.state.command_buffer.clear();
-                        self.state.command_buffer.push('/');
-                        RenderingAction::Command
+                }
+                Event::Modified(modifiers, key) => {
+                    let had_message = self.state.status_message.take().is_some();
+                    let action = self.handle_modified_key(modifiers, key);
+                    prev_key = key;
+                    if had_message {
+                        action.merge(RenderingAction::Status)
+                    } else {
+                        action
                     }
-                    // Repeat last command
-                    Key::Char(' ') => self.state.execute_command(),
+                }
+                // Pasted text goes into the command line or the value of the filter builder as it
+                // is, it is no command in other modes
+                Event::Paste(text) => match self.mode {
+                    Mode::Command => self.state.paste(&text),
+                    Mode::Pane => text
+                        .chars()
+                        .filter(|c| !c.is_control())
+                        .fold(RenderingAction::None, |action, c| {
+                            action.merge(self.state.builder_insert(c))
+                        }),
                     _ => RenderingAction::None,
                 },
-                Mode::Command => match key {
-                    // Quit app
-                    Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => RenderingAction::Reset,
-                    // Execute command
-                    Key::Char('\n') => {
-                        self.mode = Mode::Normal;
-                        if self.state.command_buffer.len() <= 1 {
-                            RenderingAction::Rerender
-                        } else {
-                            self.state.execute_command()
-                        }
+                Event::Mouse(mouse) => {
+                    let had_message = self.state.status_message.take().is_some();
+                    let action = self.handle_mouse(mouse);
+                    if had_message {
+                        action.merge(RenderingAction::Status)
+                    } else {
+                        action
                     }
-                    // Enter command character
-                    Key::Char(c) => {
-                        self.state.command_buffer.push(c);
-                        RenderingAction::Command
-                    }
-                    // Delete command character
-                    Key::Backspace => {
-                        self.state.command_buffer.pop();
-                        if self.state.command_buffer.is_empty() {
-                            self.mode = Mode::Normal;
-                            RenderingAction::Rerender
-                        } else {
-                            RenderingAction::Command
-                        }
-                    }
-                    // Switch to normal mode
-                    Key::Esc => {
-                        self.mode = Mode::Normal;
-                        self.state.command_buffer.clear();
-                        RenderingAction::Rerender
+                }
+                Event::Resize(size) => {
+                    let position = self.pager.as_ref().map(|pager| pager.position(&self.state));
+                    self.state.resize(size);
+                    if let (Some(pager), Some(position)) = (&mut self.pager, position) {
+                        pager.refill(&mut self.state, position);
                     }
+                    self.redraw()
+                }
+                Event::Tick => match self.mode {
+                    Mode::Present => self.state.auto_scroll(),
                     _ => RenderingAction::None,
                 },
+                Event::Message(message) => {
+                    self.state.status_message = Some(message);
+                    RenderingAction::Status
+                }
+                Event::Summary(col, reloads, _) if reloads != self.state.reloads => {
+                    self.pending_summaries.remove(&col);
+                    RenderingAction::None
+                }
+                Event::Summary(col, _, summary) => {
+                    self.pending_summaries.remove(&col);
+                    self.state.summaries.insert(col, summary);
+                    if col == self.state.current_column() {
+                        RenderingAction::Status
+                    } else {
+                        RenderingAction::None
+                    }
+                }
+            };
+            if let Some(task) = self.state.task.take() {
+                run_task(task, sender);
+            }
+            let action = match self
+                .state
+                .clipboard
+                .take()
+                .map(|text| clipboard::copy(&text, screen))
+            {
+                Some(Err(message)) => {
+                    self.state.status_message = Some(message);
+                    action.merge(RenderingAction::Status)
+                }
+                _ => action,
+            };
+            let action = if prev_col != self.state.current_column() {
+                self.request_summary(sender);
+                action.merge(RenderingAction::Status)
+            } else {
+                action
+            };
+            if let RenderingAction::Pane = action {
+                self.mode = Mode::Pane;
+            }
+            if self.state.prompt.is_some() {
+                self.mode = Mode::Prompt;
+            }
+            if let (Some(presentation), Mode::Normal) = (&self.state.presentation, &self.mode) {
+                self.mode = Mode::Present;
+                if let Some(interval) = presentation.interval {
+                    self.ticker = Some(start_ticker(interval, sender));
+                }
+            }
+            // The status line is not shown in panes and presentations and replaced by the
+            // command line
+            let action = match (&self.mode, action) {
+                (
+                    Mode::Pane | Mode::Command | Mode::Present | Mode::Prompt,
+                    RenderingAction::Status,
+                ) => RenderingAction::None,
+                (_, action) => action,
             };
+            #[cfg(feature = "log")]
+            if self.mode != prev_mode {
+                debug!(from = ?prev_mode, to = ?self.mode, "mode");
+            }
+            #[cfg(feature = "log")]
+            if (self.state.offsets, self.state.cur_pos) != prev_position {
+                debug!(
+                    row = self.state.current_row(),
+                    col = self.state.current_column(),
+                    offsets = ?self.state.offsets,
+                    cursor = ?self.state.cur_pos,
+                    "moved"
+                );
+            }
+            #[cfg(feature = "log")]
+            let started = Instant::now();
             if let Some(value) = self.renderer.render(&self.state, &action) {
-                print!("{}", value);
-                stdout.flush()?;
+                write!(screen, "{}", value)?;
+                // the rerendered table covers the command line, e.g. while searching
+                if let (Mode::Command | Mode::Prompt, RenderingAction::Rerender) =
+                    (&self.mode, &action)
+                {
+                    write!(screen, "{}", self.renderer.render_command(&self.state))?;
+                }
+                screen.flush()?;
+                #[cfg(feature = "log")]
+                debug!(
+                    ?action,
+                    bytes = value.len(),
+                    micros = started.elapsed().as_micros() as u64,
+                    "rendered"
+                );
             }
             if let RenderingAction::Reset = action {
                 break;
             }
-            prev_key = key;
+            if self.state.window_title() != title {
+                title = self.state.window_title();
+                write!(screen, "{}", self.renderer.render_title(&self.state))?;
+                screen.flush()?;
+            }
+        }
+        write!(screen, "{}", self.renderer.teardown())?;
+        screen.flush()?;
+        Ok(())
+    }
+
+    // Rendering of the whole screen in the current mode.
+    fn redraw(&self) -> RenderingAction {
+        match self.mode {
+            Mode::Pane => RenderingAction::Pane,
+            _ => RenderingAction::Rerender,
+        }
+    }
+
+    /// Computes the summary of the current column in the background unless it is cached.
+    fn request_summary(&mut self, sender: &Sender<Event>) {
+        let col = self.state.current_column();
+        // the rows on screen tell little about a table read on demand
+        if self.pager.is_some() {
+            return;
+        }
+        if self.state.summaries.contains_key(&col) || !self.pending_summaries.insert(col) {
+            return;
+        }
+        let values: Vec<String> = self.state.rows.iter().map(|row| row[col].clone()).collect();
+        let col_type = self.state.types[col];
+        let reloads = self.state.reloads;
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let summary = summarize(values.iter().map(String::as_str), col_type);
+            // the receiver is gone if the viewer has been closed in the meantime
+            let _ = sender.send(Event::Summary(col, reloads, summary));
+        });
+    }
+
+    // Clicks move the cursor or sort by a column of the header, dragging the right edge of a
+    // header cell resizes the column and the wheel scrolls, in normal mode only.
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> RenderingAction {
+        self.operator = None;
+        if self.mode != Mode::Normal {
+            return RenderingAction::None;
+        }
+        // a table read on demand only scrolls
+        if let Some(pager) = &mut self.pager {
+            return match mouse {
+                MouseEvent::Press(MouseButton::WheelDown, _, _) => {
+                    pager.handle_key(&mut self.state, Key::Down, Key::Null)
+                }
+                MouseEvent::Press(MouseButton::WheelUp, _, _) => {
+                    pager.handle_key(&mut self.state, Key::Up, Key::Null)
+                }
+                _ => RenderingAction::None,
+            };
+        }
+        match mouse {
+            // positions start at 1
+            MouseEvent::Press(MouseButton::Left, x, y) => {
+                let (x, y) = (x.saturating_sub(1) as usize, y.saturating_sub(1) as usize);
+                match self.state.column_edge_at(x, y) {
+                    Some(position) => {
+                        self.dragging = Some(position);
+                        RenderingAction::None
+                    }
+                    None => self.state.click(x, y),
+                }
+            }
+            MouseEvent::Press(MouseButton::WheelUp, _, _) => self.state.scroll_rows(-3),
+            MouseEvent::Press(MouseButton::WheelDown, _, _) => self.state.scroll_rows(3),
+            MouseEvent::Hold(x, _) => match self.dragging {
+                Some(position) => self
+                    .state
+                    .drag_column_edge(position, x.saturating_sub(1) as usize),
+                None => RenderingAction::None,
+            },
+            MouseEvent::Release(_, _) => {
+                self.dragging = None;
+                RenderingAction::None
+            }
+            _ => RenderingAction::None,
+        }
+    }
+
+    // Completes an operator with the key typed after it: `dd` deletes the current row and `Esc`
+    // cancels `d`, while any other key sorts by the current column in descending order first and
+    // then has its usual effect.
+    fn complete_operator(&mut self, operator: Key, key: Key, prev_key: Key) -> RenderingAction {
+        match (operator, key) {
+            (Key::Char('d'), Key::Char('d')) => self.state.delete_row(),
+            (_, Key::Esc) => RenderingAction::None,
+            _ => {
+                let sorted = self.state.descending(self.state.current_column());
+                sorted.merge(self.handle_key(key, prev_key))
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: Key, prev_key: Key) -> RenderingAction {
+        if let Some(operator) = self.operator.take() {
+            return self.complete_operator(operator, key, prev_key);
+        }
+        if let (Mode::Normal, Some(pager)) = (self.mode, &mut self.pager) {
+            return pager.handle_key(&mut self.state, key, prev_key);
+        }
+        // Keys mapped to commands by the config or profile replace their built-in action
+        if self.mode == Mode::Normal {
+            let name = input::key_name(Modifiers::default(), key);
+            if let Some(action) = name.and_then(|name| self.state.mapped_key(&name)) {
+                self.count = None;
+                return action;
+            }
+        }
+        // any key but a digit ends the count
+        let count = self.count.take();
+        match self.mode {
+            Mode::Normal => match key {
+                // Count prefix like Vim's, e.g. 123G
+                Key::Char(c @ '0'..='9') if c != '0' || count.is_some() => {
+                    let digit = c.to_digit(10).unwrap_or_default() as usize;
+                    self.count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                    RenderingAction::None
+                }
+                // Quit app
+                Key::Char('q') | Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => {
+                    RenderingAction::Reset
+                }
+                // Sort by column: ascending or descending
+                Key::Char('a') => self.state.ascending(self.state.current_column()),
+                // ... once the next key shows that it is not `dd`, see `complete_operator`
+                Key::Char('d') => {
+                    self.operator = Some(key);
+                    RenderingAction::None
+                }
+                Key::Char('o') => self.state.original_order(),
+                // Add the column to the sort order, breaking ties of the previous columns
+                Key::Char('A') => self
+                    .state
+                    .then_sort(self.state.current_column(), Ordering::Less),
+                Key::Char('D') => self
+                    .state
+                    .then_sort(self.state.current_column(), Ordering::Greater),
+                // Navigation
+                Key::Down | Key::Char('j') => self.state.move_down(),
+                Key::Up | Key::Char('k') => self.state.move_up(),
+                Key::PageDown => self.state.move_page_down(),
+                Key::PageUp => self.state.move_page_up(),
+                Key::Home => self.state.move_home(),
+                Key::Char('g') if prev_key == Key::Char('g') => self.state.move_home(),
+                Key::Char('G') if count.is_some() => self.state.go_to_row(count.unwrap_or(1)),
+                Key::End | Key::Char('G') => self.state.move_end(),
+                Key::Right | Key::Char('l') => self.state.move_right(),
+                Key::Left | Key::Char('h') => self.state.move_left(),
+                Key::Char('z') if prev_key == Key::Char('z') => self.state.scroll_center(),
+                Key::Char('t') if prev_key == Key::Char('z') => self.state.scroll_top(),
+                Key::Char('b') if prev_key == Key::Char('z') => self.state.scroll_bottom(),
+                // Hide the current column and show all hidden ones again
+                Key::Char('c') if prev_key == Key::Char('z') => self.state.hide_column(),
+                Key::Char('R') if prev_key == Key::Char('z') => self.state.unhide_columns(),
+                // Keep the columns up to the current one in view when scrolling horizontally
+                Key::Char('f') if prev_key == Key::Char('z') => self.state.toggle_frozen(),
+                Key::Char('0') => self.state.move_start_of_line(),
+                // Tag the current row
+                Key::Char('t') => self.state.toggle_tag("flagged"),
+                // Compare the current row with a marked one
+                Key::Char('m') => self.state.mark_anchor(),
+                // Copy the current row or column to the clipboard, after `y` copied the cell
+                Key::Char('y') if prev_key == Key::Char('y') => self.state.yank_row(),
+                Key::Char('c') if prev_key == Key::Char('y') => self.state.yank_column(),
+                Key::Char('y') => self.state.yank_cell(),
+                Key::Char('c') => self.state.compare_rows(),
+                // Put a copy of the current row below it, e.g. `yyp` as in Vim
+                Key::Char('p') => self.state.duplicate_row(),
+                Key::Char('$') => self.state.move_end_of_line(),
+                // Show the statistics of the current column
+                Key::Char('S') => self.state.show_stats(),
+                // Show the complete value of the current cell
+                Key::Char('v') => self.state.show_cell(),
+                // Show the current row with one field per line
+                Key::Char('x') => {
+                    let action = self.state.toggle_record_view();
+                    if self.state.record_view.is_some() {
+                        self.mode = Mode::Record;
+                    }
+                    action
+                }
+                // Switch to command mode
+                Key::Char(c @ ('/' | '?' | ':')) => {
+                    self.mode = Mode::Command;
+                    self.state.start_command(c);
+                    if c != ':' {
+                        self.state.start_search();
+                    }
+                    RenderingAction::Command
+                }
+                // Filter by the value under the cursor and remove the last filter
+                Key::Char('F') => self.state.filter_value(),
+                Key::Backspace => self.state.pop_filter_or_view(),
+                // Count the values of the current column and filter by the selected one
+                Key::Char('C') => self.state.show_frequencies(),
+                Key::Char('\n') => self.state.filter_by_frequency(),
+                // Return from a derived table like the result of :dedup
+                Key::Esc => self.state.close_derived(),
+                // Go back and forth between derived tables
+                Key::Ctrl('o') => self.state.go_back(),
+                Key::Char('\t') => self.state.go_forward(),
+                // Build a filter in a form
+                Key::Ctrl('f') => self.state.start_filter_builder(),
+                // Filter by the current column
+                Key::Char('&') => {
+                    self.mode = Mode::Command;
+                    self.state.start_column_filter()
+                }
+                // Edit the current cell on the command line
+                Key::Char('e') => {
+                    let action = self.state.start_edit();
+                    if let RenderingAction::Command = action {
+                        self.mode = Mode::Command;
+                    }
+                    action
+                }
+                // Repeat last search in its direction or the opposite one
+                Key::Char(' ') | Key::Char('n') => self.state.repeat_search(false),
+                Key::Char('N') => self.state.repeat_search(true),
+                // Search for the value under the cursor, forward or backward
+                Key::Char('*') => self.state.search_value(false),
+                Key::Char('#') => self.state.search_value(true),
+                // Unbound keys run the commands mapped to them or go to the script's key handler
+                key => match input::key_name(Modifiers::default(), key) {
+                    Some(name) => self.state.unbound_key(&name),
+                    None => RenderingAction::None,
+                },
+            },
+            Mode::Command => match key {
+                // Quit app
+                Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => RenderingAction::Reset,
+                // Execute command
+                Key::Char('\n') => {
+                    self.mode = Mode::Normal;
+                    if self.state.command_buffer.len() <= 1 {
+                        self.state.cancel_search();
+                        RenderingAction::Rerender
+                    } else {
+                        self.state.execute_command().merge(RenderingAction::Status)
+                    }
+                }
+                // Accept the suggested completion
+                Key::Char('\t') | Key::Right if self.state.command_tail == 0 => {
+                    match self.state.accept_suggestion() {
+                        RenderingAction::None => RenderingAction::None,
+                        _ => self.state.preview_search(),
+                    }
+                }
+                // Move the cursor within the command line
+                Key::Right => self.state.move_command_cursor(1),
+                Key::Left => self.state.move_command_cursor(-1),
+                Key::Ctrl('a') | Key::Home => self.state.move_command_cursor_to(true),
+                Key::Ctrl('e') | Key::End => self.state.move_command_cursor_to(false),
+                // Delete the word before the cursor or the whole command
+                Key::Ctrl('w') => self.state.delete_command_word(),
+                Key::Ctrl('u') => self.state.clear_command(),
+                // Enter command character; searches jump to the first match while typing
+                Key::Char(c) => self.state.insert_command_char(c),
+                // Delete command character
+                Key::Backspace => {
+                    let action = self.state.delete_command_char();
+                    if self.state.command_buffer.is_empty() {
+                        self.mode = Mode::Normal;
+                        self.state.cancel_search();
+                        RenderingAction::Rerender
+                    } else {
+                        action
+                    }
+                }
+                // Switch to normal mode, returning to where a search started
+                Key::Esc => {
+                    self.mode = Mode::Normal;
+                    self.state.command_buffer.clear();
+                    self.state.cancel_search();
+                    RenderingAction::Rerender
+                }
+                _ => RenderingAction::None,
+            },
+            // Yes or no; any other key cancels
+            Mode::Prompt => {
+                self.mode = Mode::Normal;
+                let yes = matches!(key, Key::Char('y') | Key::Char('Y'));
+                self.state.answer_prompt(yes).merge(RenderingAction::Status)
+            }
+            // Any key ends the presentation
+            Mode::Present => {
+                self.mode = Mode::Normal;
+                if let Some(ticker) = self.ticker.take() {
+                    ticker.store(false, AtomicOrdering::Relaxed);
+                }
+                self.state.stop_presenting()
+            }
+            // Rows are navigated while staying in the record view
+            Mode::Record => match key {
+                Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => RenderingAction::Reset,
+                Key::Down | Key::Char('j') => {
+                    self.state.move_down();
+                    RenderingAction::Rerender
+                }
+                Key::Up | Key::Char('k') => {
+                    // the header has no record
+                    if self.state.cur_pos.row > 1 || self.state.offsets.row > 0 {
+                        self.state.move_up();
+                    }
+                    RenderingAction::Rerender
+                }
+                Key::PageDown => self.state.scroll_record(true),
+                Key::PageUp => self.state.scroll_record(false),
+                Key::Char('x') | Key::Esc | Key::Char('q') => {
+                    self.mode = Mode::Normal;
+                    self.state.toggle_record_view()
+                }
+                _ => RenderingAction::None,
+            },
+            Mode::Pane if self.state.filter_builder.is_some() => match key {
+                Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => RenderingAction::Reset,
+                Key::Up | Key::BackTab => self.state.builder_move_field(false),
+                Key::Down => self.state.builder_move_field(true),
+                Key::Left => self.state.builder_choose(false),
+                Key::Right => self.state.builder_choose(true),
+                Key::Char('\t') => self.state.builder_complete(),
+                Key::Backspace => self.state.builder_delete(),
+                Key::Char('\n') => {
+                    self.mode = Mode::Normal;
+                    self.state.apply_filter_builder()
+                }
+                Key::Esc => {
+                    self.mode = Mode::Normal;
+                    self.state.close_pane()
+                }
+                Key::Char(c) => self.state.builder_insert(c),
+                _ => RenderingAction::None,
+            },
+            Mode::Pane => match key {
+                // Quit app
+                Key::Ctrl('q') | Key::Ctrl('x') | Key::Ctrl('c') => RenderingAction::Reset,
+                Key::Down | Key::Char('j') => self.state.pane_down(),
+                Key::Up | Key::Char('k') => self.state.pane_up(),
+                // Show, hide and move columns in the column editor
+                Key::Char(' ') => self.state.pane_toggle(),
+                Key::Char('J') => self.state.pane_shift(true),
+                Key::Char('K') => self.state.pane_shift(false),
+                // Jump to selected entry
+                Key::Char('\n') => {
+                    self.mode = Mode::Normal;
+                    self.state.pane_select()
+                }
+                // Switch to normal mode
+                Key::Esc | Key::Char('q') => {
+                    self.mode = Mode::Normal;
+                    self.state.close_pane()
+                }
+                _ => RenderingAction::None,
+            },
+        }
+    }
+
+    // Keys with modifiers are only bound in normal mode, other modes ignore them.
+    fn handle_modified_key(&mut self, modifiers: Modifiers, key: Key) -> RenderingAction {
+        if !matches!(self.mode, Mode::Normal) {
+            return RenderingAction::None;
+        }
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let name = input::key_name(modifiers, key);
+        if let Some(action) = name.and_then(|name| self.state.mapped_key(&name)) {
+            return action;
+        }
+        match key {
+            // Jump to the first or last column or row
+            Key::Left if modifiers == ctrl => self.state.move_start_of_line(),
+            Key::Right if modifiers == ctrl => self.state.move_end_of_line(),
+            Key::Up if modifiers == ctrl => self.state.move_home(),
+            Key::Down if modifiers == ctrl => self.state.move_end(),
+            // Page up and down
+            Key::Up if modifiers == shift => self.state.move_page_up(),
+            Key::Down if modifiers == shift => self.state.move_page_down(),
+            // Other keys run the commands mapped to them or go to the script's key handler
+            key => match input::key_name(modifiers, key) {
+                Some(name) => self.state.unbound_key(&name),
+                None => RenderingAction::None,
+            },
         }
+    }
+}
+
+// Logs the input and background results handled by the event loop, leaving out the text of
+// pastes and the summaries, which are big.
+#[cfg(feature = "log")]
+fn log_event(event: &Event, mode: Mode) {
+    match event {
+        Event::Key(key) => debug!(?key, ?mode, "key"),
+        Event::Modified(modifiers, key) => debug!(?modifiers, ?key, ?mode, "key"),
+        Event::Paste(text) => debug!(chars = text.chars().count(), "paste"),
+        Event::Mouse(mouse) => debug!(?mouse, ?mode, "mouse"),
+        Event::Summary(col, _, _) => trace!(col, "summary"),
+        Event::Message(message) => debug!(message, "message"),
+        Event::Tick => trace!("tick"),
+        Event::Resize(size) => debug!(?size, "resize"),
+        Event::Suspend => debug!("suspend"),
+        Event::Continue => debug!("continue"),
+    }
+}
+
+/// Output of the viewer: the terminal or, e.g. in tests, any other writer.
+pub trait Screen: Write {
+    /// Hands the terminal back to the shell until the viewer is resumed with `fg`; a no-op for
+    /// writers other than the terminal.
+    fn suspend(&mut self) -> io::Result<()> {
         Ok(())
     }
+
+    /// Takes the terminal back after the process was stopped and continued without `suspend`.
+    fn resume(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write> Screen for RawTerminal<W> {
+    /// Stops the process with SIGTSTP as the shell's job control expects, with the terminal in
+    /// cooked mode until the process is continued.
+    fn suspend(&mut self) -> io::Result<()> {
+        self.suspend_raw_mode()?;
+        // Safety: raise only sends a signal to the calling thread; the viewer's own handler is
+        // set aside meanwhile, so that the signal stops the process
+        unsafe {
+            let handler = libc::signal(libc::SIGTSTP, libc::SIG_DFL);
+            libc::raise(libc::SIGTSTP);
+            libc::signal(libc::SIGTSTP, handler);
+        }
+        self.activate_raw_mode()
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        self.activate_raw_mode()
+    }
+}
+
+impl<W: Screen> Screen for MouseTerminal<W> {
+    /// Turns off mouse reporting while suspended, so that clicks in the shell are not sent as
+    /// escape sequences.
+    fn suspend(&mut self) -> io::Result<()> {
+        write!(self, "{}", input::DISABLE_MOUSE)?;
+        self.flush()?;
+        (**self).suspend()?;
+        write!(self, "{}", input::ENABLE_MOUSE)?;
+        self.flush()
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        (**self).resume()?;
+        write!(self, "{}", input::ENABLE_MOUSE)?;
+        self.flush()
+    }
+}
+
+impl Screen for Vec<u8> {}
+
+/// Runs a task in the background, reporting its progress and result in the status line.
+fn run_task(task: Task, sender: &Sender<Event>) {
+    let sender = sender.clone();
+    thread::spawn(move || {
+        let progress = |message: String| {
+            let _ = sender.send(Event::Message(message));
+        };
+        let message = task(&progress);
+        let _ = sender.send(Event::Message(message));
+    });
+}
+
+/// Checks the terminal size a few times per second, sending the new size when it changed.
+fn start_resize_watcher(sender: &Sender<Event>) {
+    let sender = sender.clone();
+    thread::spawn(move || {
+        let mut size = termion::terminal_size().ok();
+        loop {
+            thread::sleep(Duration::from_millis(250));
+            let new_size = termion::terminal_size().ok();
+            if new_size == size {
+                continue;
+            }
+            size = new_size;
+            if let Some((x, y)) = new_size {
+                let size = CharCoord {
+                    x: x as usize,
+                    y: y as usize,
+                };
+                if sender.send(Event::Resize(size)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+// Write end of the pipe the signal handler reports signals through.
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn report_signal(signal: libc::c_int) {
+    let byte = signal as u8;
+    // Safety: write is async-signal-safe; the pipe stays open for the life of the process
+    unsafe {
+        libc::write(
+            SIGNAL_PIPE.load(AtomicOrdering::Relaxed),
+            &byte as *const u8 as *const libc::c_void,
+            1,
+        );
+    }
+}
+
+/// Turns SIGTSTP and SIGCONT from outside into events, so that the terminal is handed back
+/// before the process stops and set up again once it continues.
+fn start_signal_watcher(sender: &Sender<Event>) -> io::Result<()> {
+    let mut fds = [0; 2];
+    // Safety: pipe fills in two new file descriptors, the read end is owned by the file
+    let mut signals = unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        File::from_raw_fd(fds[0])
+    };
+    SIGNAL_PIPE.store(fds[1], AtomicOrdering::Relaxed);
+    // Safety: the handler only calls write
+    unsafe {
+        libc::signal(
+            libc::SIGTSTP,
+            report_signal as *const () as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGCONT,
+            report_signal as *const () as libc::sighandler_t,
+        );
+    }
+    let sender = sender.clone();
+    thread::spawn(move || {
+        let mut signal = [0];
+        while signals.read_exact(&mut signal).is_ok() {
+            let event = match signal[0] as libc::c_int {
+                libc::SIGTSTP => Event::Suspend,
+                _ => Event::Continue,
+            };
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Sends a tick at the given interval until the returned flag is cleared.
+fn start_ticker(interval: Duration, sender: &Sender<Event>) -> Arc<AtomicBool> {
+    let running = Arc::new(AtomicBool::new(true));
+    let flag = running.clone();
+    let sender = sender.clone();
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if !flag.load(AtomicOrdering::Relaxed) || sender.send(Event::Tick).is_err() {
+            break;
+        }
+    });
+    running
 }
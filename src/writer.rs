@@ -0,0 +1,224 @@
+//! Writing tables to delimiter-separated files and other formats.
+use crate::stats::json_string;
+use std::borrow::Borrow;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes the given columns of header and rows to a file in the format given by its extension:
+/// TSV (`tsv`, `tab`), a GitHub Markdown table (`md`), an array of JSON objects keyed by column
+/// name (`json`), an HTML table (`html`) or else CSV. Returns the number of written rows.
+pub fn write_table<I, R>(
+    path: &Path,
+    header: &[String],
+    columns: &[usize],
+    rows: I,
+) -> Result<usize, Box<dyn Error>>
+where
+    I: Iterator<Item = R>,
+    R: Borrow<Vec<String>>,
+{
+    let mut file = BufWriter::new(fs::File::create(path)?);
+    let count = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tsv" | "tab") => write_delimited(&mut file, b'\t', b'"', header, columns, rows)?,
+        Some("md" | "markdown") => write_markdown(&mut file, header, columns, rows)?,
+        Some("json") => write_json(&mut file, header, columns, rows)?,
+        Some("html" | "htm") => write_html(&mut file, header, columns, rows)?,
+        _ => write_delimited(&mut file, b',', b'"', header, columns, rows)?,
+    };
+    file.flush()?;
+    Ok(count)
+}
+
+/// Writes a GitHub Markdown table; pipes are escaped and line breaks become `<br>`.
+pub fn write_markdown<W, I, R>(
+    mut target: W,
+    header: &[String],
+    columns: &[usize],
+    rows: I,
+) -> io::Result<usize>
+where
+    W: Write,
+    I: Iterator<Item = R>,
+    R: Borrow<Vec<String>>,
+{
+    let cell = |value: &str| {
+        value
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    };
+    let line = |values: Vec<String>| format!("| {} |", values.join(" | "));
+    writeln!(
+        target,
+        "{}",
+        line(columns.iter().map(|&col| cell(&header[col])).collect())
+    )?;
+    writeln!(target, "{}", line(vec!["---".to_string(); columns.len()]))?;
+    let mut count = 0;
+    for row in rows {
+        let row = row.borrow();
+        writeln!(
+            target,
+            "{}",
+            line(columns.iter().map(|&col| cell(&row[col])).collect())
+        )?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Writes an array of JSON objects with the column names as keys and the values as strings.
+pub fn write_json<W, I, R>(
+    mut target: W,
+    header: &[String],
+    columns: &[usize],
+    rows: I,
+) -> io::Result<usize>
+where
+    W: Write,
+    I: Iterator<Item = R>,
+    R: Borrow<Vec<String>>,
+{
+    write!(target, "[")?;
+    let mut count = 0;
+    for row in rows {
+        let row = row.borrow();
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|&col| format!("{}: {}", json_string(&header[col]), json_string(&row[col])))
+            .collect();
+        let separator = if count == 0 { "" } else { "," };
+        write!(target, "{}\n  {{{}}}", separator, fields.join(", "))?;
+        count += 1;
+    }
+    writeln!(target, "{}]", if count == 0 { "" } else { "\n" })?;
+    Ok(count)
+}
+
+/// Writes an HTML table with a style sheet for borders and a shaded header, ready to be pasted
+/// into a page.
+pub fn write_html<W, I, R>(
+    mut target: W,
+    header: &[String],
+    columns: &[usize],
+    rows: I,
+) -> io::Result<usize>
+where
+    W: Write,
+    I: Iterator<Item = R>,
+    R: Borrow<Vec<String>>,
+{
+    writeln!(target, "{}", HTML_STYLE)?;
+    writeln!(target, "<table class=\"table-viewer\">")?;
+    let cells = |tag: &str, row: &[String]| -> String {
+        columns
+            .iter()
+            .map(|&col| format!("<{}>{}</{}>", tag, html_escape(&row[col]), tag))
+            .collect()
+    };
+    writeln!(
+        target,
+        "<thead>\n<tr>{}</tr>\n</thead>\n<tbody>",
+        cells("th", header)
+    )?;
+    let mut count = 0;
+    for row in rows {
+        writeln!(target, "<tr>{}</tr>", cells("td", row.borrow()))?;
+        count += 1;
+    }
+    writeln!(target, "</tbody>\n</table>")?;
+    Ok(count)
+}
+
+const HTML_STYLE: &str = "<style>
+table.table-viewer { border-collapse: collapse; font-family: sans-serif; }
+table.table-viewer th, table.table-viewer td { border: 1px solid #ccc; padding: 0.25em 0.5em; }
+table.table-viewer th { background: #f0f0f0; text-align: left; }
+</style>";
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats the given columns of header and rows as tab-separated values, e.g. for pasting.
+pub fn format_table<I, R>(header: &[String], columns: &[usize], rows: I) -> String
+where
+    I: Iterator<Item = R>,
+    R: Borrow<Vec<String>>,
+{
+    let mut buffer = Vec::new();
+    // writing to memory does not fail
+    write_delimited(&mut buffer, b'\t', b'"', header, columns, rows).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+/// Writes the given columns of header and rows with the given delimiter and quote character.
+pub fn write_delimited<W, I, R>(
+    target: W,
+    delimiter: u8,
+    quote: u8,
+    header: &[String],
+    columns: &[usize],
+    rows: I,
+) -> Result<usize, Box<dyn Error>>
+where
+    W: io::Write,
+    I: Iterator<Item = R>,
+    R: Borrow<Vec<String>>,
+{
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .quote(quote)
+        .from_writer(target);
+    writer.write_record(columns.iter().map(|&col| &header[col]))?;
+    let mut count = 0;
+    for row in rows {
+        let row = row.borrow();
+        writer.write_record(columns.iter().map(|&col| &row[col]))?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Replaces a file without risking its content: `write` fills a temporary file in the same
+/// directory, which is then renamed over the original. With `backup`, the original is kept as
+/// `<file>.bak`.
+pub fn replace_file<F>(path: &Path, backup: bool, write: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce(&mut fs::File) -> Result<(), Box<dyn Error>>,
+{
+    let temp = sibling_path(path, ".", ".tv-tmp");
+    let result = (|| {
+        let mut file = fs::File::create(&temp)?;
+        write(&mut file)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+            if backup {
+                fs::copy(path, sibling_path(path, "", ".bak"))?;
+            }
+        }
+        fs::rename(&temp, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+// Path in the same directory as `path` with prefix and suffix added to the file name.
+fn sibling_path(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(prefix);
+    name.push(path.file_name().unwrap_or_default());
+    name.push(suffix);
+    path.with_file_name(name)
+}
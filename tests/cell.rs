@@ -0,0 +1,256 @@
+use table_viewer::cell::{
+    format_date, infer_type, parse_date, parse_duration, wrap, Cell, ColType,
+};
+
+#[test]
+fn test_infer_type() {
+    assert_eq!(infer_type(["1", "", "-3"].into_iter()), ColType::Int);
+    assert_eq!(infer_type(["1", "2.5", "NA"].into_iter()), ColType::Float);
+    assert_eq!(infer_type(["true", "FALSE"].into_iter()), ColType::Bool);
+    assert_eq!(
+        infer_type(["2022-01-01", "2022-01-02 10:00"].into_iter()),
+        ColType::Date
+    );
+    assert_eq!(infer_type(["1", "x"].into_iter()), ColType::Str);
+    assert_eq!(infer_type(["", "null"].into_iter()), ColType::Str);
+}
+
+#[test]
+fn test_parse_cell() {
+    assert_eq!(Cell::parse(" 42", ColType::Int), Cell::Int(42));
+    assert_eq!(Cell::parse("4.2", ColType::Int), Cell::Str("4.2"));
+    assert_eq!(Cell::parse("", ColType::Float), Cell::Null);
+    assert_eq!(Cell::parse("True", ColType::Bool), Cell::Bool(true));
+    assert_eq!(parse_date("1970-01-02"), Some(86_400));
+    assert_eq!(parse_date("2000-03-01T01:00:00Z"), Some(951_872_400));
+    assert_eq!(parse_date("2000-13-01"), None);
+}
+
+#[test]
+fn test_format_date() {
+    for date in [
+        "1970-01-01",
+        "2000-02-29 23:59",
+        "1969-12-31 00:00:01",
+        "2024-03-01",
+    ] {
+        assert_eq!(format_date(parse_date(date).unwrap()), date);
+    }
+    assert_eq!(parse_duration("15m"), Some(900));
+    assert_eq!(parse_duration("2d"), Some(172_800));
+    assert_eq!(parse_duration("0h"), None);
+    assert_eq!(parse_duration("1y"), None);
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_typed_sort() {
+    use std::path::Path;
+    use table_viewer::csv::read_csv_from_file;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    assert_eq!(
+        state.types,
+        vec![
+            ColType::Int,
+            ColType::Str,
+            ColType::Int,
+            ColType::Str,
+            ColType::Date,
+            ColType::Bool
+        ]
+    );
+    let column = |state: &TableState, col: usize| -> Vec<String> {
+        state.rows.iter().map(|row| row[col].clone()).collect()
+    };
+
+    state.ascending(2);
+    assert_eq!(column(&state, 2), vec!["1", "2", "10", ""]);
+    // missing values come last in both directions
+    state.descending(4);
+    assert_eq!(
+        column(&state, 4),
+        vec!["2022-03-01", "2022-01-15T08:30:00", "2021-12-31", ""]
+    );
+    // as do values that do not fit the column type
+    state.rows[1][2] = "many".to_string();
+    state.descending(2);
+    assert_eq!(column(&state, 2), vec!["10", "2", "1", "many"]);
+    state.ascending(2);
+    assert_eq!(column(&state, 2), vec!["1", "2", "10", "many"]);
+    state.original_order();
+    assert_eq!(column(&state, 1), vec!["b", "a", "c", "d"]);
+}
+
+#[test]
+fn test_override_type() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "code"].map(String::from).to_vec();
+    let rows = ["9", "10", "x7", "08", "7b"]
+        .iter()
+        .enumerate()
+        .map(|(i, code)| vec![(i + 1).to_string(), code.to_string()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    assert_eq!(state.types[1], ColType::Str);
+    state.execute_batch(":sort code");
+    let codes = |state: &TableState| -> Vec<String> {
+        state.rows.iter().map(|row| row[1].clone()).collect()
+    };
+    assert_eq!(codes(&state), vec!["08", "10", "7b", "9", "x7"]);
+    // the sort is redone with the new type
+    state.execute_batch(":type code int");
+    assert_eq!(state.types[1], ColType::Int);
+    assert_eq!(state.status_line(), "code is int (2 values do not fit)");
+    assert_eq!(codes(&state), vec!["08", "9", "10", "7b", "x7"]);
+    state.execute_batch(":type code auto");
+    assert_eq!(state.types[1], ColType::Str);
+    state.execute_batch(":type code number");
+    assert_eq!(
+        state.status_line(),
+        "Usage: type <column> int|float|str|bool|date|auto"
+    );
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_multi_sort() {
+    use std::cmp::Ordering;
+    use std::path::Path;
+    use table_viewer::csv::read_csv_from_file;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    let names = |state: &TableState| -> Vec<String> {
+        state.rows.iter().map(|row| row[1].clone()).collect()
+    };
+
+    state.execute_batch(":sort -flag, +name");
+    assert_eq!(names(&state), vec!["b", "c", "a", "d"]);
+    state.execute_batch(":sort flag,-count");
+    assert_eq!(names(&state), vec!["a", "d", "b", "c"]);
+    state.execute_batch(":sort flag, cost");
+    assert_eq!(state.status_line(), "Unknown column: cost");
+    state.execute_batch(":sort");
+    assert_eq!(state.status_line(), "Usage: sort [+|-]<column>, ...");
+    assert_eq!(names(&state), vec!["a", "d", "b", "c"]);
+
+    // sort chains are built column by column
+    state.descending(5);
+    state.then_sort(1, Ordering::Less);
+    assert_eq!(names(&state), vec!["b", "c", "a", "d"]);
+    state.then_sort(5, Ordering::Less);
+    assert_eq!(names(&state), vec!["a", "b", "c", "d"]);
+    assert_eq!(
+        state.sort_order,
+        vec![(1, Ordering::Less), (5, Ordering::Less)]
+    );
+    state.original_order();
+    assert!(state.sort_order.is_empty());
+    assert_eq!(names(&state), vec!["b", "a", "c", "d"]);
+}
+
+#[test]
+fn test_original_order() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    // no row numbers to sort by
+    let header = vec!["name".to_string(), "size".to_string()];
+    let rows = [["m", "2"], ["z", "1"], ["a", "3"]]
+        .iter()
+        .map(|row| row.map(String::from).to_vec())
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    let names = |state: &TableState| -> Vec<String> {
+        state
+            .view
+            .iter()
+            .map(|&row| state.rows[row][0].clone())
+            .collect()
+    };
+    state.ascending(0);
+    state.descending(1);
+    state.execute_batch(":filter size != 1");
+    assert_eq!(names(&state), vec!["a", "m"]);
+    state.original_order();
+    assert_eq!(names(&state), vec!["m", "a"]);
+    assert!(state.sort_order.is_empty());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_compare_rows() {
+    use std::path::Path;
+    use table_viewer::csv::read_csv_from_file;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 40, y: 10 });
+    state.compare_rows();
+    assert_eq!(state.status_line(), "No row marked, press m on a row first");
+    state.move_down();
+    state.mark_anchor();
+    assert_eq!(
+        state.status_line(),
+        "Marked row 1, press c on another row to compare"
+    );
+    state.compare_rows();
+    assert_eq!(
+        state.status_line(),
+        "Cannot compare the marked row with itself"
+    );
+    // the anchor is kept when the rows are reordered
+    state.descending(1);
+    state.move_down();
+    state.compare_rows();
+    let pane = state.pane.take().unwrap();
+    assert_eq!(pane.title, "Row 1 vs. row 3: 4 of 5 columns differ");
+    let texts: Vec<&str> = pane
+        .entries
+        .iter()
+        .map(|entry| entry.text.as_str())
+        .collect();
+    assert_eq!(texts[0], "name   b              ≠ c");
+    // values are compared by type
+    assert_eq!(texts[4], "flag   true             TRUE");
+    let highlights: Vec<bool> = pane.entries.iter().map(|entry| entry.highlight).collect();
+    assert_eq!(highlights, vec![true, true, true, true, false]);
+}
+
+#[test]
+fn test_wrap() {
+    assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+    assert_eq!(wrap("abcdefghijkl xy", 5), ["abcde", "fghij", "kl xy"]);
+    assert_eq!(wrap("one\r\ntwo\n\nthree", 10), ["one", "two", "", "three"]);
+    assert_eq!(wrap("", 10), [""]);
+}
+
+#[test]
+fn test_show_cell() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header = vec!["#".to_string(), "text".to_string()];
+    let rows = vec![vec![
+        "1".to_string(),
+        "a long value\nwith two lines".to_string(),
+    ]];
+    let mut state = TableState::new(header, rows, CharCoord { x: 10, y: 10 });
+    state.move_right();
+    state.move_down();
+    state.show_cell();
+    let pane = state.pane.take().unwrap();
+    assert_eq!(pane.title, "text in row 1 (27 characters)");
+    let texts: Vec<&str> = pane
+        .entries
+        .iter()
+        .map(|entry| entry.text.as_str())
+        .collect();
+    assert_eq!(texts, ["a long", "value", "with two", "lines"]);
+}
@@ -0,0 +1,74 @@
+use table_viewer::clipboard::{
+    base64, join_fields, osc52, terminal_sequence, Multiplexer, OSC52_LIMIT,
+};
+use table_viewer::state::{CharCoord, TableState};
+
+#[test]
+fn test_osc52() {
+    assert_eq!(base64(b"Man"), "TWFu");
+    assert_eq!(base64(b"Ma"), "TWE=");
+    assert_eq!(base64(b"M"), "TQ==");
+    assert_eq!(base64(b""), "");
+    assert_eq!(osc52("a\tb\n"), "\x1B]52;c;YQliCg==\x07");
+}
+
+#[test]
+fn test_terminal_sequence() {
+    assert_eq!(
+        terminal_sequence("a\tb\n", Multiplexer::None),
+        Ok(osc52("a\tb\n"))
+    );
+    assert_eq!(
+        terminal_sequence("a\tb\n", Multiplexer::Tmux),
+        Ok("\x1BPtmux;\x1B\x1B]52;c;YQliCg==\x07\x1B\\".to_string())
+    );
+    let text = "x".repeat(200);
+    let sequence = terminal_sequence(&text, Multiplexer::Screen).unwrap();
+    let chunks: Vec<&str> = sequence.split_terminator("\x1B\\").collect();
+    assert_eq!(chunks.len(), 4);
+    assert!(chunks.iter().all(|chunk| chunk.starts_with("\x1BP")));
+    assert_eq!(chunks.concat().replace("\x1BP", ""), osc52(&text));
+
+    let limit = OSC52_LIMIT / 4 * 3;
+    assert!(terminal_sequence(&"x".repeat(limit), Multiplexer::None).is_ok());
+    assert_eq!(
+        terminal_sequence(&"x".repeat(limit + 1), Multiplexer::None),
+        Err(OSC52_LIMIT + 4)
+    );
+}
+
+#[test]
+fn test_yank() {
+    let header: Vec<String> = ["#", "id", "comment", "price"].map(String::from).to_vec();
+    let rows = vec![
+        ["1", "17", "ok", "1.5"].map(String::from).to_vec(),
+        ["2", "23", "say \"hi\"\tthere", "2"]
+            .map(String::from)
+            .to_vec(),
+    ];
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.move_right();
+    state.yank_cell();
+    assert_eq!(state.clipboard.take().as_deref(), Some("id"));
+    assert_eq!(state.status_line(), "Copied column name");
+
+    state.move_down();
+    state.move_down();
+    state.yank_cell();
+    assert_eq!(state.clipboard.take().as_deref(), Some("23"));
+    assert_eq!(state.status_line(), "Copied id in row 2");
+    // hidden columns are left out, values with tabs or quotes quoted
+    state.move_end_of_line();
+    state.hide_column();
+    state.yank_row();
+    assert_eq!(
+        state.clipboard.take().as_deref(),
+        Some("23\t\"say \"\"hi\"\"\tthere\"")
+    );
+    assert_eq!(state.status_line(), "Copied row 2 (2 fields)");
+    state.move_left();
+    state.yank_column();
+    assert_eq!(state.clipboard.take().as_deref(), Some("17\n23"));
+    assert_eq!(state.status_line(), "Copied 2 values of id");
+    assert_eq!(join_fields(["a", "b\nc"].into_iter(), ","), "a,\"b\nc\"");
+}
@@ -0,0 +1,77 @@
+use table_viewer::renderer::{RenderingAction, TableRenderer, TerminalTableRenderer};
+use table_viewer::state::{CharCoord, TableState};
+
+fn state_fixture() -> TableState {
+    let header: Vec<String> = ["#", "id", "name", "price"].map(String::from).to_vec();
+    let rows = vec![
+        ["1", "17", "apple", "1.5"].map(String::from).to_vec(),
+        ["2", "23", "pear", "2"].map(String::from).to_vec(),
+    ];
+    TableState::new(header, rows, CharCoord { x: 80, y: 10 })
+}
+
+#[test]
+fn test_reorder_and_hide_columns() {
+    let mut state = state_fixture();
+    state.move_right();
+    state.execute_batch(":columns");
+    let editor = state.column_editor.as_ref().unwrap();
+    assert_eq!(editor.selected, 1);
+    let lines = editor.lines(&state.header, &state.widths(), state.terminal_size);
+    assert!(lines[2].starts_with("\x1B[7m[x] id         4 "));
+
+    // move id behind price, hide name
+    state.pane_shift(true);
+    state.pane_shift(true);
+    assert!(matches!(state.pane_shift(true), RenderingAction::None));
+    state.pane_up();
+    state.pane_up();
+    state.pane_toggle();
+    // nothing changes until the editor is applied
+    assert_eq!(state.order, [0, 1, 2, 3]);
+    assert!(matches!(state.pane_select(), RenderingAction::Rerender));
+    assert!(state.column_editor.is_none());
+    assert_eq!(state.order, [0, 2, 3, 1]);
+    assert_eq!(state.hidden.len(), 1);
+    assert_eq!(state.current_column(), 1);
+
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert_eq!(lines[1].trim_end(), "1    1.5  17");
+    state.move_left();
+    assert_eq!(state.current_column(), 3);
+    assert!(matches!(state.move_right(), RenderingAction::MoveCursor));
+    assert!(matches!(state.move_right(), RenderingAction::None));
+
+    // shown again with its width, in its new place
+    state.execute_batch(":columns");
+    state.pane_up();
+    state.pane_up();
+    state.pane_toggle();
+    state.pane_select();
+    assert!(state.hidden.is_empty());
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert_eq!(lines[1].trim_end(), "1  apple    1.5  17");
+}
+
+#[test]
+fn test_column_editor_cancel() {
+    let mut state = state_fixture();
+    state.execute_batch(":columns");
+    state.pane_toggle();
+    state.pane_down();
+    state.pane_toggle();
+    state.pane_down();
+    state.pane_toggle();
+    state.pane_down();
+    state.pane_toggle();
+    assert!(matches!(state.pane_select(), RenderingAction::Status));
+    assert_eq!(state.status_line(), "Cannot hide all columns");
+    assert!(state.hidden.is_empty());
+
+    state.execute_batch(":columns");
+    state.pane_shift(true);
+    state.close_pane();
+    assert_eq!(state.order, [0, 1, 2, 3]);
+    let output = TerminalTableRenderer {}.render(&state, &RenderingAction::Rerender);
+    assert!(output.is_some());
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use table_viewer::command::{expand, remember, split_batch, suggest, unescape};
+
+#[test]
+fn test_split_batch() {
+    assert_eq!(
+        split_batch(":filter level=ERROR; sort -time;; /foo "),
+        vec![":filter level=ERROR", ":sort -time", "/foo"]
+    );
+}
+
+#[test]
+fn test_expand_aliases() {
+    let aliases: HashMap<String, String> = [
+        ("err", ":filter level=ERROR; :sort -time"),
+        ("e", ":err; :matches"),
+        ("loop", ":loop"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    assert_eq!(
+        expand(":e; /x", &aliases),
+        vec![":filter level=ERROR", ":sort -time", ":matches", "/x"]
+    );
+    // Self-referencing aliases stop expanding at the maximum depth
+    assert_eq!(expand(":loop", &aliases), vec![":loop"]);
+}
+
+#[test]
+fn test_split_quoted() {
+    assert_eq!(
+        split_batch(":reparse delim=';' quote='\\''; :note don't; :filter x = \"a;b\""),
+        vec![
+            ":reparse delim=';' quote='\\''",
+            ":note don't",
+            ":filter x = \"a;b\""
+        ]
+    );
+}
+
+#[test]
+fn test_escapes() {
+    assert_eq!(split_batch("/a\\;b; :sort x"), vec!["/a\\;b", ":sort x"]);
+    assert_eq!(unescape("a\\;b"), "a;b");
+    assert_eq!(unescape("C:\\\\temp\\tx\\"), "C:\\temp\tx\\");
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_reparse() {
+    use std::fs;
+    use table_viewer::csv::read_csv_from_file;
+    use table_viewer::session::Session;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let dir = std::env::temp_dir().join("table_viewer_reparse");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("semicolons.csv");
+    fs::write(&table, "a;b\n'x;y';2\n'z';3\n").unwrap();
+    let (header, rows) = read_csv_from_file(&table, b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.open_session(Session::load(&table).unwrap());
+    assert_eq!(state.header.len(), 2);
+    state.move_down();
+    state.move_down();
+
+    state.execute_batch(":reparse delim=ab");
+    assert_eq!(state.status_line(), "delim must be a single character");
+    state.execute_batch(":reparse sep=x");
+    assert_eq!(state.status_line(), "Unknown option: sep");
+    state.execute_batch(":reparse quote='");
+    assert_eq!(state.status_line(), "Missing closing '");
+
+    state.execute_batch(":reparse delim=';' quote='\\''");
+    assert_eq!(state.status_line(), "2 columns, 2 rows");
+    assert_eq!(state.header, vec!["#", "a", "b"]);
+    assert_eq!(state.rows[0][1], "x;y");
+    assert_eq!(state.current_data_row(), Some(1));
+    assert_eq!(state.delimited, Some((b';', b'\'')));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_load_all() {
+    use std::fs;
+    use std::fs::File;
+    use table_viewer::csv::CsvSource;
+    use table_viewer::registry::{DataSource, LoadOptions};
+    use table_viewer::session::Session;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let dir = std::env::temp_dir().join("table_viewer_load_all");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("numbers.csv");
+    fs::write(&table, "n\n1\n2\n3\n4\n").unwrap();
+    let options = LoadOptions {
+        delimiter: Some(b','),
+        max_rows: Some(2),
+        ..LoadOptions::default()
+    };
+    let source = CsvSource::new("csv", b',', &["csv"]);
+    let (header, rows) = source
+        .load(Box::new(File::open(&table).unwrap()), &options)
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":loadall");
+    assert_eq!(state.status_line(), "All rows are loaded");
+
+    state.open_session(Session::load(&table).unwrap());
+    state.delimited = Some((b',', b'"'));
+    state.row_cap = Some(2);
+    state.move_down();
+    state.move_down();
+    state.execute_batch(":loadall");
+    assert_eq!(state.status_line(), "Loaded all 4 rows");
+    assert_eq!(state.rows.len(), 4);
+    assert_eq!(state.current_data_row(), Some(1));
+    assert!(state.row_cap.is_none());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_suggest() {
+    let mut history = Vec::new();
+    for input in [
+        ":filter price > 10",
+        ":sort -name",
+        ":filter price < 2",
+        ":sort -name",
+    ] {
+        remember(&mut history, input);
+    }
+    assert_eq!(
+        history,
+        vec![":filter price > 10", ":filter price < 2", ":sort -name"]
+    );
+    let columns: Vec<String> = ["name", "unit price"].map(String::from).to_vec();
+    let suggest = |input: &str| suggest(input, &history, &columns);
+    assert_eq!(suggest(":fi").as_deref(), Some("lter price < 2"));
+    assert_eq!(suggest(":filter price >").as_deref(), Some(" 10"));
+    assert_eq!(suggest(":sortkey lower(n"), None);
+    assert_eq!(suggest(":sort un").as_deref(), Some("it price"));
+    assert_eq!(suggest(":sort -name"), None);
+    assert_eq!(suggest(":"), None);
+}
+
+#[test]
+fn test_edit_command_line() {
+    use table_viewer::renderer::{RenderingAction, TableRenderer, TerminalTableRenderer};
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "name"].map(String::from).to_vec();
+    let rows = vec![["1", "a"].map(String::from).to_vec()];
+    let mut state = TableState::new(header, rows, CharCoord { x: 40, y: 5 });
+    let text = |state: &TableState| state.command_buffer.iter().collect::<String>();
+    state.start_command(':');
+    for c in "sort nme".chars() {
+        state.insert_command_char(c);
+    }
+    state.move_command_cursor(-2);
+    state.insert_command_char('a');
+    assert_eq!(text(&state), ":sort name");
+    assert_eq!(state.command_cursor(), 8);
+    // the cursor is drawn at the edit point, without suggestion
+    let output = TerminalTableRenderer {}
+        .render(&state, &RenderingAction::Command)
+        .unwrap();
+    assert!(output.ends_with(":sort name\x1B[5;9H"));
+    state.delete_command_char();
+    state.move_command_cursor_to(true);
+    state.delete_command_char();
+    assert_eq!(text(&state), ":sort nme");
+    state.move_command_cursor(-5);
+    assert_eq!(state.command_cursor(), 1);
+    state.move_command_cursor_to(false);
+    state.delete_command_word();
+    assert_eq!(text(&state), ":sort ");
+    state.delete_command_word();
+    assert_eq!(text(&state), ":");
+    state.insert_command_char('x');
+    state.clear_command();
+    assert_eq!(text(&state), ":");
+    state.delete_command_char();
+    assert!(state.command_buffer.is_empty());
+}
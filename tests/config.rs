@@ -0,0 +1,59 @@
+use table_viewer::config::{Config, Profile};
+
+fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_env_overrides() {
+    let mut config = Config {
+        backup: true,
+        ..Default::default()
+    };
+    config.profile.insert(
+        "logs".to_string(),
+        Profile {
+            delimiter: Some(' '),
+            ..Default::default()
+        },
+    );
+    config
+        .apply_env(vars(&[
+            ("TABLE_VIEWER_DELIMITER", "tab"),
+            ("TABLE_VIEWER_BACKUP", "no"),
+            ("TABLE_VIEWER_COLOR", "0"),
+            ("TABLE_VIEWER_SCRIPT", "/etc/tv.rhai"),
+            ("HOME", "/root"),
+        ]))
+        .unwrap();
+    assert_eq!(config.delimiter, Some('\t'));
+    assert_eq!(config.profile["logs"].delimiter, Some('\t'));
+    assert_eq!(config.quote, None);
+    assert!(!config.backup);
+    assert!(!config.color);
+    assert_eq!(config.script.unwrap().to_str(), Some("/etc/tv.rhai"));
+}
+
+#[test]
+fn test_invalid_env() {
+    let mut config = Config::default();
+    let mut error = |name, value| config.apply_env(vars(&[(name, value)])).unwrap_err();
+    assert_eq!(
+        error("TABLE_VIEWER_QUOTE", "''"),
+        "TABLE_VIEWER_QUOTE must be a single character"
+    );
+    assert_eq!(
+        error("TABLE_VIEWER_COLOR", "maybe"),
+        "TABLE_VIEWER_COLOR must be true or false"
+    );
+    assert_eq!(
+        error("TABLE_VIEWER_MAX_ROWS", "lots"),
+        "TABLE_VIEWER_MAX_ROWS must be a number"
+    );
+    assert_eq!(
+        error("TABLE_VIEWER_THEME", "dark"),
+        "Unknown environment variable TABLE_VIEWER_THEME"
+    );
+}
@@ -0,0 +1,84 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use table_viewer::config::{DashboardConfig, PaneConfig};
+use table_viewer::dashboard::{Dashboard, Rect};
+use table_viewer::registry::Table;
+use table_viewer::state::CharCoord;
+
+fn pane(file: &str, refresh: u64) -> PaneConfig {
+    PaneConfig {
+        file: Some(file.into()),
+        refresh,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_dashboard_layout() {
+    let config = DashboardConfig {
+        columns: 2,
+        panes: vec![pane("a.csv", 0), pane("b.csv", 0), pane("c.csv", 0)],
+    };
+    let dashboard = Dashboard::new(&config, CharCoord { x: 81, y: 20 });
+    let rect = |x, y| Rect {
+        x,
+        y,
+        width: 40,
+        height: 10,
+    };
+    assert_eq!(
+        dashboard.layout(),
+        vec![rect(1, 1), rect(42, 1), rect(1, 11)]
+    );
+}
+
+#[test]
+fn test_dashboard_refresh() {
+    let config = DashboardConfig {
+        columns: 2,
+        panes: vec![pane("once.csv", 0), pane("often.csv", 5)],
+    };
+    let mut dashboard = Dashboard::new(&config, CharCoord { x: 40, y: 10 });
+    let loads = Cell::new(0);
+    let load = |pane: &PaneConfig| -> Result<Table, Box<dyn std::error::Error>> {
+        loads.set(loads.get() + 1);
+        match pane.title().as_str() {
+            "once.csv" => Ok((
+                vec!["#".into(), "x".into()],
+                vec![vec!["1".into(), "a".into()]],
+            )),
+            _ => Err("missing".into()),
+        }
+    };
+    let start = Instant::now();
+    assert!(dashboard.refresh(start, &load));
+    assert_eq!(loads.get(), 2);
+    assert!(!dashboard.refresh(start + Duration::from_secs(4), &load));
+    assert!(dashboard.refresh(start + Duration::from_secs(5), &load));
+    assert_eq!(loads.get(), 3);
+    let output = dashboard.render();
+    assert!(output.contains("Error: missing"));
+    assert!(output.contains("1  a"));
+
+    dashboard.invalidate();
+    dashboard.refresh(start + Duration::from_secs(6), &load);
+    assert_eq!(loads.get(), 5);
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_dashboard_config() {
+    let dir = std::env::temp_dir().join("table_viewer_dashboard");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    let content = "[dashboard]\ncolumns = 3\n\n[[dashboard.pane]]\nfile = \"a.csv\"\n\n\
+                   [[dashboard.pane]]\ntitle = \"Load\"\ncommand = \"uptime\"\nrefresh = 10\n";
+    std::fs::write(&path, content).unwrap();
+    let config = table_viewer::config::Config::load(Some(&path)).unwrap();
+    let dashboard = config.dashboard.unwrap();
+    assert_eq!(dashboard.columns, 3);
+    assert_eq!(dashboard.panes[0].title(), "a.csv");
+    assert_eq!(dashboard.panes[1].title(), "Load");
+    assert_eq!(dashboard.panes[1].refresh, 10);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,156 @@
+use table_viewer::renderer::RenderingAction;
+use table_viewer::state::{CharCoord, TableState};
+
+fn column(state: &TableState, col: usize) -> Vec<String> {
+    (0..state.row_count())
+        .map(|i| state.view_row(i)[col].clone())
+        .collect()
+}
+
+#[test]
+fn test_dedup() {
+    let header: Vec<String> = ["#", "country", "city"].map(String::from).to_vec();
+    let rows = [
+        ("fr", "Paris"),
+        ("de", "Bonn"),
+        ("fr", "Paris"),
+        ("fr", "Lyon"),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, (country, city))| vec![(i + 1).to_string(), country.to_string(), city.to_string()])
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":dedup");
+    assert_eq!(state.status_line(), "3 distinct of 4 rows, Esc returns");
+    assert_eq!(state.header, ["#", "country", "city", "count"]);
+    assert_eq!(column(&state, 2), ["Paris", "Bonn", "Lyon"]);
+    assert_eq!(column(&state, 3), ["2", "1", "1"]);
+    assert!(matches!(state.close_derived(), RenderingAction::Rerender));
+    assert_eq!(state.row_count(), 4);
+    assert!(matches!(state.close_derived(), RenderingAction::None));
+
+    state.execute_batch(":filter city != Bonn");
+    state.execute_batch(":dedup country");
+    assert_eq!(state.status_line(), "1 distinct of 3 rows, Esc returns");
+    assert_eq!(column(&state, 0), ["1"]);
+    assert_eq!(column(&state, 3), ["3"]);
+    state.close_derived();
+    assert_eq!(state.filters.len(), 1);
+    state.execute_batch(":dedup region");
+    assert_eq!(state.status_line(), "Unknown column: region");
+}
+
+#[test]
+fn test_frequencies() {
+    let header: Vec<String> = ["#", "country", "city"].map(String::from).to_vec();
+    let rows = [
+        ("fr", "Paris"),
+        ("de", "Bonn"),
+        ("fr", "Paris"),
+        ("fr", "Lyon"),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, (country, city))| vec![(i + 1).to_string(), country.to_string(), city.to_string()])
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.move_right();
+    state.move_right();
+    state.execute_batch(":freq");
+    assert_eq!(
+        state.status_line(),
+        "3 distinct values of city in 4 rows, Enter filters by one, Esc returns"
+    );
+    assert_eq!(state.header, ["#", "city", "count", "%"]);
+    assert_eq!(column(&state, 1), ["Paris", "Bonn", "Lyon"]);
+    assert_eq!(column(&state, 3), ["50.0", "25.0", "25.0"]);
+    // sortable like any table
+    state.execute_batch(":sort city");
+    assert_eq!(column(&state, 1), ["Bonn", "Lyon", "Paris"]);
+    state.move_down();
+    state.move_down();
+    assert!(matches!(
+        state.filter_by_frequency(),
+        RenderingAction::Rerender
+    ));
+    assert_eq!(state.header, ["#", "country", "city"]);
+    assert_eq!(column(&state, 0), ["4"]);
+    assert_eq!(state.status_line(), "1 of 4 rows");
+    // Enter does nothing elsewhere
+    assert!(matches!(state.filter_by_frequency(), RenderingAction::None));
+}
+
+#[test]
+fn test_view_stack() {
+    let header: Vec<String> = ["#", "country", "city"].map(String::from).to_vec();
+    let rows = [("fr", "Paris"), ("de", "Bonn"), ("fr", "Lyon")]
+        .iter()
+        .enumerate()
+        .map(|(i, (country, city))| {
+            vec![(i + 1).to_string(), country.to_string(), city.to_string()]
+        })
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":dedup country");
+    state.move_right();
+    state.execute_batch(":freq");
+    state.status_message = None;
+    assert_eq!(state.breadcrumb(), "table › dedup country › freq country");
+    assert_eq!(
+        state.status_line(),
+        "table › dedup country › freq country | #"
+    );
+
+    // back to the dedup table and forward to the frequencies again, as they were
+    state.move_down();
+    state.go_back();
+    assert_eq!(state.breadcrumb(), "table › dedup country");
+    assert!(matches!(state.go_forward(), RenderingAction::Rerender));
+    assert_eq!(state.breadcrumb(), "table › dedup country › freq country");
+    assert_eq!(state.current_data_row(), Some(0));
+    assert!(matches!(state.go_forward(), RenderingAction::Status));
+    assert_eq!(state.status_line(), "No next view");
+
+    // Backspace removes filters first, then goes back; a new view drops the ones ahead
+    state.execute_batch(":back; :filter country = fr");
+    state.pop_filter_or_view();
+    assert_eq!(state.row_count(), 2);
+    state.pop_filter_or_view();
+    assert_eq!(state.breadcrumb(), "table");
+    state.execute_batch(":describe");
+    state.go_back();
+    state.go_forward();
+    assert_eq!(state.breadcrumb(), "table › describe");
+    state.go_back();
+    state.go_back();
+    assert_eq!(state.status_line(), "No previous view");
+}
+
+#[test]
+fn test_derived_settings() {
+    use table_viewer::theme::Theme;
+
+    let header: Vec<String> = ["#", "country"].map(String::from).to_vec();
+    let rows = ["fr", "de", "fr"]
+        .iter()
+        .enumerate()
+        .map(|(i, country)| vec![(i + 1).to_string(), country.to_string()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.theme = Theme::Light;
+    state.color = true;
+    state
+        .keys
+        .insert("x".to_string(), ":filter count > 1".to_string());
+    state.move_right();
+    state.execute_batch(":freq");
+    assert_eq!(state.header, ["#", "country", "count", "%"]);
+    assert_eq!(state.theme, Theme::Light);
+    assert!(state.color);
+    // the remapped key works in the derived table too
+    state.unbound_key("x");
+    assert_eq!(state.row_count(), 1);
+    state.close_derived();
+    assert_eq!(state.row_count(), 3);
+}
@@ -0,0 +1,176 @@
+use table_viewer::cell::ColType;
+use table_viewer::expr::{Expr, Value};
+
+fn eval(text: &str) -> Value {
+    let header: Vec<String> = ["#", "name", "unit price", "day"]
+        .map(String::from)
+        .to_vec();
+    let types = [ColType::Int, ColType::Str, ColType::Float, ColType::Date];
+    let row: Vec<String> = ["1", "Widget v12", "2.5", "2022-03-01"]
+        .map(String::from)
+        .to_vec();
+    Expr::parse(text, &header).unwrap().eval(&row, &types)
+}
+
+#[test]
+fn test_eval() {
+    assert_eq!(eval("lower(name)"), Value::Str("widget v12".into()));
+    assert_eq!(eval("len(name) + 1"), Value::Num(11.0));
+    assert_eq!(eval("num(name)"), Value::Num(12.0));
+    assert_eq!(eval("`unit price` * 2 - -1"), Value::Num(6.0));
+    assert_eq!(eval("1 + 2 * 3"), Value::Num(7.0));
+    assert_eq!(eval("(1 + 2) * 3"), Value::Num(9.0));
+    assert_eq!(eval("1 / 0"), Value::Null);
+    assert_eq!(eval("name + '!'"), Value::Str("Widget v12!".into()));
+}
+
+#[test]
+fn test_conditions() {
+    assert_eq!(eval("`unit price` > '2'"), Value::Bool(true));
+    assert_eq!(eval("day < '2022-01-01'"), Value::Bool(false));
+    assert_eq!(
+        eval("day >= \"2022-03-01\" and name ~ 'v1'"),
+        Value::Bool(true)
+    );
+    assert_eq!(eval("name = 'x' || not empty(name)"), Value::Bool(true));
+    assert_eq!(eval("null < 1"), Value::Bool(false));
+    assert_eq!(eval("null = null && !false"), Value::Bool(true));
+    assert_eq!(eval("starts_with(name, 'Wid')"), Value::Bool(true));
+}
+
+#[test]
+fn test_parse_errors() {
+    let header: Vec<String> = ["#", "name"].map(String::from).to_vec();
+    let error = |text: &str| Expr::parse(text, &header).unwrap_err();
+    assert_eq!(error("price > 1"), "Unknown column: price");
+    assert_eq!(error("foo(name)"), "Unknown function: foo");
+    assert_eq!(error("lower(name, 1)"), "lower expects 1 arguments");
+    assert_eq!(error("(name"), "Expected ')'");
+    assert_eq!(error("name name"), "Unexpected 'name'");
+    assert_eq!(error("'open"), "Missing closing '");
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_sort_key() {
+    use std::path::Path;
+    use table_viewer::csv::read_csv_from_file;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    // the price column contains text, so it sorts as text by default
+    let prices = |state: &TableState| -> Vec<String> {
+        state.rows.iter().map(|row| row[3].clone()).collect()
+    };
+    for _ in 0..3 {
+        state.move_right();
+    }
+    state.ascending(3);
+    assert_eq!(prices(&state), vec!["-1.25", "10", "2.5", "n/a"]);
+    state.execute_batch(":sortkey num(price)");
+    assert_eq!(state.status_line(), "Sorting price by num(price)");
+    state.ascending(3);
+    assert_eq!(prices(&state), vec!["-1.25", "2.5", "10", "n/a"]);
+    state.execute_batch(":sortkey");
+    assert!(state.sort_keys.is_empty());
+    state.ascending(3);
+    assert_eq!(prices(&state), vec!["-1.25", "10", "2.5", "n/a"]);
+}
+
+#[cfg(all(feature = "csv", feature = "collation"))]
+#[test]
+fn test_collation() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header = vec!["#".to_string(), "name".to_string()];
+    let rows = ["Zoe", "eve", "Émile", "Adam", "ábc"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| vec![(i + 1).to_string(), name.to_string()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    let names = |state: &TableState| -> Vec<String> {
+        state.rows.iter().map(|row| row[1].clone()).collect()
+    };
+    state.ascending(1);
+    assert_eq!(names(&state), vec!["Adam", "Zoe", "eve", "Émile", "ábc"]);
+    state.execute_batch(":collation on");
+    assert_eq!(state.status_line(), "Sorting text by collation");
+    state.ascending(1);
+    assert_eq!(names(&state), vec!["ábc", "Adam", "Émile", "eve", "Zoe"]);
+    state.execute_batch(":sortkey upper(name)");
+    state.descending(1);
+    assert_eq!(names(&state), vec!["Zoe", "eve", "Émile", "Adam", "ábc"]);
+}
+
+#[test]
+fn test_table_expressions() {
+    let header: Vec<String> = ["#", "id", "price"].map(String::from).to_vec();
+    let types = [ColType::Int, ColType::Int, ColType::Float];
+    let rows: Vec<Vec<String>> = [["1", "7", "2.5"], ["2", "8", ""], ["3", "8", "-1"]]
+        .iter()
+        .map(|row| row.map(String::from).to_vec())
+        .collect();
+    let eval = |text: &str| {
+        Expr::parse_table(text, &header)
+            .unwrap()
+            .eval_table(&rows, &types, 2)
+    };
+    assert_eq!(eval("rows"), Value::Num(3.0));
+    assert_eq!(eval("rows > 0 and columns = 2"), Value::Bool(true));
+    assert_eq!(eval("no_nulls(id)"), Value::Bool(true));
+    assert_eq!(eval("no_nulls(price)"), Value::Bool(false));
+    assert_eq!(eval("nulls(price) / rows"), Value::Num(1.0 / 3.0));
+    assert_eq!(eval("unique(id)"), Value::Bool(false));
+    assert_eq!(eval("distinct(id)"), Value::Num(2.0));
+    assert_eq!(eval("count(price < 0)"), Value::Num(1.0));
+    assert_eq!(
+        eval("all(id > 5) and not any(empty(id))"),
+        Value::Bool(true)
+    );
+    let error = |text: &str| Expr::parse_table(text, &header).unwrap_err();
+    assert_eq!(
+        error("id > 0"),
+        "Column id outside of an aggregate, e.g. nulls(id)"
+    );
+    assert_eq!(error("nulls(id, price)"), "nulls expects 1 arguments");
+    assert_eq!(
+        Expr::parse("rows > 0", &header).unwrap_err(),
+        "Unknown column: rows"
+    );
+}
+
+#[test]
+fn test_jump_if() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "name", "amount"].map(String::from).to_vec();
+    let rows = [("a", "500"), ("b", "20000"), ("c", "70"), ("d", "15000")]
+        .iter()
+        .enumerate()
+        .map(|(i, (name, amount))| vec![(i + 1).to_string(), name.to_string(), amount.to_string()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":jumpif amount > 10000");
+    assert_eq!(state.current_data_row(), Some(1));
+    state.execute_batch(":jumpif amount > 10000");
+    assert_eq!(state.current_data_row(), Some(3));
+    state.execute_batch(":jumpif amount > 10000");
+    assert_eq!(state.current_data_row(), Some(1));
+    assert_eq!(state.status_line(), "Jump wrapped around to the top");
+    // the filters stay as they are
+    assert!(state.filters.is_empty());
+    assert_eq!(state.row_count(), 4);
+
+    state.execute_batch(":jumpif amount < 10 and name = 'a'");
+    assert_eq!(
+        state.status_line(),
+        "No row with amount < 10 and name = 'a'"
+    );
+    assert_eq!(state.current_data_row(), Some(1));
+    state.execute_batch(":jumpif amount >");
+    assert_eq!(state.status_line(), "Unexpected end of expression");
+    assert_eq!(state.current_data_row(), Some(1));
+}
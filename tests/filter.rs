@@ -0,0 +1,381 @@
+#![cfg(feature = "csv")]
+use std::fs;
+use std::path::Path;
+use table_viewer::csv::read_csv_from_file;
+use table_viewer::filter::{self, Filter, Op};
+use table_viewer::renderer::RenderingAction;
+use table_viewer::state::{CharCoord, TableState};
+
+fn typed_state() -> TableState {
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    TableState::new(header, rows, CharCoord { x: 80, y: 10 })
+}
+
+fn names(state: &TableState) -> Vec<String> {
+    (0..state.row_count())
+        .map(|i| state.view_row(i)[1].clone())
+        .collect()
+}
+
+#[test]
+fn test_parse_filter() {
+    let header: Vec<String> = ["#", "unit price", "name"].map(String::from).to_vec();
+    let filter = Filter::parse("unit price >= 10", &header).unwrap();
+    assert_eq!(
+        (filter.col, filter.op, filter.value.as_str()),
+        (1, Op::Ge, "10")
+    );
+    let filter = Filter::parse("name != 'a b'", &header).unwrap();
+    assert_eq!(
+        (filter.col, filter.op, filter.value.as_str()),
+        (2, Op::Ne, "a b")
+    );
+    assert_eq!(filter.describe(&header), "name != a b");
+    let filter = Filter::parse("name ~ unit price", &header).unwrap();
+    assert_eq!((filter.col, filter.other), (2, Some(1)));
+    let filter = Filter::parse("name = 'unit price'", &header).unwrap();
+    assert_eq!(filter.other, None);
+    // operators and escaped quotes inside quotes
+    let header: Vec<String> = ["#", "a<b", "name"].map(String::from).to_vec();
+    let filter = Filter::parse("'a<b' = 'it\\'s'", &header).unwrap();
+    assert_eq!(
+        (filter.col, filter.op, filter.value.as_str()),
+        (1, Op::Eq, "it's")
+    );
+    assert!(Filter::parse("price > 1", &header).is_err());
+    assert!(Filter::parse("name", &header).is_err());
+    assert_eq!(Op::HasTag.to_string(), "has");
+    assert_eq!(Op::NotContains.to_string(), "!~");
+}
+
+#[test]
+fn test_typed_filters() {
+    let mut state = typed_state();
+    state.execute_batch(":filter count >= 2");
+    assert_eq!(names(&state), vec!["b", "a"]);
+    // filters stack and survive sorting
+    state.execute_batch(":filter flag = false; :filter name ~ a");
+    assert_eq!(names(&state), vec!["a"]);
+    assert_eq!(state.status_line(), "1 of 4 rows");
+    // removing all filters needs to be confirmed
+    state.execute_batch(":filter");
+    assert_eq!(
+        state.prompt.as_ref().unwrap().question,
+        "Remove all 3 filters?"
+    );
+    state.answer_prompt(false);
+    assert_eq!(state.status_line(), "Cancelled");
+    assert_eq!(names(&state), vec!["a"]);
+    state.execute_batch(":filter");
+    state.answer_prompt(true);
+    state.execute_batch(":filter count != 10");
+    state.ascending(2);
+    assert_eq!(names(&state), vec!["d", "a", "c"]);
+    state.execute_batch(":filter day < 2022-02-01");
+    assert_eq!(names(&state), vec!["a", "c"]);
+}
+
+#[test]
+fn test_column_filters() {
+    let header = ["#", "name", "expected", "actual"]
+        .map(String::from)
+        .to_vec();
+    let rows = [
+        ["1", "a", "2", "2.0"],
+        ["2", "b", "3", "2.5"],
+        ["3", "c", "", "1"],
+        ["4", "d", "10", "9"],
+    ]
+    .iter()
+    .map(|row| row.map(String::from).to_vec())
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    // an int and a float column compare by value
+    state.execute_batch(":filter expected != actual");
+    assert_eq!(names(&state), vec!["b", "c", "d"]);
+    state.execute_batch(":filter expected > actual");
+    assert_eq!(names(&state), vec!["b", "d"]);
+    state.execute_batch(":filter");
+    state.answer_prompt(true);
+    // every text contains the empty one
+    state.execute_batch(":filter actual ~ expected");
+    assert_eq!(names(&state), vec!["a", "c"]);
+}
+
+#[test]
+fn test_write_rejected_rows() {
+    let mut state = typed_state();
+    let path = std::env::temp_dir().join("table_viewer_rejected.csv");
+    let command = format!(":filter count >= 2; :write! {}", path.display());
+    state.execute_batch(&command);
+    assert_eq!(
+        state.status_line(),
+        format!("Wrote 2 rejected rows to {}", path.display())
+    );
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "name,count,price,day,flag\nc,,-1.25,2022-01-15T08:30:00,TRUE\nd,1,n/a,,false\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_write_view() {
+    let mut state = typed_state();
+    let path = std::env::temp_dir().join("table_viewer_view.tsv");
+    // flag is hidden, price moved before name
+    state.move_end_of_line();
+    state.hide_column();
+    state.order = vec![0, 3, 1, 2, 4, 5];
+    let command = format!(":filter count >= 2; :sort -name; :write {}", path.display());
+    state.execute_batch(&command);
+    assert_eq!(
+        state.status_line(),
+        format!("Wrote 2 rows and 4 columns to {}", path.display())
+    );
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "price\tname\tcount\tday\n2.5\tb\t10\t2022-03-01\n10\ta\t2\t2021-12-31\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_search_case() {
+    let mut state = typed_state();
+    for _ in 0..5 {
+        state.move_right();
+    }
+    state.search("TRUE");
+    assert_eq!(state.current_row(), 3);
+    state.execute_batch(":case insensitive");
+    assert_eq!(state.status_line(), "Searches are case-insensitive");
+    state.move_home();
+    state.search("TRUE");
+    assert_eq!(state.current_row(), 1);
+    state.repeat_search(false);
+    assert_eq!(state.current_row(), 3);
+    // smart case only ignores case for lowercase patterns
+    state.execute_batch(":case smart");
+    state.search("True");
+    assert_eq!(state.current_row(), 3);
+    state.execute_batch("/true; :matches");
+    assert_eq!(
+        state.pane.as_ref().unwrap().title,
+        "2 matches for 'true' in column 'flag'"
+    );
+}
+
+#[test]
+fn test_search_escapes() {
+    let header: Vec<String> = ["#", "path"].map(String::from).to_vec();
+    let rows = ["a;b", "C:\\temp", "x\\;y"]
+        .iter()
+        .enumerate()
+        .map(|(i, path)| vec![(i + 1).to_string(), path.to_string()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.move_right();
+    state.execute_batch("/\\;; :matches");
+    assert_eq!(
+        state.pane.as_ref().unwrap().title,
+        "2 matches for ';' in column 'path'"
+    );
+    state.close_pane();
+    state.search("\\\\t");
+    assert_eq!(state.current_row(), 2);
+    // patterns taken as typed
+    state.execute_batch(":literal");
+    assert_eq!(state.status_line(), "Search patterns are taken as typed");
+    state.search("x\\;");
+    assert_eq!(state.current_row(), 3);
+    state.search("\\t");
+    assert_eq!(state.current_row(), 2);
+}
+
+#[test]
+fn test_backward_search() {
+    let mut state = typed_state();
+    for _ in 0..5 {
+        state.move_right();
+    }
+    state.execute_batch(":case insensitive; ?true");
+    assert_eq!(state.current_row(), 3);
+    assert_eq!(state.status_line(), "Search wrapped around to the bottom");
+    // n keeps the direction, N reverses it without changing the last search
+    state.repeat_search(false);
+    assert_eq!(state.current_row(), 1);
+    state.repeat_search(false);
+    assert_eq!(state.current_row(), 3);
+    state.repeat_search(true);
+    assert_eq!(state.current_row(), 1);
+    assert_eq!(state.status_line(), "Search wrapped around to the top");
+    assert!(state.last_search.as_ref().unwrap().backward);
+
+    state.search("maybe");
+    assert_eq!(state.current_row(), 1);
+    assert_eq!(state.status_line(), "No match for 'maybe' in flag");
+    state.start_search();
+    state.command_buffer = "?fa".chars().collect();
+    state.preview_search();
+    assert_eq!(state.current_row(), 4);
+}
+
+#[test]
+fn test_incremental_search() {
+    let mut state = typed_state();
+    state.move_down();
+    state.move_right();
+    let type_search = |state: &mut TableState, text: &str| {
+        state.command_buffer = text.chars().collect();
+        state.preview_search();
+    };
+    state.start_search();
+    type_search(&mut state, "/");
+    assert_eq!(state.current_row(), 1);
+    type_search(&mut state, "/c");
+    assert_eq!(state.current_row(), 3);
+    type_search(&mut state, "/d");
+    assert_eq!(state.current_row(), 4);
+    // cancelling returns to where the search started
+    state.cancel_search();
+    assert_eq!(state.current_row(), 1);
+    assert!(state.last_search.is_none());
+
+    state.start_search();
+    type_search(&mut state, "/c");
+    state.execute_command();
+    assert_eq!(state.current_row(), 3);
+    assert_eq!(state.last_search.as_ref().unwrap().pattern, "c");
+    assert!(matches!(state.cancel_search(), RenderingAction::None));
+
+    // pasted text is inserted at once, line breaks become spaces
+    state.start_search();
+    state.command_buffer = vec!['/'];
+    state.paste("d\n");
+    assert_eq!(state.current_row(), 4);
+    state.cancel_search();
+    state.command_buffer = vec![':'];
+    state.paste("filter a\r\n= 1\n");
+    assert_eq!(
+        state.command_buffer.iter().collect::<String>(),
+        ":filter a = 1"
+    );
+}
+
+#[test]
+fn test_column_filter_key() {
+    let mut state = typed_state();
+    state.move_right();
+    state.move_right();
+    state.start_column_filter();
+    state.command_buffer.extend("> 1".chars());
+    state.execute_command();
+    assert_eq!(names(&state), vec!["b", "a"]);
+    assert_eq!(state.filters[0].describe(&state.header), "count > 1");
+}
+
+#[test]
+fn test_filter_builder() {
+    let mut state = typed_state();
+    state.move_right();
+    state.move_right();
+    assert!(matches!(
+        state.start_filter_builder(),
+        RenderingAction::Pane
+    ));
+    assert_eq!(state.filter_builder.as_ref().unwrap().col(), 2);
+    // `>` is the fourth operator, the value field follows
+    state.builder_move_field(true);
+    for _ in 0..3 {
+        state.builder_choose(true);
+    }
+    state.builder_move_field(true);
+    state.builder_insert('1');
+    let builder = state.filter_builder.as_ref().unwrap();
+    assert_eq!(builder.suggestion(), Some("0"));
+    assert_eq!(builder.expression(&state.header), "count > 1");
+    state.apply_filter_builder();
+    assert!(state.filter_builder.is_none());
+    assert_eq!(names(&state), vec!["b", "a"]);
+    assert_eq!(state.status_line(), ":filter count > 1: 2 of 4 rows");
+    assert_eq!(state.command_history, [":filter count > 1"]);
+
+    // `empty` takes no value and matches missing ones
+    state.pop_filter();
+    state.start_filter_builder();
+    state.builder_move_field(true);
+    state.builder_choose(false);
+    assert!(matches!(
+        state.builder_move_field(true),
+        RenderingAction::None
+    ));
+    state.apply_filter_builder();
+    assert_eq!(names(&state), vec!["c"]);
+    assert_eq!(state.filters[0].describe(&state.header), "count = ");
+
+    // names and values are quoted where the filter syntax needs it
+    let header = &state.header;
+    assert_eq!(filter::quote("a<b", &[]), "'a<b'");
+    assert_eq!(filter::quote("it's; ok", &[]), "'it\\'s\\; ok'");
+    assert_eq!(filter::quote("price", header), "'price'");
+    assert_eq!(filter::quote("apple pie", header), "apple pie");
+    let filter = Filter::parse("name = 'it\\'s\\; ok'", header).unwrap();
+    assert_eq!(filter.value, "it's; ok");
+}
+
+#[test]
+fn test_quick_filter() {
+    let mut state = typed_state();
+    for _ in 0..5 {
+        state.move_right();
+    }
+    for _ in 0..4 {
+        state.move_down();
+    }
+    state.filter_value();
+    assert_eq!(names(&state), vec!["a", "d"]);
+    assert_eq!(state.current_data_row(), Some(1));
+    for _ in 0..3 {
+        state.move_left();
+    }
+    state.filter_value();
+    assert_eq!(names(&state), vec!["d"]);
+    assert_eq!(state.status_line(), "1 of 4 rows");
+
+    state.pop_filter();
+    assert_eq!(names(&state), vec!["a", "d"]);
+    assert_eq!(state.status_line(), "Removed count = 1: 2 of 4 rows");
+    state.execute_batch(":clearfilter");
+    assert_eq!(names(&state).len(), 4);
+    state.pop_filter();
+    assert_eq!(state.status_line(), "No filter active");
+}
+
+#[test]
+fn test_search_value() {
+    let mut state = typed_state();
+    assert!(matches!(state.search_value(false), RenderingAction::Status));
+    for _ in 0..5 {
+        state.move_right();
+    }
+    state.move_down();
+    state.move_down();
+    state.search_value(false);
+    assert_eq!(state.status_line(), "2 rows with flag = 'false'");
+    assert_eq!(state.current_row(), 4);
+    state.search_value(false);
+    assert_eq!(state.current_row(), 2);
+    // backward, wrapping around at the start; repeating keeps the direction
+    state.search_value(true);
+    assert_eq!(state.current_row(), 4);
+    state.repeat_search(false);
+    assert_eq!(state.current_row(), 2);
+
+    state.execute_batch(":case insensitive");
+    state.move_up();
+    state.search_value(false);
+    assert_eq!(state.status_line(), "2 rows with flag = 'true'");
+    assert_eq!(state.current_row(), 3);
+}
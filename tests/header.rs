@@ -0,0 +1,98 @@
+use table_viewer::cell::ColType;
+use table_viewer::state::{CharCoord, TableState};
+
+fn state_fixture(rows: &[[&str; 2]]) -> TableState {
+    let header: Vec<String> = ["#", "name", "price"].map(String::from).to_vec();
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(i, [name, price])| vec![(i + 1).to_string(), name.to_string(), price.to_string()])
+        .collect();
+    TableState::new(header, rows, CharCoord { x: 80, y: 10 })
+}
+
+#[test]
+fn test_promote_header() {
+    let mut state = state_fixture(&[["str", "float"], ["apple", "1.5"], ["pear", "2"]]);
+    assert_eq!(
+        state.status_line(),
+        "The first row looks like a header, :promote-header makes it one"
+    );
+    state.execute_batch(":promote-header");
+    assert_eq!(
+        state.status_line(),
+        "Promoted the first row to the header, 2 rows left"
+    );
+    assert_eq!(state.header, ["#", "str", "float"]);
+    assert_eq!(state.rows[0], ["1", "apple", "1.5"]);
+    assert_eq!(state.types[2], ColType::Float);
+
+    state.execute_batch(":demote-header");
+    assert_eq!(state.header, ["#", "col_1", "col_2"]);
+    assert_eq!(state.rows[0], ["1", "str", "float"]);
+    assert_eq!(state.rows[2], ["3", "pear", "2"]);
+    assert_eq!(state.types[2], ColType::Str);
+}
+
+#[test]
+fn test_header_hint() {
+    // missing values are no header
+    let state = state_fixture(&[["apple", ""], ["pear", "2"]]);
+    assert!(state.status_message.is_none());
+    let state = state_fixture(&[["apple", "1"], ["pear", "2"]]);
+    assert!(state.status_message.is_none());
+}
+
+#[test]
+fn test_skip_header_rows() {
+    let mut state = state_fixture(&[
+        ["apple", "1.5"],
+        ["name", "price"],
+        ["pear", "2"],
+        ["name", "price"],
+    ]);
+    assert_eq!(state.status_line(), "Skipped 2 rows repeating the header");
+    assert_eq!(state.rows.len(), 2);
+    assert_eq!(state.rows[1], ["3", "pear", "2"]);
+    assert_eq!(state.types[2], ColType::Float);
+
+    // empty rows are kept under a header of empty names
+    let header = vec!["#".to_string(), String::new()];
+    let rows = vec![vec!["1".to_string(), String::new()]];
+    state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    assert_eq!(state.rows.len(), 1);
+}
+
+#[test]
+fn test_group_header() {
+    let header: Vec<String> = ["#", "", "Q1", "", "Q2", "Q2_2", "Q2_2"]
+        .map(String::from)
+        .to_vec();
+    let rows = [
+        ["id", "sales", "costs", "sales", "costs", "x_2"],
+        ["7", "10", "4", "12", "5", ""],
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, row)| {
+        let mut row: Vec<String> = row.map(String::from).to_vec();
+        row.insert(0, (i + 1).to_string());
+        row
+    })
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":promote-header groups");
+    // blank and repeated names continue the group before, names are taken as they were loaded
+    assert_eq!(state.groups, ["", "", "Q1", "Q1", "Q2", "Q2_2", "Q2_2"]);
+    assert_eq!(state.header[1..3], ["id", "sales"]);
+    assert_eq!(state.rows[0][..2], ["1", "7"]);
+    assert!(state.has_groups());
+    assert_eq!(state.header_height(), 2);
+
+    // other headers have no groups
+    state.execute_batch(":promote-header");
+    assert!(state.groups.is_empty());
+    state.execute_batch(":promote-header groups");
+    assert_eq!(state.status_line(), "No row to promote");
+    assert!(state.groups.is_empty());
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use table_viewer::history::History;
+
+#[test]
+fn test_history() {
+    let dir = std::env::temp_dir().join("table_viewer_history");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("state").join("history");
+    let (a, b) = (dir.join("a.csv"), dir.join("b.csv"));
+    fs::write(&a, "x\n1\n").unwrap();
+
+    let mut history = History::load(&path).unwrap();
+    assert!(history.entries.is_empty());
+    history.record(&a);
+    history.record(&b);
+    history.record(&a);
+    for i in 0..60 {
+        history.record(&dir.join(format!("{}.csv", i)));
+    }
+    history.record(&a);
+    assert_eq!(history.entries.len(), 50);
+    history.save().unwrap();
+
+    let history = History::load(&path).unwrap();
+    assert_eq!(history.entries.len(), 50);
+    assert_eq!(history.entries[0].0, fs::canonicalize(&a).unwrap());
+    assert_eq!(history.entries[1].0, dir.join("59.csv"));
+    // files that no longer exist are not offered
+    assert_eq!(history.files(), vec![fs::canonicalize(&a).unwrap()]);
+
+    fs::write(&path, "yesterday\ta.csv\n").unwrap();
+    assert_eq!(
+        History::load(&path).unwrap_err().to_string(),
+        format!("{}:1: invalid entry", path.display())
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_recent() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let dir = std::env::temp_dir().join("table_viewer_recent");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let (a, b) = (dir.join("a.csv"), dir.join("b.csv"));
+    fs::write(&a, "x\n1\n").unwrap();
+    fs::write(&b, "y\n2\n").unwrap();
+
+    let mut state = TableState::new(
+        vec!["#".to_string()],
+        Vec::new(),
+        CharCoord { x: 40, y: 20 },
+    );
+    state.history = History::load(&dir.join("history")).unwrap();
+    state.history.record(&a);
+    state.history.record(&b);
+    state.execute_batch(":recent");
+    assert_eq!(state.picker.as_ref().unwrap().title, "Recent files");
+    state.pane_down();
+    state.pane_select();
+    assert_eq!(state.header, vec!["#", "x"]);
+    let history = History::load(&dir.join("history")).unwrap();
+    assert_eq!(history.files()[0], fs::canonicalize(&a).unwrap());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,66 @@
+use table_viewer::input::{inputs, key_name, Input, Modifiers};
+use termion::event::Key;
+
+#[test]
+fn test_bracketed_paste() {
+    let bytes: &[u8] = b"/\x1b[200~a:b\nq\x1b[201~\nq";
+    assert_eq!(
+        inputs(bytes).collect::<Vec<_>>(),
+        vec![
+            Input::Key(Key::Char('/')),
+            Input::Paste("a:b\nq".to_string()),
+            Input::Key(Key::Char('\n')),
+            Input::Key(Key::Char('q')),
+        ]
+    );
+}
+
+#[test]
+fn test_modified_keys() {
+    let ctrl = Modifiers {
+        ctrl: true,
+        ..Default::default()
+    };
+    let shift = Modifiers {
+        shift: true,
+        ..Default::default()
+    };
+    // xterm sequences, kitty sequences and an unknown one, which is skipped
+    let bytes: &[u8] =
+        b"\x1b[1;5C\x1b[1;2A\x1b[6;5~\x1b[99;5u\x1b[27u\x1b[120;3u\x1b[9;2u\x1b[97;6u\x1b[1;5Qx";
+    assert_eq!(
+        inputs(bytes).collect::<Vec<_>>(),
+        vec![
+            Input::Modified(ctrl, Key::Right),
+            Input::Modified(shift, Key::Up),
+            Input::Modified(ctrl, Key::PageDown),
+            Input::Key(Key::Ctrl('c')),
+            Input::Key(Key::Esc),
+            Input::Key(Key::Alt('x')),
+            Input::Key(Key::BackTab),
+            Input::Modified(
+                Modifiers {
+                    shift: true,
+                    ctrl: true,
+                    ..Default::default()
+                },
+                Key::Char('a')
+            ),
+            Input::Key(Key::Char('x')),
+        ]
+    );
+}
+
+#[test]
+fn test_key_name() {
+    let shift = Modifiers {
+        shift: true,
+        ..Default::default()
+    };
+    let none = Modifiers::default();
+    assert_eq!(key_name(none, Key::Char('x')).unwrap(), "x");
+    assert_eq!(key_name(none, Key::Ctrl('x')).unwrap(), "ctrl-x");
+    assert_eq!(key_name(shift, Key::Alt('x')).unwrap(), "alt-shift-x");
+    assert_eq!(key_name(shift, Key::PageDown).unwrap(), "shift-pagedown");
+    assert_eq!(key_name(none, Key::Null), None);
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "jsonl")]
+use std::fs::File;
+use std::path::Path;
+use table_viewer::jsonl::read_jsonl;
+use table_viewer::registry::Registry;
+
+#[test]
+fn test_read_jsonl() {
+    let (header, rows) = read_jsonl(File::open("tests/resources/records.jsonl").unwrap()).unwrap();
+    assert_eq!(header, ["#", "id", "name", "tags", "ok", "extra"]);
+    assert_eq!(
+        rows,
+        [
+            ["1", "1", "a", "[\"x\",\"y\"]", "", ""],
+            ["2", "2", "b", "", "true", ""],
+            ["3", "3", "", "", "", "{\"k\":1.5}"],
+        ]
+    );
+    let error = read_jsonl("{\"id\": 1}\n[1, 2]\n".as_bytes()).unwrap_err();
+    assert_eq!(error.to_string(), "Line 2: not a JSON object");
+}
+
+#[test]
+fn test_detect_jsonl() {
+    let registry = Registry::default();
+    for file in ["events.jsonl", "events.NDJSON"] {
+        let source = registry.source_for_path(Path::new(file)).unwrap();
+        assert_eq!(source.name(), "jsonl");
+    }
+    assert!(registry.source("jsonl").is_some());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "csv")]
+use std::fs::OpenOptions;
+use std::io::Write;
+use table_viewer::cell::ColType;
+use table_viewer::lazy::{IndexedCsv, TableDataSource};
+use table_viewer::renderer::TerminalTableRenderer;
+use table_viewer::state::CharCoord;
+use table_viewer::viewer::{Event, TableViewer};
+use termion::event::Key;
+
+fn write_table(name: &str, rows: usize) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("table_viewer_lazy");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let mut content = String::from("id,name\n");
+    for i in 0..rows {
+        content.push_str(&format!("{},\"row {}\"\n", i * 2, i));
+    }
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn keys(text: &str) -> Vec<Event> {
+    text.chars().map(|c| Event::Key(Key::Char(c))).collect()
+}
+
+#[test]
+fn test_indexed_csv() {
+    let path = write_table("indexed.csv", 3000);
+    let mut source = IndexedCsv::open(&path, b',', b'"').unwrap();
+    assert_eq!(source.header(), ["#", "id", "name"]);
+    assert_eq!(source.row_count(), None);
+    // across the first position in the index
+    let rows = source.rows(1022, 3).unwrap();
+    assert_eq!(rows[0], ["1023", "2044", "row 1022"]);
+    assert_eq!(rows[2], ["1025", "2048", "row 1024"]);
+    assert_eq!(source.rows(10, 1).unwrap()[0], ["11", "20", "row 10"]);
+    assert_eq!(source.rows(2998, 5).unwrap().len(), 2);
+    assert_eq!(source.row_count(), Some(3000));
+    assert!(source.rows(3000, 5).unwrap().is_empty());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_pager() {
+    let path = write_table("pager.csv", 3000);
+    // a last row with a text id and a long name
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    writeln!(file, "n/a,a much longer name").unwrap();
+    let source = IndexedCsv::open(&path, b',', b'"').unwrap();
+    let mut viewer = TableViewer::paged(TerminalTableRenderer {}, Box::new(source)).unwrap();
+    let mut run = |events: Vec<Event>| {
+        viewer.run_events(events, &mut Vec::new()).unwrap();
+        let state = viewer.state();
+        (
+            state.status_line(),
+            state.rows[0][0].clone(),
+            state.types.clone(),
+            state.widths(),
+        )
+    };
+    // ten data rows on screen
+    let (status, first, types, widths) = run(vec![Event::Resize(CharCoord { x: 40, y: 12 })]);
+    assert_eq!(
+        (status.as_str(), first.as_str()),
+        ("row 0/? (read on demand)", "1")
+    );
+    assert_eq!(types[1], ColType::Int);
+    let (status, first, ..) = run(keys("jjjjj"));
+    assert_eq!(
+        (status.as_str(), first.as_str()),
+        ("row 5/? (read on demand)", "1")
+    );
+    let (status, first, ..) = run((0..250).map(|_| Event::Key(Key::PageDown)).collect());
+    assert_eq!(status, "row 2.505/? (read on demand)");
+    assert_eq!(first, "2496");
+    // the types of the first page stay, columns only grow
+    let (status, first, last_types, last_widths) = run(keys("G"));
+    assert_eq!(status, "row 3.001/3.001 (read on demand)");
+    assert_eq!(first, "2992");
+    assert_eq!(last_types, types);
+    assert!(last_widths[2] > widths[2]);
+    let (status, first, _, first_widths) = run(keys("gg"));
+    assert_eq!(
+        (status.as_str(), first.as_str()),
+        ("row 0/3.001 (read on demand)", "1")
+    );
+    assert_eq!(first_widths, last_widths);
+    let (status, ..) = run(keys("/row"));
+    assert_eq!(status, "Not available while reading on demand");
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "csv")]
+use std::path::Path;
+use table_viewer::csv::read_csv_from_file;
+use table_viewer::lint::lint;
+
+#[test]
+fn test_lint() {
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/lint_table.csv"), b',', b'"').unwrap();
+    let findings: Vec<String> = lint(&header, &rows)
+        .into_iter()
+        .map(|finding| finding.text)
+        .collect();
+    assert_eq!(
+        findings,
+        vec![
+            "12: code: 'ab-012' unusual characters",
+            "15: code: 'AB-0150' length 7, usually 6",
+            "7: amount: '7.x' not numeric",
+            "15: name: '15' numeric in text column",
+        ]
+    );
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "log")]
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use table_viewer::log::{parse_option, subscriber};
+use table_viewer::renderer::TerminalTableRenderer;
+use table_viewer::viewer::{Event, TableViewer};
+use termion::event::Key;
+use tracing::Level;
+
+// Log lines shared with the subscriber.
+#[derive(Clone, Default)]
+struct Lines(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_option() {
+    assert_eq!(
+        parse_option("trace:/tmp/tv.log"),
+        (Level::TRACE, "/tmp/tv.log")
+    );
+    assert_eq!(parse_option("/tmp/tv.log"), (Level::DEBUG, "/tmp/tv.log"));
+    // colons in the path without a level
+    assert_eq!(parse_option("/tmp/a:b.log"), (Level::DEBUG, "/tmp/a:b.log"));
+    assert_eq!(
+        parse_option("info:/tmp/a:b.log"),
+        (Level::INFO, "/tmp/a:b.log")
+    );
+}
+
+#[test]
+fn test_logged_events() {
+    let header: Vec<String> = ["#", "name"].map(String::from).to_vec();
+    let rows = vec![vec!["1".to_string(), "pear".to_string()]];
+    let mut viewer = TableViewer::new(TerminalTableRenderer {}, header, rows);
+    let events = ":sort name\n".chars().map(|c| Event::Key(Key::Char(c)));
+    let lines = Lines::default();
+    let writer = lines.clone();
+    tracing::subscriber::with_default(subscriber(Level::DEBUG, move || writer.clone()), || {
+        viewer.run_events(events, &mut Vec::new()).unwrap();
+    });
+    let log = String::from_utf8(lines.0.lock().unwrap().clone()).unwrap();
+    assert!(log.contains("DEBUG"));
+    assert!(log.contains("key=Char(':') mode=Normal"));
+    assert!(log.contains("command command=\":sort name\""));
+    assert!(log.contains("mode from=Command to=Normal"));
+    // no events below the level
+    assert!(!log.contains("TRACE"));
+}
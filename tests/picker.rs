@@ -0,0 +1,42 @@
+#![cfg(feature = "csv")]
+use std::fs;
+use table_viewer::picker::FilePicker;
+use table_viewer::renderer::{RenderingAction, TableRenderer, TerminalTableRenderer};
+use table_viewer::state::{CharCoord, TableState};
+
+#[test]
+fn test_file_picker() {
+    let dir = std::env::temp_dir().join("table_viewer_picker");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.csv"), "x,y\n1,first\n2,second\n").unwrap();
+    fs::write(dir.join("b.tsv"), "name\tvalue\nfoo\t42\n").unwrap();
+    fs::write(dir.join("notes.md"), "# not a table\n").unwrap();
+
+    let mut picker = FilePicker::new(&dir).unwrap();
+    assert_eq!(picker.files, vec![dir.join("a.csv"), dir.join("b.tsv")]);
+    let size = CharCoord { x: 30, y: 12 };
+    let lines = picker.lines(size);
+    // title, four file lines, separator, preview header and rows
+    assert_eq!(lines.len(), 9);
+    assert!(lines[1].contains("a.csv"));
+    assert!(lines.iter().any(|line| line.contains("first")));
+    picker.move_down(FilePicker::list_height(size.y));
+    assert!(picker.lines(size).iter().any(|line| line.contains("foo")));
+
+    // start without table, like the binary without arguments
+    let mut state = TableState::new(vec!["#".to_string()], Vec::new(), size);
+    assert!(TerminalTableRenderer {}
+        .render(&state, &RenderingAction::Rerender)
+        .is_some());
+    let action = state.execute_batch(&format!(":open {}", dir.display()));
+    assert!(matches!(action, RenderingAction::Pane));
+    state.pane_down();
+    state.pane_select();
+    assert!(state.picker.is_none());
+    assert_eq!(state.header, vec!["#", "name", "value"]);
+    assert_eq!(state.delimited, Some((b'\t', b'"')));
+
+    state.execute_batch(":open /nonexistent");
+    assert!(state.status_line().starts_with("Cannot list /nonexistent"));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,40 @@
+use table_viewer::plot::{braille, scatter};
+use table_viewer::state::{CharCoord, TableState};
+
+#[test]
+fn test_braille() {
+    assert_eq!(braille(&[(0.0, 0.0), (1.0, 1.0)], 1, 1), ["⡈"]);
+    assert_eq!(
+        braille(&[(0.0, 0.0), (1.0, 2.0), (3.0, 4.0)], 2, 2),
+        ["⢀⠈", "⡀ "]
+    );
+    // all points equal
+    assert_eq!(braille(&[(5.0, 5.0)], 1, 1), ["⠐"]);
+}
+
+#[test]
+fn test_scatter() {
+    assert_eq!(
+        scatter(&[(1.0, -2.5), (10.0, 3.0)], 9, 4),
+        ["   3│   ⠈", "-2.5│⡀   ", "    └────", "     1 10"]
+    );
+}
+
+#[test]
+fn test_plot_command() {
+    let header: Vec<String> = ["#", "x", "y"].map(String::from).to_vec();
+    let rows: Vec<Vec<String>> = [["1", "1", "2"], ["2", "2", "n/a"], ["3", "3", "4"]]
+        .iter()
+        .map(|row| row.map(String::from).to_vec())
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 20, y: 8 });
+    state.execute_batch(":plot x, y");
+    let pane = state.pane.take().unwrap();
+    assert_eq!(pane.title, "y by x: 2 points");
+    assert_eq!(pane.entries.len(), 6);
+    assert_eq!(pane.entries[5].text, "  1                3");
+    state.execute_batch(":plot x");
+    assert_eq!(state.status_line(), "Usage: :plot <x column>,<y column>");
+    state.execute_batch(":plot x,z");
+    assert_eq!(state.status_line(), "Unknown column: z");
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "config")]
+use std::path::Path;
+use table_viewer::cell::ColType;
+use table_viewer::config::{glob_match, Config};
+use table_viewer::renderer::TerminalTableRenderer;
+use table_viewer::state::{CharCoord, TableState};
+use table_viewer::viewer::{Event, TableViewer};
+use termion::event::Key;
+
+fn config() -> Config {
+    Config::from_file(Path::new("tests/resources/profiles.toml")).unwrap()
+}
+
+fn table() -> (Vec<String>, Vec<Vec<String>>) {
+    let header: Vec<String> = ["#", "host", "path", "status"].map(String::from).to_vec();
+    let rows = vec![
+        ["1", "a", "/", "200"].map(String::from).to_vec(),
+        ["2", "b", "/x", "503"].map(String::from).to_vec(),
+    ];
+    (header, rows)
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*.log", "app.log"));
+    assert!(glob_match("*.log", ".log"));
+    assert!(!glob_match("*.log", "app.log.gz"));
+    assert!(glob_match("access-??.csv", "access-01.csv"));
+    assert!(!glob_match("access-??.csv", "access-1.csv"));
+    assert!(glob_match("a*b*c", "aXbYbZc"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("a", ""));
+}
+
+#[test]
+fn test_select_profile() {
+    let config = config();
+    let profile = |name, file: &str| {
+        config
+            .profile(name, Some(Path::new(file)))
+            .unwrap()
+            .map(|profile| profile.files[0].clone())
+    };
+    assert_eq!(
+        profile(None, "data/ledger-2024.csv").as_deref(),
+        Some("ledger-*.csv")
+    );
+    assert_eq!(profile(None, "access-07.csv").as_deref(), Some("*.log"));
+    assert_eq!(profile(None, "other.csv"), None);
+    // a profile given by name applies to any file
+    assert_eq!(profile(Some("logs"), "other.csv").as_deref(), Some("*.log"));
+    assert!(config.profile(Some("audit"), None).is_err());
+    assert_eq!(config.profile["finance"].delimiter, Some(';'));
+}
+
+#[test]
+fn test_apply_profile() {
+    let (header, rows) = table();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    let mut profile = config().profile["logs"].clone();
+    profile
+        .types
+        .insert("status".to_string(), "str".to_string());
+    profile
+        .types
+        .insert("missing".to_string(), "int".to_string());
+    state.apply_profile(&profile).unwrap();
+    assert_eq!(state.types[3], ColType::Str);
+    assert!(state.hidden.contains(&1));
+    assert_eq!(state.widths()[1], 0);
+    assert!(!state.is_highlighted(0));
+    assert!(state.is_highlighted(1));
+    state.unbound_key("e");
+    assert_eq!(state.row_count(), 1);
+
+    profile.highlight = vec!["nope > 1".to_string()];
+    assert!(state.apply_profile(&profile).is_err());
+}
+
+#[test]
+fn test_top_level_settings() {
+    let config = config();
+    assert_eq!(config.theme.as_deref(), Some("light"));
+    let (header, rows) = table();
+    let mut viewer = TableViewer::new(TerminalTableRenderer {}, header, rows)
+        .with_keys(config.keys)
+        .with_padding(config.padding)
+        .with_sort(config.sort.as_deref());
+    assert_eq!(viewer.state().widths()[1..], [7, 7, 9]);
+    // sorted by the column the table has
+    assert_eq!(viewer.state().view_row(0)[3], "503");
+    assert_eq!(viewer.state().sort_order.len(), 1);
+
+    // the mapped key replaces the built-in one
+    let events = [Key::Char('d'), Key::Char('j')].map(Event::Key);
+    viewer.run_events(events, &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().row_count(), 1);
+    assert_eq!(viewer.state().sort_order.len(), 1);
+}
@@ -0,0 +1,66 @@
+use std::error::Error;
+use std::io::Read;
+use std::path::Path;
+use table_viewer::registry::{DataSource, LoadOptions, Registry, Table};
+
+/// Reads one value per line into a single column.
+struct LinesSource;
+
+impl DataSource for LinesSource {
+    fn name(&self) -> &str {
+        "lines"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn load(&self, mut reader: Box<dyn Read>, _: &LoadOptions) -> Result<Table, Box<dyn Error>> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let rows = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| vec![format!("{}", i + 1), line.to_string()])
+            .collect();
+        Ok((vec!["#".to_string(), "line".to_string()], rows))
+    }
+}
+
+#[test]
+#[cfg(feature = "csv")]
+fn test_default_sources() {
+    let registry = Registry::default();
+    let tsv = registry.source_for_path(Path::new("data.TSV")).unwrap();
+    assert_eq!(tsv.name(), "tsv");
+    assert!(registry.source_for_path(Path::new("data.txt")).is_none());
+    assert!(registry.renderer("terminal").is_some());
+
+    let (header, rows) = registry
+        .source("csv")
+        .unwrap()
+        .load(
+            Box::new("a;b\n1;2\n".as_bytes()),
+            &LoadOptions {
+                delimiter: Some(b';'),
+                quote: None,
+                sheet: None,
+                max_rows: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(header, vec!["#", "a", "b"]);
+    assert_eq!(rows, vec![vec!["1", "1", "2"]]);
+}
+
+#[test]
+fn test_register_source() {
+    let mut registry = Registry::default();
+    registry.register_source(Box::new(LinesSource));
+    let source = registry.source_for_path(Path::new("notes.txt")).unwrap();
+    let (_, rows) = source
+        .load(Box::new("x\ny\n".as_bytes()), &LoadOptions::default())
+        .unwrap();
+    assert_eq!(rows[1], vec!["2", "y"]);
+    assert_eq!(registry.sources().next().unwrap().name(), "lines");
+}
@@ -1,24 +1,35 @@
+#![cfg(feature = "csv")]
+#![allow(clippy::useless_vec, clippy::needless_borrow)]
 use std::path::Path;
 use table_viewer::csv::read_csv_from_file;
-use table_viewer::renderer::{RenderingAction, TableRenderer, TerminalTableRenderer};
-use table_viewer::state::{CharCoord, TableState};
+use table_viewer::renderer::{plain_lines, RenderingAction, TableRenderer, TerminalTableRenderer};
+use table_viewer::state::{fit_widths, CharCoord, TableState};
 
 fn small_table_state_fixture() -> TableState {
     let (header, rows) =
         read_csv_from_file(Path::new("tests/resources/small_table.csv"), b',', b'"').unwrap();
-    TableState::new(header, rows, CharCoord { x: 9, y: 4 })
+    TableState::new(header, rows, CharCoord { x: 9, y: 5 })
 }
 
 fn pretty_print(value: &str) -> String {
     let value: String = value.chars().skip(10).collect();
     value
         .replace("\x1B[1m", "")
+        .replace("\x1B[7m", "")
+        .replace("\x1B[38;5;3m", "")
+        .replace("\x1B[39m", "")
         .replace("\x1B[m", "")
+        .replace("\x1B[?25h", "")
+        .replace("\x1B[?25l", "<hidden>")
         .replace("\x1B[", "\n<goto>")
         .replace("H", "</goto>")
         .replace("\r", "")
 }
 
+fn status(text: &str) -> String {
+    format!("<goto>5;1</goto>{:9}", text)
+}
+
 fn render(renderer: &TerminalTableRenderer, state: &TableState) -> String {
     pretty_print(&renderer.render(&state, &RenderingAction::Rerender).unwrap())
 }
@@ -37,6 +48,7 @@ fn test_move_down() {
             "1  1a  1…",
             "2  2a  2…",
             "3  3a  3…",
+            &status("#"),
             &format!("<goto>{};1</goto>", row),
         ]
         .join("\n");
@@ -53,6 +65,7 @@ fn test_move_down() {
         "2  2a  2…",
         "3  3a  3…",
         "4  4a  4…",
+        &status("#"),
         "<goto>4;1</goto>",
     ]
     .join("\n");
@@ -65,6 +78,7 @@ fn test_move_down() {
         "3  3a  3…",
         "4  4a  4…",
         "5  5a  5…",
+        &status("#"),
         "<goto>4;1</goto>",
     ]
     .join("\n");
@@ -92,6 +106,7 @@ fn test_move_up() {
             "3  3a  3…",
             "4  4a  4…",
             "5  5a  5…",
+            &status("#"),
             &format!("<goto>{};1</goto>", row),
         ]
         .join("\n");
@@ -105,6 +120,7 @@ fn test_move_up() {
         "2  2a  2…",
         "3  3a  3…",
         "4  4a  4…",
+        &status("#"),
         &format!("<goto>{};1</goto>", 2),
     ]
     .join("\n");
@@ -117,6 +133,7 @@ fn test_move_up() {
         "1  1a  1…",
         "2  2a  2…",
         "3  3a  3…",
+        &status("#"),
         &format!("<goto>{};1</goto>", 2),
     ]
     .join("\n");
@@ -130,6 +147,7 @@ fn test_move_up() {
         "1  1a  1…",
         "2  2a  2…",
         "3  3a  3…",
+        &status("#"),
         &format!("<goto>{};1</goto>", 1),
     ]
     .join("\n");
@@ -152,6 +170,7 @@ fn test_move_right() {
         "1  1a  1…",
         "2  2a  2…",
         "3  3a  3…",
+        &status("#"),
         "<goto>1;1</goto>",
     ]
     .join("\n");
@@ -164,6 +183,7 @@ fn test_move_right() {
         "1  1a  1…",
         "2  2a  2…",
         "3  3a  3…",
+        &status("a"),
         "<goto>1;4</goto>",
     ]
     .join("\n");
@@ -177,6 +197,7 @@ fn test_move_right() {
         "1a  1bb  ",
         "2a  2bb  ",
         "3a  3bb  ",
+        &status("bb"),
         "<goto>1;5</goto>",
     ]
     .join("\n");
@@ -189,6 +210,7 @@ fn test_move_right() {
         "1bb  1c  ",
         "2bb  2c  ",
         "3bb  3c  ",
+        &status("c"),
         "<goto>1;6</goto>",
     ]
     .join("\n");
@@ -202,8 +224,466 @@ fn test_move_right() {
         "1bb  1c  ",
         "2bb  2c  ",
         "3bb  3c  ",
+        &status("c"),
         "<goto>1;6</goto>",
     ]
     .join("\n");
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn test_matches_pane() {
+    let mut state = small_table_state_fixture();
+    let renderer = TerminalTableRenderer {};
+    state.move_right();
+    state.search("a");
+    state.show_matches();
+    let actual = pretty_print(&renderer.render(&state, &RenderingAction::Pane).unwrap());
+    let expected = [
+        "5 matche…",
+        "1: 1a    ",
+        "2: 2a    ",
+        "3: 3a    ",
+        "<goto>2;1</goto>",
+    ]
+    .join("\n");
+    assert_eq!(actual, expected);
+
+    // Selecting an entry jumps to its row
+    state.pane_down();
+    state.pane_down();
+    state.pane_down();
+    state.pane_select();
+    assert!(state.pane.is_none());
+    assert_eq!(state.current_row(), 4);
+}
+
+#[test]
+fn test_fit_columns() {
+    assert_eq!(fit_widths(&[3, 4, 10], 12), vec![3, 4, 5]);
+    assert_eq!(fit_widths(&[3, 8, 10], 12), vec![3, 5, 4]);
+    assert_eq!(fit_widths(&[3, 4, 5], 24), vec![6, 8, 10]);
+
+    // Columns don't shrink below one character plus padding
+    let mut state = small_table_state_fixture();
+    state.fit_columns();
+    let actual = render(&TerminalTableRenderer {}, &state);
+    let expected = [
+        "#  a  bb ",
+        "1  1a 1bb",
+        "2  2a 2bb",
+        "3  3a 3bb",
+        &status("#"),
+        "<goto>1;1</goto>",
+    ]
+    .join("\n");
+    assert_eq!(actual, expected);
+
+    state.unfit_columns();
+    assert_eq!(state.columns[3].width, 4);
+}
+
+#[test]
+fn test_hide_columns() {
+    let mut state = small_table_state_fixture();
+    state.move_right();
+    state.hide_column();
+    assert_eq!(state.current_column(), 2);
+    assert_eq!(
+        state.status_message.as_deref(),
+        Some("Hid a (1 hidden, zR shows all)")
+    );
+    let actual = render(&TerminalTableRenderer {}, &state);
+    assert!(actual.starts_with("#  bb   c\n1  1bb  …"));
+
+    // the cursor skips hidden columns
+    state.move_left();
+    assert_eq!(state.current_column(), 0);
+    state.move_right();
+    assert_eq!(state.current_column(), 2);
+
+    // hiding the last column moves the cursor left, the last shown one stays
+    state.move_end_of_line();
+    state.hide_column();
+    assert_eq!(state.current_column(), 2);
+    state.hide_column();
+    assert_eq!(state.current_column(), 0);
+    state.hide_column();
+    assert_eq!(
+        state.status_message.as_deref(),
+        Some("Cannot hide the last shown column")
+    );
+    assert!(matches!(state.move_right(), RenderingAction::None));
+
+    state.unhide_columns();
+    assert!(state.hidden.is_empty());
+    assert_eq!(state.columns[1].width, 4);
+    assert_eq!(state.current_column(), 0);
+}
+
+#[test]
+fn test_frozen_columns() {
+    let mut state = small_table_state_fixture();
+    state.resize(CharCoord { x: 12, y: 5 });
+    state.move_right();
+    state.toggle_frozen();
+    assert_eq!(state.status_line(), "Froze columns up to a");
+    state.move_right();
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert_eq!(lines[1], "1  1a  1bb  ");
+    assert_eq!(state.cursor_x(), 7);
+
+    // the frozen columns stay while the others scroll
+    assert!(matches!(state.move_right(), RenderingAction::Rerender));
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert_eq!(lines[1], "1  1a  1c  ");
+    assert_eq!((state.current_column(), state.cursor_x()), (3, 7));
+    state.move_left();
+    assert!(matches!(state.move_left(), RenderingAction::MoveCursor));
+    assert_eq!((state.current_column(), state.cursor_x()), (1, 3));
+    state.move_end_of_line();
+    assert_eq!((state.current_column(), state.cursor_x()), (3, 7));
+
+    state.move_start_of_line();
+    state.move_right();
+    state.toggle_frozen();
+    assert_eq!(state.status_line(), "Unfroze columns");
+    assert_eq!((state.offsets.col, state.cur_pos.col), (0, 1));
+}
+
+#[test]
+fn test_record_view() {
+    let mut state = small_table_state_fixture();
+    state.resize(CharCoord { x: 20, y: 4 });
+    state.toggle_record_view();
+    assert_eq!(
+        state.record_lines(),
+        ["Row 1 (1 of 5)", "a  │ 1a", "bb │ 1bb"]
+    );
+    assert!(matches!(
+        state.scroll_record(true),
+        RenderingAction::Rerender
+    ));
+    assert_eq!(state.record_lines()[1..], ["bb │ 1bb", "c  │ 1c"]);
+    assert!(matches!(state.scroll_record(true), RenderingAction::None));
+    state.move_down();
+    assert_eq!(state.record_lines()[0], "Row 2 (2 of 5)");
+    let actual = render(&TerminalTableRenderer {}, &state);
+    assert!(actual.starts_with("<hidden>Row 2 (2 of 5)      \nbb │ 2bb"));
+
+    state.toggle_record_view();
+    assert!(state.record_view.is_none());
+    assert_eq!(state.current_data_row(), Some(1));
+}
+
+#[test]
+fn test_truncation_indicator() {
+    let header = vec!["#".to_string(), "text".to_string()];
+    let rows = vec![
+        vec!["1".to_string(), "more than fits".to_string()],
+        vec!["2".to_string(), "ends in…".to_string()],
+    ];
+    let mut state = TableState::new(header, rows, CharCoord { x: 20, y: 5 });
+    state.set_widths(vec![3, 10]);
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    // only the ellipsis of the truncated value is highlighted
+    assert_eq!(lines[1], "1  more than\x1B[38;5;3m…\x1B[39m");
+    assert_eq!(lines[2], "2  ends in…  ");
+}
+
+#[test]
+fn test_resize() {
+    let mut state = small_table_state_fixture();
+    for _ in 0..5 {
+        state.move_down();
+    }
+    state.move_right();
+    state.move_right();
+    assert_eq!((state.offsets.row, state.offsets.col), (2, 1));
+
+    // everything fits, the cursor stays on its cell
+    state.resize(CharCoord { x: 20, y: 10 });
+    let actual = render(&TerminalTableRenderer {}, &state);
+    assert!(actual.starts_with("#  a   bb   c   \n1  1a  1bb  1c  "));
+    assert_eq!((state.offsets.row, state.offsets.col), (0, 0));
+    assert_eq!(
+        (state.current_data_row(), state.current_column()),
+        (Some(4), 2)
+    );
+
+    state.resize(CharCoord { x: 9, y: 5 });
+    assert_eq!((state.offsets.row, state.cur_pos.row), (2, 3));
+    assert_eq!(
+        (state.current_data_row(), state.current_column()),
+        (Some(4), 2)
+    );
+}
+
+#[test]
+fn test_recompute_layout() {
+    let mut state = small_table_state_fixture();
+    state.move_end();
+    state.move_end_of_line();
+    assert_eq!(
+        (state.current_data_row(), state.current_column()),
+        (Some(4), 3)
+    );
+
+    // narrower than a column: it is shown first, cut off
+    state.recompute_layout(CharCoord { x: 3, y: 5 });
+    assert_eq!((state.offsets.col, state.cur_pos.col), (3, 0));
+    assert_eq!(state.columns[3].width, 3);
+    assert!(matches!(state.move_right(), RenderingAction::None));
+    state.move_left();
+    assert_eq!((state.offsets.col, state.cur_pos.col), (2, 0));
+    assert!(matches!(state.move_right(), RenderingAction::Rerender));
+    assert_eq!((state.offsets.col, state.cur_pos.col), (3, 0));
+
+    // sizes too small for a row are raised to the minimum
+    state.recompute_layout(CharCoord { x: 0, y: 0 });
+    assert_eq!((state.terminal_size.x, state.terminal_size.y), (1, 3));
+    assert_eq!((state.offsets.row, state.cur_pos.row), (4, 1));
+
+    // a cursor beyond the last row moves to the last row, the window scrolls as little as
+    // possible
+    state.recompute_layout(CharCoord { x: 20, y: 5 });
+    state.offsets.row = 1;
+    state.cur_pos.row = 9;
+    state.recompute_layout(CharCoord { x: 20, y: 5 });
+    assert_eq!((state.offsets.row, state.cur_pos.row), (2, 3));
+    assert_eq!(state.current_data_row(), Some(4));
+    state.move_home();
+    state.offsets.row = 9;
+    state.recompute_layout(CharCoord { x: 20, y: 5 });
+    assert_eq!((state.offsets.row, state.cur_pos.row), (2, 0));
+    assert_eq!(state.current_column(), 3);
+}
+
+#[test]
+fn test_scroll_current_row() {
+    let mut state = small_table_state_fixture();
+    state.move_down();
+    state.move_down();
+    state.move_down();
+    state.move_down();
+    assert_eq!((state.offsets.row, state.cur_pos.row), (1, 3));
+
+    // Row 4 to the top: the window can't scroll past the last row
+    state.scroll_top();
+    assert_eq!((state.offsets.row, state.cur_pos.row), (2, 2));
+    assert_eq!(state.current_data_row(), Some(3));
+
+    state.scroll_bottom();
+    assert_eq!((state.offsets.row, state.cur_pos.row), (1, 3));
+
+    state.scroll_center();
+    assert_eq!((state.offsets.row, state.cur_pos.row), (2, 2));
+    assert_eq!(state.current_data_row(), Some(3));
+}
+
+#[test]
+fn test_multiline_values() {
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/multiline.csv"), b',', b'"').unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0][2], "first line\nsecond line");
+    let state = TableState::new(header, rows, CharCoord { x: 30, y: 6 });
+    let actual = render(&TerminalTableRenderer {}, &state);
+    let expected = [
+        "#  id  note                   ",
+        "1   1  first line↵second line ",
+        "2   2  crlf↵value             ",
+        "3   3  plain                  ",
+        &format!("<goto>6;1</goto>{:30}", "#"),
+        "<goto>1;1</goto>",
+    ]
+    .join("\n");
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_disambiguate_header() {
+    let header = ["#", " name", "", "name", "name_2", "#"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let rows = vec![(0..6).map(|i| i.to_string()).collect()];
+    let state = TableState::new(header, rows, CharCoord { x: 40, y: 5 });
+    assert_eq!(
+        state.header,
+        vec!["#", "name", "col_2", "name_2", "name_2_2", "#_2"]
+    );
+    assert_eq!(state.column_index("name_2"), Some(3));
+    assert_eq!(
+        state.status_line(),
+        "Renamed 4 duplicate or empty column names"
+    );
+}
+
+#[test]
+fn test_presentation() {
+    let mut state = small_table_state_fixture();
+    state.terminal_size.x = 12;
+    let renderer = TerminalTableRenderer {};
+
+    state.execute_batch(":present 0.5");
+    assert_eq!(
+        state.presentation.as_ref().unwrap().interval,
+        Some(std::time::Duration::from_millis(500))
+    );
+    assert_eq!(state.current_column(), 1);
+    // without row numbers and status line, with wider padding
+    let expected = "<hidden>a     bb    \n1a    1bb   \n2a    2bb   \n3a    3bb   \n4a    4bb   ";
+    assert_eq!(render(&renderer, &state), expected);
+
+    // scrolls down until the last row is visible, then starts over
+    state.auto_scroll();
+    let expected = "<hidden>a     bb    \n2a    2bb   \n3a    3bb   \n4a    4bb   \n5a    5bb   ";
+    assert_eq!(render(&renderer, &state), expected);
+    state.auto_scroll();
+    assert_eq!(state.offsets.row, 0);
+
+    state.stop_presenting();
+    let expected = [
+        "#  a   bb   ",
+        "1  1a  1bb  ",
+        "2  2a  2bb  ",
+        "3  3a  3bb  ",
+        &format!("<goto>5;1</goto>{:12}", "a"),
+        "<goto>1;4</goto>",
+    ]
+    .join("\n");
+    assert_eq!(render(&renderer, &state), expected);
+}
+
+#[test]
+fn test_window_title() {
+    let header = vec!["#".to_string(), "x".to_string()];
+    let rows = (1..=56789)
+        .map(|i| vec![i.to_string(), String::new()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 20, y: 5 });
+    let renderer = TerminalTableRenderer {};
+    assert_eq!(
+        renderer.render_title(&state),
+        "\x1B]2;row 1/56.789 — table-viewer\x07"
+    );
+    for _ in 0..1234 {
+        state.move_down();
+    }
+    assert_eq!(state.window_title(), "row 1.234/56.789 — table-viewer");
+    assert_eq!(renderer.restore_title(), "\x1B[23;0t");
+
+    let state = small_table_state_fixture();
+    assert_eq!(state.window_title(), "row 1/5 — table-viewer");
+}
+
+#[test]
+fn test_alternate_screen() {
+    let state = small_table_state_fixture();
+    let renderer = TerminalTableRenderer {};
+    let init = renderer.init(&state);
+    assert!(init.starts_with("\x1B[?1049h"));
+    assert!(init.ends_with(&renderer.render_title(&state)));
+    let teardown = renderer.teardown();
+    assert!(teardown.starts_with(&renderer.restore_title()));
+    assert!(teardown.ends_with("\x1B[2J\x1B[1;1H\x1B[?1049l"));
+    assert!(renderer.render(&state, &RenderingAction::Reset).is_none());
+}
+
+#[test]
+fn test_sort_indicators() {
+    let mut state = small_table_state_fixture();
+    state.terminal_size = CharCoord { x: 20, y: 5 };
+    let renderer = TerminalTableRenderer {};
+    let header =
+        |state: &TableState| pretty_print(&format!("{:10}{}", "", renderer.frame_lines(state)[0]));
+    state.descending(1);
+    assert_eq!(header(&state), "#  a▼  bb   c   ");
+    state.then_sort(2, std::cmp::Ordering::Less);
+    assert_eq!(header(&state), "#  a▼1 bb▲2 c   ");
+    state.original_order();
+    assert_eq!(header(&state), "#  a   bb   c   ");
+}
+
+#[test]
+fn test_plain_lines() {
+    let mut state = small_table_state_fixture();
+    state.move_right();
+    state.hide_column();
+    state.execute_batch(":filter c != 3c; :sort -bb");
+    let lines = plain_lines(&state);
+    assert_eq!(
+        lines,
+        [
+            "#  bb▼  c",
+            "5  5bb  5c",
+            "4  4bb  4c",
+            "2  2bb  2c",
+            "1  1bb  1c"
+        ]
+    );
+}
+
+#[test]
+fn test_highlight_matches() {
+    let mut state = small_table_state_fixture();
+    state.move_right();
+    state.search("3");
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert!(lines[2].starts_with("2  2a  2"));
+    assert!(lines[3].starts_with("3  \x1B[7m3a  \x1B[27m3"));
+    // only the searched column, which n searches again from another column
+    state.move_right();
+    state.repeat_search(false);
+    assert_eq!(state.current_column(), 1);
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert!(lines[3].contains("\x1B[7m3a"));
+    assert!(!lines[3].contains("\x1B[7m3b"));
+    state.execute_batch(":noh");
+    let lines = TerminalTableRenderer {}.frame_lines(&state);
+    assert!(!lines.iter().any(|line| line.contains("\x1B[7m")));
+}
+
+#[test]
+fn test_go_to_row() {
+    let mut state = small_table_state_fixture();
+    state.execute_batch(":4");
+    assert_eq!(state.current_data_row(), Some(3));
+    assert_eq!(state.view_row(3)[0], "4");
+    // positions among the shown rows, the last one if there are fewer
+    state.execute_batch(":filter a != 1a; :1");
+    assert_eq!(state.view_row(state.current_data_row().unwrap())[0], "2");
+    state.go_to_row(99);
+    assert_eq!(state.current_data_row(), Some(3));
+    state.execute_batch(":filter a = x; :2");
+    assert_eq!(state.status_line(), "No rows");
+}
+
+#[test]
+fn test_go_to_column() {
+    let header: Vec<String> = ["#", "id", "first_name", "last_name", "Price", "net_price"]
+        .map(String::from)
+        .to_vec();
+    let rows = vec![["1", "7", "Ada", "Lovelace", "10", "8"]
+        .map(String::from)
+        .to_vec()];
+    let mut state = TableState::new(header, rows, CharCoord { x: 20, y: 5 });
+    state.execute_batch(":col price");
+    assert_eq!(state.header[state.current_column()], "Price");
+    assert!(state.offsets.col > 0);
+    // prefixes before parts, cycling through equally good matches
+    state.execute_batch(":col pri");
+    assert_eq!(state.header[state.current_column()], "Price");
+    state.execute_batch(":col name");
+    assert_eq!(state.header[state.current_column()], "first_name");
+    assert_eq!(state.offsets.col, 2);
+    assert_eq!(state.status_line(), "Column 1 of 2 matching 'name'");
+    state.execute_batch(":col name");
+    assert_eq!(state.header[state.current_column()], "last_name");
+    // letters in order
+    state.execute_batch(":col ntpr");
+    assert_eq!(state.header[state.current_column()], "net_price");
+    state.execute_batch(":col zip");
+    assert_eq!(state.status_line(), "No column matches 'zip'");
+}
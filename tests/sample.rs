@@ -0,0 +1,87 @@
+use table_viewer::sample::Sample;
+use table_viewer::state::{CharCoord, TableState};
+
+fn numbers(state: &TableState) -> Vec<usize> {
+    (0..state.row_count())
+        .map(|i| state.view_row(i)[1].parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_select() {
+    let origin: Vec<usize> = (0..100).collect();
+    let view: Vec<usize> = (0..100).collect();
+    let rows: Vec<Vec<String>> = (0..100).map(|i| vec![i.to_string()]).collect();
+    let sample = |seed| Sample {
+        size: 10,
+        seed,
+        stratify: None,
+    };
+    let selected = sample(7).select(&view, &origin, &rows);
+    assert_eq!(selected.len(), 10);
+    assert!(selected.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(sample(7).select(&view, &origin, &rows), selected);
+    assert_ne!(sample(8).select(&view, &origin, &rows), selected);
+    assert_eq!(
+        sample(7).select(&view[..5], &origin, &rows),
+        [0, 1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn test_sample_command() {
+    let header: Vec<String> = ["#", "n"].map(String::from).to_vec();
+    let rows = (1..=50)
+        .map(|i| vec![i.to_string(), i.to_string()])
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":sample 5 seed=42");
+    assert_eq!(state.status_line(), "Sample of 5 of 50 rows (seed 42)");
+    let sample = numbers(&state);
+    assert_eq!(sample.len(), 5);
+    // sorting shows the same rows
+    state.descending(1);
+    let mut sorted = numbers(&state);
+    sorted.reverse();
+    assert_eq!(sorted, sample);
+    state.execute_batch(":sample off");
+    assert_eq!(state.row_count(), 50);
+    state.execute_batch(":sample off");
+    assert_eq!(state.status_line(), "No sample active");
+    state.execute_batch(":sample many");
+    assert_eq!(
+        state.status_line(),
+        "Usage: :sample <size> [stratify <column>] [seed=<number>] or :sample off"
+    );
+}
+
+#[test]
+fn test_stratified_sample() {
+    let header: Vec<String> = ["#", "country"].map(String::from).to_vec();
+    // 60 rows for de, 30 for fr and 10 for it
+    let rows = (1..=100)
+        .map(|i| {
+            let country = match i % 10 {
+                0 => "it",
+                1..=3 => "fr",
+                _ => "de",
+            };
+            vec![i.to_string(), country.to_string()]
+        })
+        .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":sample 11 stratify country seed=3");
+    assert_eq!(
+        state.status_line(),
+        "Sample of 11 of 100 rows, stratified by country (seed 3)"
+    );
+    let count = |country: &str| {
+        (0..state.row_count())
+            .filter(|&i| state.view_row(i)[1] == country)
+            .count()
+    };
+    // 6.6, 3.3 and 1.1 rows, the one left goes to de
+    assert_eq!((count("de"), count("fr"), count("it")), (7, 3, 1));
+    state.execute_batch(":sample 5 stratify region");
+    assert_eq!(state.status_line(), "Unknown column: region");
+}
@@ -0,0 +1,35 @@
+#![cfg(all(feature = "config", feature = "csv"))]
+use std::path::Path;
+use table_viewer::cell::ColType;
+use table_viewer::csv::read_csv_from_file;
+use table_viewer::schema::Schema;
+use table_viewer::state::{CharCoord, TableState};
+
+#[test]
+fn test_schema_issues() {
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let schema = Schema::from_file(Path::new("tests/resources/typed_table.schema.toml")).unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 20, y: 10 });
+    state.apply_schema(&schema);
+
+    assert_eq!(state.types[3], ColType::Float);
+    let issues: Vec<&str> = state.issues.iter().map(|i| i.text.as_str()).collect();
+    assert_eq!(
+        issues,
+        vec![
+            "missing column 'id'",
+            "3: count: missing value",
+            "4: price: 'n/a' is not float"
+        ]
+    );
+    assert_eq!(state.pane.as_ref().unwrap().entries.len(), 3);
+
+    // Jump to the unparsable price
+    state.pane_down();
+    state.pane_down();
+    state.pane_select();
+    assert_eq!(state.current_data_row(), Some(3));
+    assert_eq!(state.current_column(), 3);
+    assert!(state.pane.is_none());
+}
@@ -0,0 +1,42 @@
+#![cfg(all(feature = "scripting", feature = "csv"))]
+use std::path::Path;
+use table_viewer::csv::read_csv_from_file;
+use table_viewer::script::ScriptHost;
+use table_viewer::state::{CharCoord, TableState};
+
+const SCRIPT: &str = r#"
+fn shout(row) { row["a"].to_upper() }
+fn goto_matches(ctx, args) { "/" + args + "; :matches" }
+fn on_key(key, ctx) { if key == "x" { ":goto_matches " + ctx.value } else { "" } }
+"#;
+
+fn scripted_state_fixture() -> TableState {
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/small_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 9, y: 4 });
+    state.script = Some(ScriptHost::from_source(SCRIPT).unwrap());
+    state
+}
+
+#[test]
+fn test_compute_column() {
+    let mut state = scripted_state_fixture();
+    state.execute_batch(":compute A shout");
+    assert_eq!(state.header.last().unwrap(), "A");
+    assert_eq!(state.rows[1].last().unwrap(), "2A");
+    assert_eq!(state.columns.len(), 5);
+}
+
+#[test]
+fn test_script_command_and_key_handler() {
+    let mut state = scripted_state_fixture();
+    state.execute_batch(":goto_matches 3");
+    assert_eq!(state.pane.as_ref().unwrap().entries.len(), 1);
+
+    state.pane = None;
+    state.move_home();
+    state.move_down();
+    state.script_key("x");
+    assert_eq!(state.last_search.as_ref().unwrap().pattern, "1");
+    assert!(state.pane.is_some());
+}
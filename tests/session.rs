@@ -0,0 +1,347 @@
+#![cfg(feature = "csv")]
+use std::fs;
+use std::path::Path;
+use table_viewer::csv::read_csv_from_file;
+use table_viewer::session::{row_hash, Session};
+use table_viewer::state::{CharCoord, TableState};
+
+fn open(table: &Path) -> TableState {
+    let (header, rows) = read_csv_from_file(table, b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.open_session(Session::load(table).unwrap());
+    state
+}
+
+#[test]
+fn test_row_hash() {
+    let row = |values: [&str; 2]| values.map(String::from).to_vec();
+    assert_eq!(row_hash(&row(["a", "b"])), row_hash(&row(["a", "b"])));
+    assert_ne!(row_hash(&row(["ab", ""])), row_hash(&row(["a", "b"])));
+}
+
+#[test]
+fn test_notes() {
+    let dir = std::env::temp_dir().join("table_viewer_notes");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("typed_table.csv");
+    fs::copy("tests/resources/typed_table.csv", &table).unwrap();
+    let _ = fs::remove_file(Session::sidecar_path(&table));
+
+    let mut state = open(&table);
+    assert_eq!(state.note_col, None);
+    state.move_down();
+    state.move_down();
+    state.execute_batch(":note check\tprice");
+    assert_eq!(state.note_col, Some(6));
+    assert_eq!(state.header[6], "note");
+    assert_eq!(state.rows[1][6], "check\tprice");
+
+    // notes are found again by row content after reordering
+    state.ascending(1);
+    drop(state);
+    let mut state = open(&table);
+    assert_eq!(state.rows[1][6], "check\tprice");
+    assert_eq!(state.rows[0][6], "");
+
+    let export = dir.join("export.csv");
+    let _ = fs::remove_file(&export);
+    state.execute_batch(&format!(
+        ":filter name = b; :write! --notes {}",
+        export.display()
+    ));
+    assert_eq!(
+        fs::read_to_string(&export).unwrap().lines().nth(1),
+        Some("a,2,10,2021-12-31,false,check\tprice")
+    );
+    state.execute_batch(&format!(":write! {}", export.display()));
+    assert_eq!(
+        state.prompt.as_ref().unwrap().question,
+        format!("Overwrite {}?", export.display())
+    );
+    state.answer_prompt(true);
+    assert_eq!(
+        fs::read_to_string(&export).unwrap().lines().next(),
+        Some("name,count,price,day,flag")
+    );
+
+    // removing the last note removes the sidecar
+    state.execute_batch(":filter");
+    state.answer_prompt(true);
+    state.execute_batch(":filter name = a");
+    state.move_down();
+    state.execute_batch(":note");
+    assert!(!Session::sidecar_path(&table).exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_tags() {
+    let dir = std::env::temp_dir().join("table_viewer_tags");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("typed_table.csv");
+    fs::copy("tests/resources/typed_table.csv", &table).unwrap();
+    let _ = fs::remove_file(Session::sidecar_path(&table));
+
+    let mut state = open(&table);
+    state.execute_batch(":filter tag:flagged");
+    assert_eq!(state.status_line(), "No tagged rows");
+    state.move_down();
+    state.toggle_tag("flagged");
+    state.move_down();
+    state.move_down();
+    state.execute_batch(":tag flagged; :tag todo");
+    assert_eq!(state.tag_col, Some(6));
+    assert_eq!(state.rows[2][6], "flagged, todo");
+    // commas separate the tags in the column
+    state.execute_batch(":tag to, do");
+    assert_eq!(state.status_line(), "Tags cannot contain commas");
+    assert_eq!(state.rows[2][6], "flagged, todo");
+
+    let mut state = open(&table);
+    assert_eq!(state.header[6], "tags");
+    state.execute_batch(":filter tag:flagged");
+    assert_eq!(state.row_count(), 2);
+    assert_eq!(state.view_row(1)[1], "c");
+
+    let export = dir.join("todo.tsv");
+    state.execute_batch(&format!(":export tag:todo {}", export.display()));
+    assert_eq!(
+        fs::read_to_string(&export).unwrap(),
+        "name\tcount\tprice\tday\tflag\ttags\nc\t\t-1.25\t2022-01-15T08:30:00\tTRUE\tflagged, todo\n"
+    );
+    state.execute_batch(":export tagged --clipboard");
+    assert_eq!(state.status_line(), "Copied 2 rows to the clipboard");
+    assert_eq!(state.clipboard.take().unwrap().lines().count(), 3);
+
+    // toggling removes the tag
+    state.move_down();
+    state.toggle_tag("flagged");
+    let state = open(&table);
+    assert_eq!(state.rows[0][6], "");
+    assert_eq!(
+        fs::read_to_string(Session::sidecar_path(&table)).unwrap(),
+        format!(
+            "tag\t{:016x}\tflagged\ntag\t{:016x}\ttodo\n",
+            row_hash(&state.rows[2][1..6]),
+            row_hash(&state.rows[2][1..6])
+        )
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_edits() {
+    let dir = std::env::temp_dir().join("table_viewer_edits");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("typed_table.csv");
+    fs::copy("tests/resources/typed_table.csv", &table).unwrap();
+    let _ = fs::remove_file(Session::sidecar_path(&table));
+
+    let mut state = open(&table);
+    state.descending(1);
+    state.move_down();
+    state.move_right();
+    state.execute_batch(":edit dd; :note renamed");
+    // edits are shown, but the loaded values stay untouched
+    assert_eq!(state.rows[0][1], "d");
+    assert_eq!(state.edited_row(0)[1], "dd");
+
+    // the journal survives restarts
+    let mut state = open(&table);
+    state.delimited = Some((b',', b'"'));
+    assert_eq!(state.edited_row(3)[1], "dd");
+    state.execute_batch(":changes");
+    let pane = state.pane.take().unwrap();
+    assert_eq!(pane.title, "1 pending edits");
+    assert_eq!(pane.entries[0].text, "4 name: d -> dd");
+    assert_eq!(pane.entries[0].row, Some(3));
+
+    let export = dir.join("export.csv");
+    let _ = fs::remove_file(&export);
+    state.execute_batch(&format!(":filter name != d; :write! {}", export.display()));
+    assert_eq!(
+        fs::read_to_string(&export).unwrap(),
+        "name,count,price,day,flag\ndd,1,n/a,,false\n"
+    );
+
+    state.execute_batch(":save");
+    assert_eq!(
+        state.status_line(),
+        format!("Saved 1 edits to {}", table.display())
+    );
+    assert!(state.session.journal.is_empty());
+    assert_eq!(state.rows[3][1], "dd");
+    let content = fs::read_to_string(&table).unwrap();
+    assert_eq!(content.lines().nth(4), Some("dd,1,n/a,,false"));
+    assert_eq!(content.lines().count(), 5);
+
+    // notes follow the edited row
+    let state = open(&table);
+    assert_eq!(state.rows[3][6], "renamed");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_edit_key() {
+    let dir = std::env::temp_dir().join("table_viewer_edit_key");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("typed_table.csv");
+    fs::copy("tests/resources/typed_table.csv", &table).unwrap();
+    let _ = fs::remove_file(Session::sidecar_path(&table));
+
+    let mut state = open(&table);
+    state.move_down();
+    state.move_right();
+    state.start_edit();
+    assert_eq!(state.command_buffer.iter().collect::<String>(), ":edit b");
+    // the typed value is taken as it is, widening the column
+    state
+        .command_buffer
+        .extend(" and; a much longer name ".chars());
+    state.execute_command();
+    assert_eq!(state.edited_row(0)[1], "b and; a much longer name ");
+    assert_eq!(state.widths()[1], 28);
+    state.move_left();
+    state.start_edit();
+    assert_eq!(state.status_line(), "Column cannot be edited");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_row_changes() {
+    let dir = std::env::temp_dir().join("table_viewer_row_changes");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("typed_table.csv");
+    fs::copy("tests/resources/typed_table.csv", &table).unwrap();
+    let _ = fs::remove_file(Session::sidecar_path(&table));
+
+    let mut state = open(&table);
+    state.delimited = Some((b',', b'"'));
+    state.descending(1);
+    // rows d, c, b, a: edit b, duplicate it and change the copy, then delete c
+    state.move_down();
+    state.move_down();
+    state.move_down();
+    state.move_right();
+    state.execute_batch(":edit bb");
+    state.duplicate_row();
+    assert_eq!(state.status_line(), "Duplicated row 1, :w saves the table");
+    assert_eq!(state.current_data_row(), Some(3));
+    assert_eq!(state.view_row(3)[..2], ["+", "bb"]);
+    state.execute_batch(":edit e");
+    assert_eq!(state.view_row(3)[1], "e");
+    assert!(state.session.journal.contains_key(&(1, 1)));
+    state.move_up();
+    state.move_up();
+    state.execute_batch(":edit cc");
+    state.delete_row();
+    assert_eq!(state.status_line(), "Deleted row 3, :w saves the table");
+    assert_eq!(state.session.journal.len(), 1);
+    assert_eq!(state.view_row(1)[1], "b");
+    assert_eq!(state.row_count(), 4);
+
+    // saved in the loaded order, the copy right after its row
+    state.execute_batch(":w");
+    assert_eq!(
+        state.status_line(),
+        format!("Saved 1 edits and 2 row changes to {}", table.display())
+    );
+    assert_eq!(
+        fs::read_to_string(&table).unwrap(),
+        "name,count,price,day,flag\nbb,10,2.5,2022-03-01,true\ne,10,2.5,2022-03-01,true\n\
+         a,2,10,2021-12-31,false\nd,1,n/a,,false\n"
+    );
+    let numbers: Vec<_> = (0..4).map(|row| state.view_row(row)[0].as_str()).collect();
+    assert_eq!(numbers, ["4", "1", "2", "3"]);
+    state.original_order();
+    assert_eq!(state.view_row(1)[1], "e");
+
+    // the last row is deleted with the cursor moving up, derived tables are not changed
+    state.move_end();
+    state.execute_batch(":delete-row");
+    assert_eq!(state.current_data_row(), Some(2));
+    state.execute_batch(":freq; :duplicate-row");
+    assert_eq!(
+        state.status_line(),
+        "Rows of derived tables cannot be changed"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_save_conflict() {
+    let dir = std::env::temp_dir().join("table_viewer_save");
+    fs::create_dir_all(&dir).unwrap();
+    let table = dir.join("typed_table.csv");
+    fs::copy("tests/resources/typed_table.csv", &table).unwrap();
+    let _ = fs::remove_file(Session::sidecar_path(&table));
+
+    let mut state = open(&table);
+    state.execute_batch(":save");
+    assert_eq!(state.status_line(), "Cannot save tables of this format");
+    state.delimited = Some((b';', b'\''));
+    state.backup = true;
+    state.move_down();
+    state.move_right();
+    state.execute_batch(":edit b'2");
+
+    fs::write(&table, "name\nchanged\n").unwrap();
+    state.execute_batch(":w");
+    assert!(state.status_line().ends_with(
+        "changed on disk: reopen it to reload (edits are kept) or overwrite with :save!"
+    ));
+    assert_eq!(fs::read_to_string(&table).unwrap(), "name\nchanged\n");
+
+    state.execute_batch(":w!");
+    assert_eq!(
+        fs::read_to_string(&table)
+            .unwrap()
+            .lines()
+            .take(2)
+            .collect::<Vec<_>>(),
+        vec!["name;count;price;day;flag", "'b''2';10;2.5;2022-03-01;true"]
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("typed_table.csv.bak")).unwrap(),
+        "name\nchanged\n"
+    );
+    assert!(!dir.join(".typed_table.csv.tv-tmp").exists());
+    // saving again is no conflict
+    state.execute_batch(":w");
+    assert_eq!(
+        state.status_line(),
+        format!("Saved 0 edits to {}", table.display())
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_info() {
+    let table = Path::new("tests/resources/typed_table.csv");
+    let mut state = open(table);
+    state.delimited = Some((b'\t', b'"'));
+    state.execute_batch(":filter name = a; :info");
+    let pane = state.pane.take().unwrap();
+    let lines: Vec<&str> = pane
+        .entries
+        .iter()
+        .map(|entry| entry.text.as_str())
+        .collect();
+    assert_eq!(pane.title, "Table info");
+    assert_eq!(
+        lines[..6],
+        [
+            "File: tests/resources/typed_table.csv",
+            "Size: 124 B",
+            "Encoding: UTF-8",
+            "Delimiter: tab, quote: '\"'",
+            "Rows: 4 (1 shown)",
+            "Columns: 5 (0 added)",
+        ]
+    );
+    assert!(lines[6].starts_with("Memory: about "));
+    assert_eq!(
+        state.window_title(),
+        "typed_table.csv — row 1/1 — table-viewer"
+    );
+}
@@ -0,0 +1,281 @@
+//! Golden tests of the rendered frames. The output of the renderer is played on a virtual
+//! screen, whose text and cursor position are compared with the snapshots in `snapshots/`;
+//! review changes with `cargo insta review`.
+use insta::assert_snapshot;
+use table_viewer::renderer::{RenderingAction, TableRenderer, TerminalTableRenderer};
+use table_viewer::state::{CharCoord, TableState};
+use table_viewer::theme::Rules;
+
+/// Text cells and cursor of a terminal, understanding the escape sequences of the renderer that
+/// move the cursor or clear the screen and skipping those for styles, colors and titles.
+struct Screen {
+    lines: Vec<Vec<char>>,
+    row: usize,
+    col: usize,
+}
+
+impl Screen {
+    fn new(size: CharCoord) -> Self {
+        Screen {
+            lines: vec![vec![' '; size.x]; size.y],
+            row: 0,
+            col: 0,
+        }
+    }
+
+    fn play(&mut self, output: &str) {
+        let mut chars = output.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' if chars.peek() == Some(&'[') => {
+                    chars.next();
+                    let mut params = String::new();
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            self.control(c, &params);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                }
+                // operating system commands like the window title end with BEL or ST
+                '\x1b' if chars.peek() == Some(&']') => {
+                    let mut prev = c;
+                    for c in chars.by_ref() {
+                        if c == '\x07' || (prev == '\x1b' && c == '\\') {
+                            break;
+                        }
+                        prev = c;
+                    }
+                }
+                '\r' => self.col = 0,
+                '\n' => self.row += 1,
+                c => {
+                    if let Some(cell) = self
+                        .lines
+                        .get_mut(self.row)
+                        .and_then(|l| l.get_mut(self.col))
+                    {
+                        *cell = c;
+                    }
+                    self.col += 1;
+                }
+            }
+        }
+    }
+
+    fn control(&mut self, command: char, params: &str) {
+        let width = self.lines[0].len();
+        match command {
+            'H' => {
+                let mut numbers = params.split(';').map(|n| n.parse().unwrap_or(1));
+                self.row = numbers.next().unwrap_or(1).max(1) - 1;
+                self.col = numbers.next().unwrap_or(1).max(1) - 1;
+            }
+            'J' => self.lines.iter_mut().for_each(|line| line.fill(' ')),
+            'K' => {
+                if let Some(line) = self.lines.get_mut(self.row) {
+                    line[self.col.min(width)..].fill(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The lines of text, framed to show their extent, and the cursor position.
+    fn text(&self) -> String {
+        let border = format!("+{}+", "-".repeat(self.lines[0].len()));
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| format!("|{}|", line.iter().collect::<String>()));
+        let mut text: Vec<String> = std::iter::once(border.clone())
+            .chain(lines)
+            .chain(std::iter::once(border))
+            .collect();
+        text.push(format!(
+            "cursor at line {}, column {}",
+            self.row + 1,
+            self.col + 1
+        ));
+        text.join("\n")
+    }
+}
+
+fn state_fixture(size: CharCoord) -> TableState {
+    let header: Vec<String> = ["#", "name", "price", "origin"].map(String::from).to_vec();
+    let rows = [
+        ("pear", "2", "Italy"),
+        ("apple", "1.5", "New Zealand"),
+        ("fig", "", "Turkey"),
+        ("cherry", "12", "Chile"),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, (name, price, origin))| {
+        [&(i + 1).to_string(), *name, *price, *origin]
+            .map(String::from)
+            .to_vec()
+    })
+    .collect();
+    TableState::new(header, rows, size)
+}
+
+// Plays the renderings of the actions one after the other, starting with a full render.
+fn render(state: &TableState, actions: &[RenderingAction]) -> String {
+    let renderer = TerminalTableRenderer {};
+    let mut screen = Screen::new(state.terminal_size);
+    for action in std::iter::once(&RenderingAction::Rerender).chain(actions) {
+        if let Some(output) = renderer.render(state, action) {
+            screen.play(&output);
+        }
+    }
+    screen.text()
+}
+
+const SIZE: CharCoord = CharCoord { x: 40, y: 7 };
+
+#[test]
+fn test_full_render() {
+    let state = state_fixture(SIZE);
+    assert_snapshot!(render(&state, &[]));
+}
+
+#[test]
+fn test_cursor_moves() {
+    let mut state = state_fixture(SIZE);
+    state.move_down();
+    state.move_right();
+    state.move_right();
+    assert_snapshot!(render(&state, &[RenderingAction::MoveCursor]));
+}
+
+#[test]
+fn test_scrolled_render() {
+    let mut state = state_fixture(CharCoord { x: 20, y: 4 });
+    state.move_end();
+    state.move_end_of_line();
+    assert_snapshot!(render(&state, &[]));
+}
+
+#[test]
+fn test_status_line() {
+    let mut state = state_fixture(SIZE);
+    state.execute_batch(":filter price > 1.5");
+    assert_snapshot!(render(&state, &[RenderingAction::Status]));
+}
+
+#[test]
+fn test_command_line() {
+    let mut state = state_fixture(SIZE);
+    state.command_history.push(":sort -price".to_string());
+    state.start_command(':');
+    for c in "sort -p".chars() {
+        state.insert_command_char(c);
+    }
+    // with the suggested completion after the cursor
+    let suggested = render(&state, &[RenderingAction::Command]);
+    assert_snapshot!(suggested);
+    state.move_command_cursor(-2);
+    assert_snapshot!(render(&state, &[RenderingAction::Command]));
+}
+
+#[test]
+fn test_rules() {
+    let mut state = state_fixture(CharCoord { x: 30, y: 6 });
+    state.rules = Rules::Box;
+    state.move_right();
+    state.toggle_frozen();
+    // the last row scrolls into view below the rule
+    state.move_end();
+    state.move_end_of_line();
+    assert_snapshot!(render(&state, &[RenderingAction::MoveCursor]));
+}
+
+#[test]
+fn test_column_groups() {
+    let mut state = state_fixture(CharCoord { x: 24, y: 5 });
+    state.groups = ["", "fruit", "fruit", "source"].map(String::from).to_vec();
+    state.move_right();
+    let full = render(&state, &[]);
+    assert_snapshot!(full);
+    // the span of a group partly scrolled out of view starts at its first shown column
+    state.move_right();
+    state.move_right();
+    assert_snapshot!(render(&state, &[]));
+}
+
+#[test]
+fn test_prompt() {
+    let mut state = state_fixture(SIZE);
+    state.confirm("Remove all filters?".to_string(), |_| RenderingAction::None);
+    assert_snapshot!(render(&state, &[RenderingAction::Command]));
+}
+
+#[test]
+fn test_filter_builder() {
+    let mut state = state_fixture(SIZE);
+    state.move_right();
+    state.start_filter_builder();
+    state.builder_move_field(true);
+    state.builder_choose(true);
+    state.builder_move_field(true);
+    state.builder_insert('p');
+    assert_snapshot!(render(&state, &[RenderingAction::Pane]));
+}
+
+#[test]
+fn test_stats_pane() {
+    let mut state = state_fixture(CharCoord { x: 40, y: 12 });
+    state.move_right();
+    state.move_right();
+    let action = state.show_stats();
+    assert_snapshot!(render(&state, &[action]));
+}
+
+#[test]
+fn test_column_editor() {
+    let mut state = state_fixture(SIZE);
+    state.execute_batch(":columns");
+    state.pane_toggle();
+    assert_snapshot!(render(&state, &[RenderingAction::Pane]));
+}
+
+#[test]
+fn test_record_view() {
+    let mut state = state_fixture(SIZE);
+    state.move_down();
+    state.toggle_record_view();
+    assert_snapshot!(render(&state, &[]));
+}
+
+#[test]
+fn test_single_column() {
+    let header: Vec<String> = ["#", "value"].map(String::from).to_vec();
+    let rows = vec![["1", "only"].map(String::from).to_vec()];
+    let state = TableState::new(header, rows, CharCoord { x: 12, y: 4 });
+    assert_snapshot!(render(&state, &[]));
+}
+
+#[test]
+fn test_single_line_terminal() {
+    let state = state_fixture(CharCoord { x: 30, y: 1 });
+    assert_snapshot!(render(&state, &[]));
+}
+
+#[test]
+fn test_unicode_cells() {
+    let header: Vec<String> = ["#", "word", "note"].map(String::from).to_vec();
+    let rows = [
+        ("Straße", "ß"),
+        ("東京", "wide"),
+        ("café", "e\u{301}"),
+        ("tab\there", "🙂"),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, (word, note))| vec![(i + 1).to_string(), word.to_string(), note.to_string()])
+    .collect();
+    let state = TableState::new(header, rows, CharCoord { x: 30, y: 6 });
+    assert_snapshot!(render(&state, &[]));
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "csv")]
+use table_viewer::csv::sniff;
+use table_viewer::registry::{LoadOptions, Registry};
+
+#[test]
+fn test_sniff() {
+    assert_eq!(sniff(b"a,b\n1,2\n"), Some((b',', b'"')));
+    assert_eq!(sniff(b"a\tb\tc\r\n1\t2\t3\r\n"), Some((b'\t', b'"')));
+    // commas in values, semicolons between them
+    assert_eq!(sniff(b"name;price\nx;1,5\ny, z;2,0\n"), Some((b';', b'"')));
+    assert_eq!(sniff(b"a|b\n\"1|2\"|3\n"), Some((b'|', b'"')));
+    assert_eq!(sniff(b"a,b\n'x, y',1\n'z',2\n"), Some((b',', b'\'')));
+    // a cut off last line is ignored
+    assert_eq!(sniff(b"a;b\n1;2\n3;4\n5"), Some((b';', b'"')));
+    assert_eq!(sniff(b"single\ncolumn\n"), None);
+}
+
+#[test]
+fn test_sniff_on_load() {
+    let registry = Registry::default();
+    let csv = registry.source("csv").unwrap();
+    let load = |input: &'static str, options: &LoadOptions| {
+        csv.load(Box::new(input.as_bytes()), options).unwrap()
+    };
+    let (header, rows) = load("a;b\n1;2\n", &LoadOptions::default());
+    assert_eq!(header, ["#", "a", "b"]);
+    assert_eq!(rows, [["1", "1", "2"]]);
+    let options = LoadOptions {
+        delimiter: Some(b','),
+        ..LoadOptions::default()
+    };
+    let (header, _) = load("a;b\n1;2\n", &options);
+    assert_eq!(header, ["#", "a;b"]);
+}
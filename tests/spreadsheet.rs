@@ -0,0 +1,41 @@
+#![cfg(feature = "spreadsheet")]
+use std::fs::File;
+use table_viewer::registry::{LoadOptions, Registry};
+use table_viewer::spreadsheet::sheet_names;
+
+const WORKBOOK: &str = "tests/resources/orders.xlsx";
+
+#[test]
+fn test_read_workbook() {
+    let registry = Registry::default();
+    let source = registry
+        .source_for_path(std::path::Path::new(WORKBOOK))
+        .unwrap();
+    let load = |sheet: Option<&str>| {
+        let options = LoadOptions {
+            sheet: sheet.map(String::from),
+            ..LoadOptions::default()
+        };
+        source.load(Box::new(File::open(WORKBOOK).unwrap()), &options)
+    };
+    let (header, rows) = load(None).unwrap();
+    assert_eq!(header, ["#", "item", "price", "ordered", "paid"]);
+    assert_eq!(
+        rows,
+        [
+            ["1", "Widget", "2.5", "2022-03-01", "true"],
+            ["2", "Gadget", "10", "2022-03-01 18:00:00", "#N/A"],
+        ]
+    );
+    let (header, rows) = load(Some("Totals")).unwrap();
+    assert_eq!(header, ["#", "total"]);
+    assert_eq!(rows, [["1", "12.5"]]);
+    assert_eq!(
+        load(Some("Missing")).unwrap_err().to_string(),
+        "No sheet named 'Missing'"
+    );
+    assert_eq!(
+        sheet_names(File::open(WORKBOOK).unwrap()).unwrap(),
+        ["Orders", "Totals"]
+    );
+}
@@ -0,0 +1,253 @@
+use table_viewer::cell::ColType;
+use table_viewer::stats::{describe, describe_json, summarize};
+
+#[test]
+fn test_summarize() {
+    let summary = summarize(["1", "2", "", "2"].into_iter(), ColType::Int);
+    assert_eq!(summary.count, 4);
+    assert_eq!(summary.nulls, 1);
+    assert_eq!(summary.distinct, 2);
+    assert_eq!(summary.null_percentage(), 25.0);
+    assert_eq!(summary.min.as_deref(), Some("1"));
+    assert_eq!(summary.max.as_deref(), Some("2"));
+}
+
+#[test]
+fn test_describe_json() {
+    let header: Vec<String> = ["#", "n", "label"].map(String::from).to_vec();
+    let rows: Vec<Vec<String>> = [["1", "10", "b \"x\""], ["2", "9", ""], ["3", "-2.5", "a"]]
+        .iter()
+        .map(|row| row.map(String::from).to_vec())
+        .collect();
+    let profiles = describe(&header, &rows);
+    assert_eq!(profiles[0].1.summary.col_type, ColType::Float);
+    assert_eq!(
+        describe_json(rows.len(), &profiles),
+        "{\n  \"rows\": 3,\n  \"columns\": [\n    \
+         {\"name\": \"n\", \"type\": \"float\", \"count\": 3, \"nulls\": 0, \
+         \"distinct\": 3, \"min\": -2.5, \"max\": 10, \"mean\": 5.5, \"median\": 9, \
+         \"stddev\": 6.946221994724902},\n    \
+         {\"name\": \"label\", \"type\": \"str\", \"count\": 3, \"nulls\": 1, \
+         \"distinct\": 2, \"min\": \"a\", \"max\": \"b \\\"x\\\"\", \"mean\": null, \
+         \"median\": null, \"stddev\": null}\n  ]\n}"
+    );
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_status_line() {
+    use std::path::Path;
+    use table_viewer::csv::read_csv_from_file;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.move_right();
+    state.move_right();
+    assert_eq!(state.status_line(), "count");
+    let summary = summarize(state.rows.iter().map(|row| row[2].as_str()), state.types[2]);
+    state.summaries.insert(2, summary);
+    assert_eq!(state.status_line(), "count | int | 3 distinct | 25.0% null");
+    state.execute_batch(":nonsense");
+    assert_eq!(state.status_line(), "Unknown command: nonsense");
+}
+
+#[test]
+fn test_stats_pane() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "group", "n"].map(String::from).to_vec();
+    let rows: Vec<Vec<String>> = [
+        ["1", "a", "1"],
+        ["2", "b", "2"],
+        ["3", "a", "6"],
+        ["4", "b", ""],
+    ]
+    .iter()
+    .map(|row| row.map(String::from).to_vec())
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.move_right();
+    state.move_right();
+    let texts = |state: &mut TableState| -> Vec<(String, bool)> {
+        let pane = state.pane.take().unwrap();
+        assert_eq!(pane.title, "Statistics of n");
+        pane.entries
+            .into_iter()
+            .map(|entry| (entry.text, entry.highlight))
+            .collect()
+    };
+    state.execute_batch(":stats");
+    assert_eq!(texts(&mut state)[6], ("mean     3.0000".to_string(), false));
+    state.execute_batch(":filter group = a");
+    state.execute_batch(":stats");
+    assert_eq!(
+        texts(&mut state),
+        [
+            ("         filtered  all", false),
+            ("type     int       int", false),
+            ("rows     2         4", true),
+            ("nulls    0 (0.0%)  1 (25.0%)", true),
+            ("distinct 2         3", true),
+            ("min      1         1", false),
+            ("max      6         6", false),
+            ("mean     3.5000    3.0000", true),
+            ("median   3.5000    2.0000", true),
+            ("stddev   3.5355    2.6458", true),
+        ]
+        .map(|(text, highlight)| (text.to_string(), highlight))
+    );
+
+    // selecting a statistic keeps the cursor in its row
+    state.move_down();
+    let row = state.current_row();
+    state.execute_batch(":stats");
+    state.pane_select();
+    assert_eq!(state.current_row(), row);
+    assert_eq!(state.current_column(), 2);
+}
+
+#[test]
+fn test_time_buckets() {
+    use table_viewer::stats::{time_buckets, TimeBucket};
+
+    let times = [(0, 3600), (1, 7199), (2, 3 * 3600 + 1), (3, 3000)];
+    let bucket = |start, count, first_row| TimeBucket {
+        start,
+        count,
+        first_row,
+    };
+    assert_eq!(
+        time_buckets(times.into_iter(), 3600, 10).unwrap(),
+        [
+            bucket(0, 1, Some(3)),
+            bucket(3600, 2, Some(0)),
+            bucket(7200, 0, None),
+            bucket(10_800, 1, Some(2)),
+        ]
+    );
+    assert_eq!(
+        time_buckets(times.into_iter(), 60, 10).unwrap_err(),
+        "More than 10 buckets, choose a larger size"
+    );
+}
+
+#[test]
+fn test_bucket_command() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "created"].map(String::from).to_vec();
+    let rows: Vec<Vec<String>> = [
+        "2022-03-01 10:05",
+        "2022-03-01 10:40",
+        "",
+        "2022-03-01 12:00",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, created)| vec![(i + 1).to_string(), created.to_string()])
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 30, y: 10 });
+    state.execute_batch(":bucket created by 1h count");
+    let pane = state.pane.take().unwrap();
+    assert_eq!(pane.title, "Rows per 1h of created");
+    let texts: Vec<&str> = pane
+        .entries
+        .iter()
+        .map(|entry| entry.text.as_str())
+        .collect();
+    assert_eq!(
+        texts,
+        [
+            "2022-03-01 10:00  2  █████████",
+            "2022-03-01 11:00  0  ",
+            "2022-03-01 12:00  1  ████",
+        ]
+    );
+    let rows: Vec<Option<usize>> = pane.entries.iter().map(|entry| entry.row).collect();
+    assert_eq!(rows, [Some(0), None, Some(3)]);
+    state.execute_batch(":bucket created by 1h sum");
+    assert_eq!(state.status_line(), "Unknown aggregate: sum (only count)");
+    state.execute_batch(":bucket created");
+    assert!(state.status_line().starts_with("Usage: :bucket"));
+}
+
+#[test]
+fn test_describe_table() {
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "id", "email", "age"].map(String::from).to_vec();
+    let rows: Vec<Vec<String>> = [
+        ["1", "1", "a@x.org", ""],
+        ["2", "2", "", ""],
+        ["3", "3", "c@x.org", "41"],
+    ]
+    .iter()
+    .map(|row| row.map(String::from).to_vec())
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    state.execute_batch(":describe");
+    assert_eq!(state.status_line(), "3 columns of 3 rows, Esc returns");
+    assert_eq!(
+        state.rows[1],
+        ["2", "email", "str", "3", "1", "33.3", "2", "a@x.org", "c@x.org", "", "", ""]
+    );
+    assert_eq!(
+        state.rows[2],
+        ["3", "age", "int", "3", "2", "66.7", "1", "41", "41", "41.0000", "41.0000", ""]
+    );
+    assert_eq!(state.types[5], ColType::Float);
+
+    // the profile is a table of its own, e.g. sorted by the share of nulls
+    state.execute_batch(":sort -null %");
+    assert_eq!(state.view_row(0)[1], "age");
+    state.move_right();
+    state.execute_batch("/mail");
+    assert_eq!(state.current_row(), 2);
+    state.close_derived();
+    assert_eq!(state.header.len(), 4);
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_export_stats() {
+    use std::fs;
+    use table_viewer::state::{CharCoord, TableState};
+
+    let header: Vec<String> = ["#", "id", "email", "age"].map(String::from).to_vec();
+    let rows: Vec<Vec<String>> = [
+        ["1", "1", "a@x.org", ""],
+        ["2", "2", "", "30"],
+        ["3", "3", "c@x.org", "41"],
+    ]
+    .iter()
+    .map(|row| row.map(String::from).to_vec())
+    .collect();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    let path = std::env::temp_dir().join("table_viewer_stats.csv");
+    let _ = fs::remove_file(&path);
+    state.execute_batch(&format!(":export stats {}", path.display()));
+    assert_eq!(
+        state.status_line(),
+        format!("Wrote statistics of 3 columns to {}", path.display())
+    );
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "column,type,rows,nulls,null %,distinct,min,max,mean,median,stddev\n\
+         id,int,3,0,0.0,3,1,3,2.0000,2.0000,1.0000\n\
+         email,str,3,1,33.3,2,a@x.org,c@x.org,,,\n\
+         age,int,3,1,33.3,2,30,41,35.5000,35.5000,7.7782\n"
+    );
+    // of the shown rows, asking before overwriting the file
+    state.execute_batch(&format!(":filter id > 1; :export stats {}", path.display()));
+    assert_eq!(
+        state.prompt.as_ref().unwrap().question,
+        format!("Overwrite {}?", path.display())
+    );
+    state.answer_prompt(true);
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains("\nid,int,2,0,0.0,2,2,3,"));
+    fs::remove_file(path).unwrap();
+}
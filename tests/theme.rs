@@ -0,0 +1,22 @@
+use table_viewer::theme::{from_background_reply, from_colorfgbg, Theme};
+
+#[test]
+fn test_colorfgbg() {
+    assert_eq!(from_colorfgbg("15;0"), Some(Theme::Dark));
+    assert_eq!(from_colorfgbg("0;default;15"), Some(Theme::Light));
+    assert_eq!(from_colorfgbg("0;7"), Some(Theme::Light));
+    assert_eq!(from_colorfgbg("15;default"), None);
+}
+
+#[test]
+fn test_background_reply() {
+    let reply = "\x1b]11;rgb:ffff/ffff/ffff\x07";
+    assert_eq!(from_background_reply(reply), Some(Theme::Light));
+    let reply = "\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\";
+    assert_eq!(from_background_reply(reply), Some(Theme::Dark));
+    // one to four hex digits per channel
+    assert_eq!(from_background_reply("rgb:f/e/d"), Some(Theme::Light));
+    assert_eq!(from_background_reply("rgb:00/ff"), None);
+    assert_eq!("light".parse::<Theme>(), Ok(Theme::Light));
+    assert!("solarized".parse::<Theme>().is_err());
+}
@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+use table_viewer::renderer::TerminalTableRenderer;
+use table_viewer::state::CharCoord;
+use table_viewer::viewer::{Event, Screen, TableViewer};
+use termion::event::{Key, MouseButton, MouseEvent};
+
+fn viewer_fixture() -> TableViewer<TerminalTableRenderer> {
+    let header: Vec<String> = ["#", "name", "price"].map(String::from).to_vec();
+    let rows = [("pear", "2"), ("apple", "1.5"), ("fig", "3")]
+        .iter()
+        .enumerate()
+        .map(|(i, (name, price))| vec![(i + 1).to_string(), name.to_string(), price.to_string()])
+        .collect();
+    TableViewer::new(TerminalTableRenderer {}, header, rows)
+}
+
+fn keys(text: &str) -> Vec<Event> {
+    text.chars().map(|c| Event::Key(Key::Char(c))).collect()
+}
+
+#[test]
+fn test_session() {
+    let mut viewer = viewer_fixture();
+    let mut events = keys(":sort -pric");
+    // edit the command before running it
+    events.extend([Key::Left, Key::Left].map(Event::Key));
+    events.extend(keys("x"));
+    events.extend([Key::Backspace, Key::Ctrl('e')].map(Event::Key));
+    events.extend(keys("e\n"));
+    events.extend(keys("l/app\n"));
+    let mut screen = Vec::new();
+    viewer.run_events(events, &mut screen).unwrap();
+    let state = viewer.state();
+    assert_eq!(state.command_history, [":sort -price", "/app"]);
+    assert_eq!(state.view_row(0)[1], "fig");
+    assert_eq!(state.current_data_row(), Some(2));
+    assert!(String::from_utf8(screen).unwrap().contains("apple"));
+
+    // `d` waits for the next key: another `d` deletes the row, Esc cancels and any other key
+    // sorts before taking effect
+    let mut viewer = viewer_fixture();
+    viewer.run_events(keys("jd"), &mut Vec::new()).unwrap();
+    assert!(viewer.state().sort_order.is_empty());
+    viewer
+        .run_events([Event::Key(Key::Esc)], &mut Vec::new())
+        .unwrap();
+    viewer.run_events(keys("j"), &mut Vec::new()).unwrap();
+    assert!(viewer.state().sort_order.is_empty());
+    assert_eq!(viewer.state().current_data_row(), Some(1));
+    viewer.run_events(keys("dk"), &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().row_count(), 3);
+    assert_eq!(viewer.state().view_row(0)[1], "fig");
+    assert_eq!(viewer.state().current_data_row(), Some(0));
+
+    let mut viewer = viewer_fixture();
+    let mut events = keys("jdddlp");
+    viewer
+        .run_events(events.drain(..3), &mut Vec::new())
+        .unwrap();
+    assert!(viewer.state().sort_order.is_empty());
+    assert_eq!(viewer.state().row_count(), 2);
+    assert_eq!(viewer.state().view_row(0)[1], "apple");
+    viewer.run_events(events, &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().view_row(0)[1], "fig");
+    assert_eq!(viewer.state().view_row(1)[..2], ["+", "fig"]);
+
+    // Esc cancels a command, q quits and ignores the rest
+    let mut viewer = viewer_fixture();
+    let mut events = keys(":sort name");
+    events.extend([Key::Esc, Key::Char('q'), Key::Char('d')].map(Event::Key));
+    let mut screen = Vec::new();
+    viewer.run_events(events, &mut screen).unwrap();
+    assert_eq!(viewer.state().view_row(0)[1], "pear");
+    assert!(viewer.state().sort_order.is_empty());
+    // back on the screen shown before
+    let output = String::from_utf8(screen).unwrap();
+    assert!(output.starts_with("\x1B[?1049h"));
+    assert!(output.ends_with("\x1B[?1049l"));
+}
+
+#[test]
+fn test_mouse() {
+    let mut viewer = viewer_fixture();
+    // a click on the price of apple, positions start at 1
+    let click = |x, y| Event::Mouse(MouseEvent::Press(MouseButton::Left, x, y));
+    viewer.run_events([click(12, 3)], &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().current_data_row(), Some(1));
+    assert_eq!(viewer.state().current_column(), 2);
+
+    // clicks on the header sort ascending and then descending
+    viewer.run_events([click(5, 1)], &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().current_column(), 1);
+    assert_eq!(viewer.state().view_row(0)[1], "apple");
+    viewer.run_events([click(5, 1)], &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().view_row(0)[1], "pear");
+
+    // dragging the right edge of the name header widens the column
+    let events = [
+        click(10, 1),
+        Event::Mouse(MouseEvent::Hold(12, 1)),
+        Event::Mouse(MouseEvent::Hold(13, 1)),
+        Event::Mouse(MouseEvent::Release(13, 1)),
+        Event::Mouse(MouseEvent::Hold(20, 1)),
+    ];
+    viewer.run_events(events, &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().widths(), [3, 10, 7]);
+    assert_eq!(viewer.state().view_row(0)[1], "pear");
+
+    // the wheel scrolls the rows, keeping the cursor on its row while it is in view
+    let wheel = |button| Event::Mouse(MouseEvent::Press(button, 1, 2));
+    let events = [
+        Event::Resize(CharCoord { x: 40, y: 4 }),
+        click(2, 2),
+        wheel(MouseButton::WheelDown),
+    ];
+    viewer.run_events(events, &mut Vec::new()).unwrap();
+    assert_eq!(viewer.state().offsets.row, 1);
+    assert_eq!(viewer.state().current_data_row(), Some(1));
+    viewer
+        .run_events([wheel(MouseButton::WheelUp)], &mut Vec::new())
+        .unwrap();
+    assert_eq!(viewer.state().offsets.row, 0);
+}
+
+// Screen marking where the terminal is handed to the shell and taken back.
+struct JobScreen(Vec<u8>);
+
+impl Write for JobScreen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Screen for JobScreen {
+    fn suspend(&mut self) -> io::Result<()> {
+        self.0.extend(b"<suspend>");
+        Ok(())
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        self.0.extend(b"<resume>");
+        Ok(())
+    }
+}
+
+#[test]
+fn test_suspend() {
+    let mut viewer = viewer_fixture();
+    let mut screen = JobScreen(Vec::new());
+    // stopped by the shell, continued, then stopped and continued from outside
+    let events = [Event::Suspend, Event::Continue, Event::Continue];
+    viewer.run_events(events, &mut screen).unwrap();
+    let output = String::from_utf8(screen.0).unwrap();
+    let (before, rest) = output.split_once("<suspend>").unwrap();
+    let (between, after) = rest.split_once("<resume>").unwrap();
+    assert!(!after.contains("<suspend>") && !after.contains("<resume>"));
+    // torn down before handing over the terminal, set up and drawn completely after
+    for part in [before, between] {
+        assert!(part.ends_with("\x1B[?1049l"));
+    }
+    for part in [between, after] {
+        assert!(part.starts_with("\x1B[?1049h"));
+        assert!(["name", "pear", "apple", "fig"]
+            .iter()
+            .all(|text| part.contains(text)));
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "csv")]
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use table_viewer::csv::read_csv_from_file;
+use table_viewer::state::{CharCoord, TableState};
+
+#[test]
+fn test_split_by_column() {
+    let (header, rows) =
+        read_csv_from_file(Path::new("tests/resources/typed_table.csv"), b',', b'"').unwrap();
+    let mut state = TableState::new(header, rows, CharCoord { x: 80, y: 10 });
+    let dir = std::env::temp_dir().join("table_viewer_split");
+    let _ = fs::remove_dir_all(&dir);
+    state.execute_batch(&format!(":split by flag {}", dir.display()));
+    assert_eq!(state.status_line(), "Splitting by flag...");
+
+    let progress = RefCell::new(Vec::new());
+    let message = state.task.take().unwrap()(&|p| progress.borrow_mut().push(p));
+    assert_eq!(message, format!("Wrote 3 files to {}", dir.display()));
+    assert_eq!(progress.borrow()[2], "Splitting: 2/3 files");
+    assert_eq!(
+        fs::read_to_string(dir.join("false.csv")).unwrap(),
+        "name,count,price,day,flag\na,2,10,2021-12-31,false\nd,1,n/a,,false\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("TRUE_2.csv")).unwrap(),
+        "name,count,price,day,flag\nc,,-1.25,2022-01-15T08:30:00,TRUE\n"
+    );
+    assert!(dir.join("true.csv").exists());
+    fs::remove_dir_all(dir).unwrap();
+
+    state.execute_batch(":split flag");
+    assert_eq!(state.status_line(), "Usage: split by <column> <dir>");
+}
+
+#[test]
+fn test_write_formats() {
+    use table_viewer::writer::write_table;
+
+    let header: Vec<String> = ["#", "name", "note"].map(String::from).to_vec();
+    let rows = [
+        ["1", "a|b", "x < \"y\""].map(String::from).to_vec(),
+        ["2", "c", "two\nlines"].map(String::from).to_vec(),
+    ];
+    let dir = std::env::temp_dir();
+    let write = |name: &str| {
+        let path = dir.join(name);
+        assert_eq!(
+            write_table(&path, &header, &[1, 2], rows.iter()).unwrap(),
+            2
+        );
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        text
+    };
+    assert_eq!(
+        write("table_viewer_formats.md"),
+        "| name | note |\n| --- | --- |\n| a\\|b | x < \"y\" |\n| c | two<br>lines |\n"
+    );
+    assert_eq!(
+        write("table_viewer_formats.json"),
+        "[\n  {\"name\": \"a|b\", \"note\": \"x < \\\"y\\\"\"},\n  {\"name\": \"c\", \"note\": \"two\\nlines\"}\n]\n"
+    );
+    let html = write("table_viewer_formats.html");
+    assert!(html.starts_with("<style>"));
+    assert!(html.contains("<tr><th>name</th><th>note</th></tr>"));
+    assert!(html.contains("<tr><td>a|b</td><td>x &lt; &quot;y&quot;</td></tr>"));
+    assert!(html.ends_with("</tbody>\n</table>\n"));
+
+    let path = dir.join("table_viewer_formats_empty.json");
+    write_table(&path, &header, &[1], std::iter::empty::<Vec<String>>()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[]\n");
+    fs::remove_file(path).unwrap();
+}